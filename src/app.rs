@@ -1,4 +1,31 @@
-use crate::rsync::options::RsyncOptions;
+use crate::history::HistoryEntry;
+use crate::path;
+use crate::profile::{LastRun, Profile, DEFAULT_PROFILE};
+use crate::remote::{self, Completion, RemoteCompleter};
+use crate::rsync::binary::RsyncInfo;
+use crate::rsync::catalog::{self, OptionSpec, ValueType, CATALOG};
+use crate::rsync::command::{self, build_command};
+use crate::rsync::daemon::{self, Module};
+use crate::rsync::exit::RsyncExit;
+use crate::rsync::filter::{FilterRule, RuleKind};
+use crate::rsync::ignore;
+use crate::rsync::itemize::{parse_itemize, parse_itemize_sized, ItemizedChange};
+use crate::rsync::matcher::{self, FilterReport};
+use crate::rsync::options::{OptionValue, RsyncOptions};
+use crate::rsync::parse;
+use crate::rsync::plan::{Plan, PlanNode};
+use crate::rsync::runner::{self, OutputLine, Signal, Stream, Transfer, TransferEvent};
+use crate::rsync::stats::TransferStats;
+use crate::safety::{self, DryRunRecord, PolicySetting, SafetyPolicy};
+use crate::ssh_config::{HostInfo, SshConfig};
+use crate::timestamp;
+use anyhow::Context;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+use std::time::Instant;
+use tokio::sync::oneshot;
 
 /// Active panel in the TUI
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -17,39 +44,1038 @@ pub enum Mode {
     Insert,
 }
 
+/// Lifecycle of the background rsync transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferState {
+    Idle,
+    Running,
+    Paused,
+    Cancelling,
+    Cancelled,
+    Finished(Option<i32>), // rsync exit code
+}
+
+/// Dry-run review screen: the plan and the directory being browsed
+pub struct Review {
+    pub plan: Plan,
+    /// Directory components from the transfer root
+    pub path: Vec<String>,
+    pub selected: usize,
+}
+
+impl Review {
+    pub fn new(plan: Plan) -> Self {
+        Self {
+            plan,
+            path: Vec::new(),
+            selected: 0,
+        }
+    }
+
+    /// Directory currently shown
+    pub fn current(&self) -> &PlanNode {
+        self.plan.node(&self.path).unwrap_or(&self.plan.root)
+    }
+
+    /// Move the selection by `delta` rows
+    pub fn move_selection(&mut self, delta: isize) {
+        let max = self.current().children.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(max);
+    }
+
+    /// Drill into the selected directory
+    pub fn enter(&mut self) {
+        let Some(child) = self.current().children.get(self.selected) else {
+            return;
+        };
+        if child.is_dir && !child.children.is_empty() {
+            self.path.push(child.name.clone());
+            self.selected = 0;
+        }
+    }
+
+    /// Go back to the parent directory, keeping it selected
+    pub fn back(&mut self) {
+        if let Some(name) = self.path.pop() {
+            self.selected = self
+                .current()
+                .children
+                .iter()
+                .position(|c| c.name == name)
+                .unwrap_or(0);
+        }
+    }
+}
+
+/// Pending confirmation for a destructive command
+pub struct Confirmation {
+    pub command: Vec<String>,
+    pub run: RunProfile,
+    /// Risk summary shown in the modal
+    pub lines: Vec<String>,
+    /// Reason the policy refuses to run the command, if it does
+    pub blocked: Option<String>,
+}
+
+/// Row of the Options panel: the rsync options of the catalog, the
+/// settings of the safety policy, then the profile's `vanished_ok`
+#[derive(Debug, Clone, Copy)]
+pub enum OptionRow {
+    Rsync(&'static OptionSpec),
+    Policy(PolicySetting),
+    /// Whether rsync's exit code 24 counts as success
+    VanishedOk,
+}
+
+impl OptionRow {
+    /// Number of rows in the Options panel
+    pub fn count() -> usize {
+        CATALOG.len() + PolicySetting::ALL.len() + 1
+    }
+
+    /// Every row, in panel order
+    pub fn all() -> impl Iterator<Item = OptionRow> {
+        CATALOG
+            .iter()
+            .map(OptionRow::Rsync)
+            .chain(PolicySetting::ALL.into_iter().map(OptionRow::Policy))
+            .chain([OptionRow::VanishedOk])
+    }
+}
+
+/// Value being typed for a valued option in the Options panel
+pub struct OptionEdit {
+    pub id: &'static str,
+    pub text: String,
+}
+
+impl OptionEdit {
+    /// Step through the choices of an Enum option
+    pub fn cycle(&mut self, delta: isize) {
+        let Some(ValueType::Enum(choices)) = catalog::find(self.id).map(|spec| spec.value) else {
+            return;
+        };
+        let len = choices.len() as isize;
+        let next = match choices.iter().position(|c| *c == self.text) {
+            Some(i) => (i as isize + delta).rem_euclid(len),
+            None if delta > 0 => 0,
+            None => len - 1,
+        };
+        self.text = choices[next as usize].to_string();
+    }
+}
+
+/// Rule being added or changed in the filter editor
+pub struct RuleEdit {
+    /// Rule being changed, or None when adding a new one
+    pub index: Option<usize>,
+    pub kind: RuleKind,
+    pub text: String,
+}
+
+/// Filter rule editor popup. The rules themselves live in `RsyncOptions`.
+#[derive(Default)]
+pub struct FilterEditor {
+    pub selected: usize,
+    pub edit: Option<RuleEdit>,
+    /// Result of testing the rules against the source, while the tester
+    /// is shown
+    pub test: Option<Result<FilterReport, String>>,
+    /// First tester row on screen
+    pub test_scroll: usize,
+    /// Result of the last import, shown until the next key
+    pub message: Option<String>,
+}
+
+impl FilterEditor {
+    /// Walk the source and decide every file with the current rules
+    pub fn run_test(&mut self, source: &str, rules: &[FilterRule]) {
+        let source = source.trim();
+        let result = if source.is_empty() {
+            Err("Set a source to test the rules against".to_string())
+        } else if path::is_remote(source) {
+            Err("Only local sources can be tested".to_string())
+        } else {
+            matcher::test_filters(&path::expand_tilde(source), rules).map_err(|e| e.to_string())
+        };
+        if let Ok(report) = &result {
+            self.test_scroll = self.test_scroll.min(report.entries.len().saturating_sub(1));
+        }
+        self.test = Some(result);
+    }
+
+    /// Show or hide the tester
+    pub fn toggle_test(&mut self, source: &str, rules: &[FilterRule]) {
+        if self.test.take().is_none() {
+            self.run_test(source, rules);
+        }
+    }
+
+    pub fn scroll_test(&mut self, delta: isize) {
+        let len = match &self.test {
+            Some(Ok(report)) => report.entries.len(),
+            _ => 0,
+        };
+        self.test_scroll = self
+            .test_scroll
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+
+    pub fn move_selection(&mut self, rules: &[FilterRule], delta: isize) {
+        let last = rules.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Move the selected rule up or down, keeping it selected
+    pub fn move_rule(&mut self, rules: &mut [FilterRule], delta: isize) {
+        if rules.is_empty() {
+            return;
+        }
+        let target = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(rules.len() - 1);
+        rules.swap(self.selected, target);
+        self.selected = target;
+    }
+
+    pub fn delete_rule(&mut self, rules: &mut Vec<FilterRule>) {
+        if self.selected < rules.len() {
+            rules.remove(self.selected);
+            self.move_selection(rules, 0);
+        }
+    }
+
+    /// Start typing a new rule, inserted after the selected one
+    pub fn start_add(&mut self, rules: &[FilterRule]) {
+        let kind = rules
+            .get(self.selected)
+            .map_or(RuleKind::Exclude, |rule| rule.kind);
+        self.edit = Some(RuleEdit {
+            index: None,
+            kind,
+            text: String::new(),
+        });
+    }
+
+    /// Start changing the selected rule
+    pub fn start_edit(&mut self, rules: &[FilterRule]) {
+        if let Some(rule) = rules.get(self.selected) {
+            self.edit = Some(RuleEdit {
+                index: Some(self.selected),
+                kind: rule.kind,
+                text: rule.pattern.clone(),
+            });
+        }
+    }
+
+    /// Store the rule being edited. Empty rules are dropped.
+    pub fn commit(&mut self, rules: &mut Vec<FilterRule>) {
+        let Some(edit) = self.edit.take() else {
+            return;
+        };
+        let pattern = edit.text.trim();
+        if pattern.is_empty() {
+            return;
+        }
+        let rule = FilterRule::new(edit.kind, pattern);
+        match edit.index {
+            Some(index) if index < rules.len() => rules[index] = rule,
+            _ => {
+                let index = if rules.is_empty() {
+                    0
+                } else {
+                    (self.selected + 1).min(rules.len())
+                };
+                rules.insert(index, rule);
+                self.selected = index;
+            }
+        }
+    }
+}
+
+/// Name prompt in the profile manager
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileAction {
+    SaveAs,
+    Rename,
+    Duplicate,
+}
+
+/// Profile manager popup: saved profiles and the selected one
+pub struct ProfileManager {
+    pub profiles: Vec<Profile>,
+    pub selected: usize,
+    /// Name being typed for save-as, rename or duplicate
+    pub input: Option<(ProfileAction, String)>,
+    /// Waiting for 'y' to delete the selected profile
+    pub confirm_delete: bool,
+}
+
+impl ProfileManager {
+    pub fn new(profiles: Vec<Profile>) -> Self {
+        Self {
+            profiles,
+            selected: 0,
+            input: None,
+            confirm_delete: false,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Profile> {
+        self.profiles.get(self.selected)
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.profiles.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Select the profile with the given name, if it is listed
+    pub fn select(&mut self, name: &str) {
+        if let Some(i) = self.profiles.iter().position(|p| p.name == name) {
+            self.selected = i;
+        }
+    }
+}
+
+/// Module picker for an rsync daemon target, filled in by a background
+/// listing of `rsync host::`
+pub struct ModuleBrowser {
+    /// `host::` or `rsync://host/` the modules belong to
+    pub base: String,
+    /// Panel whose path receives the chosen module
+    pub panel: Panel,
+    /// Start of the module name typed so far
+    pub filter: String,
+    /// None while the daemon is being listed
+    pub modules: Option<Result<Vec<Module>, String>>,
+    pub selected: usize,
+    listing: Option<oneshot::Receiver<remote::Listing>>,
+}
+
+impl ModuleBrowser {
+    /// Modules whose name starts with the filter
+    pub fn visible(&self) -> Vec<&Module> {
+        match &self.modules {
+            Some(Ok(modules)) => modules
+                .iter()
+                .filter(|m| m.name.starts_with(&self.filter))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.visible().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    pub fn push_filter(&mut self, c: char) {
+        self.filter.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop_filter(&mut self) {
+        self.filter.pop();
+        self.selected = 0;
+    }
+
+    /// Collect the listing once rsync has answered
+    fn poll(&mut self) {
+        let Some(listing) = self.listing.as_mut() else {
+            return;
+        };
+        let result = match listing.try_recv() {
+            Ok(result) => result,
+            Err(oneshot::error::TryRecvError::Empty) => return,
+            Err(oneshot::error::TryRecvError::Closed) => Err("listing stopped".to_string()),
+        };
+        self.listing = None;
+        self.modules = Some(result.map(|lines| daemon::parse_modules(&lines)));
+    }
+}
+
+/// Which runs the history view lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFilter {
+    All,
+    Succeeded,
+    Failed,
+}
+
+impl StatusFilter {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatusFilter::All => "all",
+            StatusFilter::Succeeded => "succeeded",
+            StatusFilter::Failed => "failed",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            StatusFilter::All => StatusFilter::Succeeded,
+            StatusFilter::Succeeded => StatusFilter::Failed,
+            StatusFilter::Failed => StatusFilter::All,
+        }
+    }
+}
+
+/// History popup: past runs, newest first, narrowed by profile, status
+/// and a search of the profile name and command line
+pub struct HistoryView {
+    pub entries: Vec<HistoryEntry>,
+    pub profile: Option<String>,
+    pub status: StatusFilter,
+    pub search: String,
+    /// Typing into the search instead of using the keys
+    pub searching: bool,
+    pub selected: usize,
+}
+
+impl HistoryView {
+    pub fn new(entries: Vec<HistoryEntry>) -> Self {
+        Self {
+            entries,
+            profile: None,
+            status: StatusFilter::All,
+            search: String::new(),
+            searching: false,
+            selected: 0,
+        }
+    }
+
+    /// Entries passing the filters
+    pub fn visible(&self) -> Vec<&HistoryEntry> {
+        let search = self.search.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|e| self.profile.as_ref().is_none_or(|p| &e.profile == p))
+            .filter(|e| match self.status {
+                StatusFilter::All => true,
+                StatusFilter::Succeeded => e.success,
+                StatusFilter::Failed => !e.success,
+            })
+            .filter(|e| {
+                search.is_empty()
+                    || e.profile.to_lowercase().contains(&search)
+                    || e.command.join(" ").to_lowercase().contains(&search)
+            })
+            .collect()
+    }
+
+    pub fn selected(&self) -> Option<&HistoryEntry> {
+        self.visible().get(self.selected).copied()
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.visible().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Show only the next profile with recorded runs, then all again
+    pub fn cycle_profile(&mut self) {
+        let mut names: Vec<&String> = self.entries.iter().map(|e| &e.profile).collect();
+        names.sort();
+        names.dedup();
+        let next = match &self.profile {
+            None => names.first(),
+            Some(current) => names
+                .iter()
+                .position(|name| *name == current)
+                .and_then(|i| names.get(i + 1)),
+        };
+        self.profile = next.map(|name| name.to_string());
+        self.selected = 0;
+    }
+
+    pub fn cycle_status(&mut self) {
+        self.status = self.status.next();
+        self.selected = 0;
+    }
+
+    pub fn push_search(&mut self, c: char) {
+        self.search.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop_search(&mut self) {
+        self.search.pop();
+        self.selected = 0;
+    }
+}
+
+/// Profile a run belongs to: the loaded one, or the profile of a history
+/// entry being re-run. Its settings decide how the run is checked, judged
+/// and recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunProfile {
+    pub name: String,
+    pub policy: SafetyPolicy,
+    pub vanished_ok: bool,
+    /// Variables rsync runs with, such as `RSYNC_PASSWORD`
+    pub env: Vec<(String, String)>,
+}
+
+impl RunProfile {
+    pub fn from_profile(profile: &Profile) -> Self {
+        Self {
+            name: profile.name.clone(),
+            policy: profile.policy.clone(),
+            vanished_ok: profile.vanished_ok,
+            env: command::command_env(&profile.options),
+        }
+    }
+}
+
+/// A run in progress, recorded in the history when it ends
+struct RunStart {
+    command: Vec<String>,
+    profile: RunProfile,
+    started_at: u64,
+    started: Instant,
+}
+
 /// Application state
 pub struct App {
+    // Saved profile the current setup belongs to
+    pub profile_name: String,
+    pub profile_description: String,
+    pub profile_last_run: Option<LastRun>,
+    pub profiles: Option<ProfileManager>,
+    // Result of the last real run, waiting to be recorded in the named profile
+    pub finished_run: Option<(String, LastRun)>,
+    // Run history popup, and the last run waiting to be added to the history
+    pub history: Option<HistoryView>,
+    pub finished_entry: Option<HistoryEntry>,
+    run_start: Option<RunStart>,
     pub source: String,
     pub destination: String,
     pub options: RsyncOptions,
+    // Options panel cursor (index into the option catalog) and value editor
+    pub option_cursor: usize,
+    pub option_edit: Option<OptionEdit>,
+    pub filter_editor: Option<FilterEditor>,
+    // rsync binary run for transfers and what it supports
+    pub rsync: RsyncInfo,
+    // Host aliases offered while typing a remote destination
+    pub ssh_config: SshConfig,
+    pub host_selected: usize,
+    // Remote directory listings for `host:path` completion
+    pub remote: RemoteCompleter,
+    // Daemon module picker opened by Tab on `host::` or `rsync://host/`
+    pub modules: Option<ModuleBrowser>,
+    // rsync command line being pasted, replacing the setup when applied
+    pub command_input: Option<String>,
+    // Path typed for exporting the command as a shell script, and whether
+    // it exists and the next Enter replaces it
+    pub script_path: Option<String>,
+    pub script_overwrite: bool,
     pub logs: Vec<String>,
     pub active_panel: Panel,
     pub mode: Mode,
-    pub running: bool,
+    pub transfer_state: TransferState,
     pub should_quit: bool,
     // Progress tracking
-    pub progress_output: Vec<String>,
+    pub progress_output: Vec<OutputLine>,
     pub progress_percentage: f64,
     pub transfer_info: String,
+    // Per-file changes from --itemize-changes output
+    pub changes: Vec<ItemizedChange>,
+    pub changes_scroll: usize,
+    // Summary parsed from --stats output
+    pub stats: Option<TransferStats>,
+    // Dry run for review: real command to run and the sized changes seen
+    pub pending_plan: Option<Vec<String>>,
+    pub plan_changes: Vec<(ItemizedChange, u64)>,
+    pub review: Option<Review>,
+    // Confirmation gate for destructive commands
+    pub policy: SafetyPolicy,
+    pub last_dry_run: Option<DryRunRecord>,
+    pub confirm: Option<Confirmation>,
+    // Whether rsync's exit code 24 (vanished source files) counts as success,
+    // and whether it did for the profile of the run that ended
+    pub vanished_ok: bool,
+    pub run_vanished_ok: bool,
+    // Background rsync transfer (process id and event channel)
+    pub transfer: Option<Transfer>,
 }
 
 impl App {
     pub fn new() -> Self {
         Self {
+            profile_name: DEFAULT_PROFILE.to_string(),
+            profile_description: String::new(),
+            profile_last_run: None,
+            profiles: None,
+            finished_run: None,
+            history: None,
+            finished_entry: None,
+            run_start: None,
             source: String::new(),
             destination: String::new(),
             options: RsyncOptions::default(),
+            option_cursor: 0,
+            option_edit: None,
+            filter_editor: None,
+            rsync: RsyncInfo::default(),
+            ssh_config: SshConfig::default(),
+            host_selected: 0,
+            remote: RemoteCompleter::default(),
+            modules: None,
+            command_input: None,
+            script_path: None,
+            script_overwrite: false,
             logs: Vec::new(),
             active_panel: Panel::Source,
             mode: Mode::Normal,
-            running: false,
+            transfer_state: TransferState::Idle,
             should_quit: false,
             progress_output: Vec::new(),
             progress_percentage: 0.0,
             transfer_info: String::new(),
+            changes: Vec::new(),
+            changes_scroll: 0,
+            stats: None,
+            pending_plan: None,
+            plan_changes: Vec::new(),
+            review: None,
+            policy: SafetyPolicy::default(),
+            vanished_ok: false,
+            run_vanished_ok: false,
+            last_dry_run: None,
+            confirm: None,
+            transfer: None,
+        }
+    }
+
+    /// Snapshot the current setup as a profile
+    pub fn to_profile(&self) -> Profile {
+        Profile {
+            name: self.profile_name.clone(),
+            description: self.profile_description.clone(),
+            source: self.source.clone(),
+            destination: self.destination.clone(),
+            options: self.options.clone(),
+            policy: self.policy.clone(),
+            vanished_ok: self.vanished_ok,
+            last_run: self.profile_last_run.clone(),
+            ..Profile::new(&self.profile_name)
+        }
+    }
+
+    /// Replace the current setup with a saved profile
+    pub fn apply_profile(&mut self, profile: &Profile) {
+        self.profile_name = profile.name.clone();
+        self.profile_description = profile.description.clone();
+        self.source = profile.source.clone();
+        self.destination = profile.destination.clone();
+        self.options = profile.options.clone();
+        self.policy = profile.policy.clone();
+        self.vanished_ok = profile.vanished_ok;
+        self.profile_last_run = profile.last_run.clone();
+        self.last_dry_run = None;
+    }
+
+    /// Replace the source, destination and options with those of an rsync
+    /// command line. Arguments the catalog doesn't know are kept and passed
+    /// through to rsync.
+    pub fn apply_command(&mut self, line: &str) -> anyhow::Result<()> {
+        let parsed = parse::parse_command(line)?;
+        self.source = parsed.source;
+        self.destination = parsed.destination;
+        self.options = parsed.options;
+        self.last_dry_run = None;
+
+        self.log("Loaded options from command line".to_string());
+        if !self.options.extra_args.is_empty() {
+            self.log(format!(
+                "Passing through unrecognised arguments: {}",
+                self.options.extra_args.join(" ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Command line for the current setup with `options`, run by the
+    /// configured rsync binary
+    pub fn command(&self, options: &RsyncOptions) -> Vec<String> {
+        let mut args = build_command(&self.source, &self.destination, options);
+        args[0] = self.rsync.program.clone();
+        args
+    }
+
+    /// Flip a Bool option, noting any conflicting options it turned off.
+    /// Options the installed rsync doesn't support can't be turned on.
+    pub fn toggle_option(&mut self, id: &str) {
+        if let Some(spec) = catalog::find(id) {
+            if !self.options.is_enabled(id) {
+                if let Some(reason) = self.rsync.unsupported(spec, None) {
+                    self.log(format!("Cannot turn on {}: {}", spec.flag, reason));
+                    return;
+                }
+            }
+        }
+        for cleared in self.options.toggle(id) {
+            self.log(format!("Turned off {} (conflicts with {})", cleared, id));
+        }
+    }
+
+    /// Row under the cursor in the Options panel
+    pub fn selected_row(&self) -> OptionRow {
+        match CATALOG.get(self.option_cursor) {
+            Some(spec) => OptionRow::Rsync(spec),
+            None => match PolicySetting::ALL.get(self.option_cursor - CATALOG.len()) {
+                Some(setting) => OptionRow::Policy(*setting),
+                None => OptionRow::VanishedOk,
+            },
+        }
+    }
+
+    pub fn move_option_cursor(&mut self, delta: isize) {
+        self.option_cursor = self
+            .option_cursor
+            .saturating_add_signed(delta)
+            .min(OptionRow::count() - 1);
+    }
+
+    /// Toggle the selected flag, or start editing the selected value
+    pub fn activate_option(&mut self) {
+        let spec = match self.selected_row() {
+            OptionRow::Rsync(spec) => spec,
+            OptionRow::Policy(PolicySetting::RequireDryRun) => {
+                self.policy.require_dry_run = !self.policy.require_dry_run;
+                return;
+            }
+            OptionRow::Policy(setting @ PolicySetting::MaxDeletions) => {
+                let text = self
+                    .policy
+                    .max_deletions
+                    .map(|max| max.to_string())
+                    .unwrap_or_default();
+                self.option_edit = Some(OptionEdit {
+                    id: setting.id(),
+                    text,
+                });
+                return;
+            }
+            OptionRow::VanishedOk => {
+                self.vanished_ok = !self.vanished_ok;
+                return;
+            }
+        };
+        if spec.value == ValueType::Bool {
+            self.toggle_option(spec.id);
+            return;
+        }
+        let text = self
+            .options
+            .value(spec.id)
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+        self.option_edit = Some(OptionEdit { id: spec.id, text });
+    }
+
+    /// Return the selected option to its default (unset for valued options)
+    pub fn reset_option(&mut self) {
+        let spec = match self.selected_row() {
+            OptionRow::Rsync(spec) => spec,
+            OptionRow::Policy(PolicySetting::RequireDryRun) => {
+                self.policy.require_dry_run = false;
+                return;
+            }
+            OptionRow::Policy(PolicySetting::MaxDeletions) => {
+                self.policy.max_deletions = None;
+                return;
+            }
+            OptionRow::VanishedOk => {
+                self.vanished_ok = false;
+                return;
+            }
+        };
+        let value = (spec.value == ValueType::Bool).then_some(OptionValue::Bool(spec.default));
+        let _ = self.options.set(spec.id, value);
+    }
+
+    /// Apply the value being edited. An empty value unsets the option; an
+    /// invalid one is reported and stays in the editor.
+    pub fn commit_option_edit(&mut self) {
+        let Some(edit) = self.option_edit.take() else {
+            return;
+        };
+        if edit.id == PolicySetting::MaxDeletions.id() {
+            let text = edit.text.trim();
+            match text.parse() {
+                _ if text.is_empty() => self.policy.max_deletions = None,
+                Ok(max) => self.policy.max_deletions = Some(max),
+                Err(_) => {
+                    self.log("Max deletions needs a whole number".to_string());
+                    self.option_edit = Some(edit);
+                }
+            }
+            return;
+        }
+        let Some(spec) = catalog::find(edit.id) else {
+            return;
+        };
+
+        let text = edit.text.trim();
+        let value = match spec.value {
+            _ if text.is_empty() => None,
+            ValueType::Int => match text.parse() {
+                Ok(n) => Some(OptionValue::Int(n)),
+                Err(_) => {
+                    self.log(format!("{} needs a whole number", spec.flag));
+                    self.option_edit = Some(edit);
+                    return;
+                }
+            },
+            // Lists are edited as comma-separated entries
+            ValueType::List => Some(OptionValue::List(
+                text.split(',')
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect(),
+            )),
+            ValueType::Text | ValueType::Enum(_) | ValueType::Bool => {
+                Some(OptionValue::Text(text.to_string()))
+            }
+        };
+
+        let setting = value.is_some();
+        if let Some(reason) = self.rsync.unsupported(spec, value.as_ref()) {
+            self.log(format!("Warning: {}: {}", spec.flag, reason));
+        }
+        if let Err(e) = self.options.set(spec.id, value) {
+            self.log(format!("{:#}", e));
+            self.option_edit = Some(edit);
+            return;
+        }
+        // SSH settings only apply through the SSH toggle
+        if spec.ssh && setting && !self.options.is_enabled("use_ssh") {
+            self.options.set_enabled("use_ssh", true);
+            self.log("Turned on SSH".to_string());
         }
     }
 
+    /// Add rules translated from the source's `.rsyncignore` and
+    /// `.gitignore` files after the existing rules. Rules already in the
+    /// list are skipped, so importing twice changes nothing.
+    pub fn import_ignore_files(&mut self) {
+        let source = self.source.trim();
+        let result = if source.is_empty() {
+            Err("Set a source to import its ignore files".to_string())
+        } else if path::is_remote(source) {
+            Err("Only local sources can be imported".to_string())
+        } else {
+            ignore::import(&path::expand_tilde(source)).map_err(|e| format!("{:#}", e))
+        };
+
+        let message = match result {
+            Err(message) => message,
+            Ok(imported) if imported.files.is_empty() => {
+                "No .gitignore or .rsyncignore files found".to_string()
+            }
+            Ok(imported) => {
+                let mut added = 0;
+                for rule in imported.rules {
+                    if !self.options.filters.contains(&rule) {
+                        self.options.filters.push(rule);
+                        added += 1;
+                    }
+                }
+                for warning in &imported.warnings {
+                    self.log(format!("Inexact: {}", warning));
+                }
+                let mut message = format!(
+                    "Imported {} rules from {}",
+                    added,
+                    imported.files.join(", ")
+                );
+                if !imported.warnings.is_empty() {
+                    message.push_str(&format!(" ({} inexact, see logs)", imported.warnings.len()));
+                }
+                message
+            }
+        };
+        self.log(message.clone());
+
+        if let Some(editor) = self.filter_editor.as_mut() {
+            editor.message = Some(message);
+            if editor.test.is_some() {
+                editor.run_test(&self.source, &self.options.filters);
+            }
+        }
+    }
+
+    /// Host aliases the destination being typed could complete to, listed
+    /// by the host picker under the Destination panel
+    pub fn host_matches(&self) -> Vec<String> {
+        if self.mode != Mode::Insert || self.active_panel != Panel::Destination {
+            return Vec::new();
+        }
+        let aliases = self.ssh_config.aliases();
+        path::matching_hosts(&self.destination, &aliases)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Highlighted alias in the host picker and what ssh resolves it to
+    pub fn highlighted_host(&self) -> Option<(String, HostInfo)> {
+        let matches = self.host_matches();
+        let alias = matches.get(self.host_selected.min(matches.len().saturating_sub(1)))?;
+        Some((alias.clone(), self.ssh_config.resolve(alias)))
+    }
+
+    pub fn move_host_selection(&mut self, delta: isize) {
+        let last = self.host_matches().len().saturating_sub(1);
+        self.host_selected = self.host_selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Complete the destination to the highlighted alias, keeping any
+    /// `user@` already typed. Returns false when the picker is empty.
+    pub fn accept_host(&mut self) -> bool {
+        let Some((alias, _)) = self.highlighted_host() else {
+            return false;
+        };
+        let user = match self.destination.rfind('@') {
+            Some(at) => &self.destination[..=at],
+            None => "",
+        };
+        self.destination = format!("{}{}:", user, alias);
+        self.host_selected = 0;
+        true
+    }
+
+    /// Complete the path typed in the Source or Destination panel. Remote
+    /// `host:path` completions list the directory over ssh in the background
+    /// and are applied by `poll_remote` once the listing arrives.
+    pub fn complete_active_path(&mut self) {
+        let current = match self.active_panel {
+            Panel::Source => self.source.clone(),
+            Panel::Destination => self.destination.clone(),
+            _ => return,
+        };
+        if let Some((base, module)) = daemon::split_module(&current) {
+            self.open_modules(base.to_string(), module.to_string());
+            return;
+        }
+        let completed = if remote::split_remote(&current).is_some() {
+            let shell = if self.options.is_enabled("use_ssh") {
+                parse::split_remote_shell(&command::remote_shell(&self.options))
+            } else {
+                vec!["ssh".to_string()]
+            };
+            match self.remote.complete(&current, &shell) {
+                Completion::Done(completed) => completed,
+                Completion::Listing => None,
+                Completion::Failed(e) => {
+                    self.log(format!("Cannot list {}: {}", current, e));
+                    None
+                }
+            }
+        } else {
+            path::complete_path(&current, &self.ssh_config.aliases())
+        };
+        if let Some(completed) = completed {
+            self.set_active_path(completed);
+        }
+    }
+
+    /// List the modules of the daemon at `base` in the background and show
+    /// them in the module browser, filtered by the name typed so far
+    fn open_modules(&mut self, base: String, filter: String) {
+        let (tx, rx) = oneshot::channel();
+        let args = daemon::list_command(&self.rsync.program, &base, &self.options);
+        tokio::spawn(async move {
+            let _ = tx.send(remote::list(args, remote::LIST_TIMEOUT).await);
+        });
+        self.modules = Some(ModuleBrowser {
+            base,
+            panel: self.active_panel,
+            filter,
+            modules: None,
+            selected: 0,
+            listing: Some(rx),
+        });
+    }
+
+    /// Put the highlighted module into the path the browser was opened from
+    pub fn choose_module(&mut self) {
+        let Some(browser) = self.modules.as_ref() else {
+            return;
+        };
+        let Some(module) = browser.visible().get(browser.selected).copied() else {
+            return;
+        };
+        let path = format!("{}{}/", browser.base, module.name);
+        let panel = browser.panel;
+        self.modules = None;
+        match panel {
+            Panel::Source => self.source = path,
+            Panel::Destination => self.destination = path,
+            _ => {}
+        }
+    }
+
+    fn set_active_path(&mut self, path: String) {
+        match self.active_panel {
+            Panel::Source => self.source = path,
+            Panel::Destination => self.destination = path,
+            _ => {}
+        }
+    }
+
+    /// Apply a remote completion that finished listing, unless the path has
+    /// been edited since Tab was pressed
+    pub fn poll_remote(&mut self) {
+        if let Some(browser) = self.modules.as_mut() {
+            browser.poll();
+        }
+        let Some((partial, completion)) = self.remote.poll() else {
+            return;
+        };
+        match completion {
+            Completion::Done(Some(completed)) => {
+                if self.source == partial {
+                    self.source = completed;
+                } else if self.destination == partial {
+                    self.destination = completed;
+                }
+            }
+            Completion::Failed(e) => self.log(format!("Cannot list {}: {}", partial, e)),
+            Completion::Done(None) | Completion::Listing => {}
+        }
+    }
+
+    /// Write the command as an executable shell script. An existing file
+    /// is only replaced with `overwrite`.
+    pub fn export_script(&self, path: &str, overwrite: bool) -> anyhow::Result<PathBuf> {
+        let path = PathBuf::from(path::expand_tilde(path));
+        let script = command::format_script(
+            &self.command(&self.options),
+            &self.options,
+            &self.profile_name,
+        );
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(!overwrite)
+            .create(true)
+            .truncate(true)
+            .mode(0o755)
+            .open(&path)
+            .with_context(|| format!("writing {}", path.display()))?;
+        file.write_all(script.as_bytes())
+            .with_context(|| format!("writing {}", path.display()))?;
+        // A replaced file keeps its mode, so make it executable
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("making {} executable", path.display()))?;
+        Ok(path)
+    }
+
     /// Move focus to next panel
     pub fn next_panel(&mut self) {
         self.active_panel = match self.active_panel {
@@ -77,17 +1103,261 @@ impl App {
         self.progress_output.clear();
         self.progress_percentage = 0.0;
         self.transfer_info.clear();
+        self.changes.clear();
+        self.changes_scroll = 0;
+        self.stats = None;
+        self.plan_changes.clear();
+    }
+
+    /// Scroll the change list by `delta` rows, clamped to its length
+    pub fn scroll_changes(&mut self, delta: isize) {
+        let max = self.changes.len().saturating_sub(1);
+        self.changes_scroll = self.changes_scroll.saturating_add_signed(delta).min(max);
     }
 
     /// Add a log message
     pub fn log(&mut self, message: String) {
         self.logs.push(message);
     }
+
+    /// Build the plan from the finished dry run and show the review screen
+    fn open_review(&mut self, command: Vec<String>) {
+        let plan = Plan::new(command.clone(), &self.plan_changes);
+        let totals = plan.totals();
+        self.last_dry_run = Some(DryRunRecord {
+            command,
+            deletions: totals.deleted,
+        });
+        self.log(format!(
+            "Dry run: {} to create, {} to update, {} to delete",
+            totals.created, totals.updated, totals.deleted
+        ));
+        self.review = Some(Review::new(plan));
+    }
+
+    /// Whether a transfer is in progress (including paused/cancelling)
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self.transfer_state,
+            TransferState::Running | TransferState::Paused | TransferState::Cancelling
+        )
+    }
+
+    /// Settings a run of the loaded profile uses
+    pub fn run_profile(&self) -> RunProfile {
+        RunProfile {
+            name: self.profile_name.clone(),
+            policy: self.policy.clone(),
+            vanished_ok: self.vanished_ok,
+            env: command::command_env(&self.options),
+        }
+    }
+
+    /// Track a newly spawned transfer of `command` for `profile`
+    pub fn start_transfer(&mut self, transfer: Transfer, command: Vec<String>, profile: RunProfile) {
+        self.transfer = Some(transfer);
+        self.transfer_state = TransferState::Running;
+        self.run_start = Some(RunStart {
+            command,
+            profile,
+            started_at: timestamp::now(),
+            started: Instant::now(),
+        });
+    }
+
+    /// Turn the run that just ended into a history entry for the main loop
+    /// to store with its output
+    fn finish_history(&mut self, exit_code: Option<i32>) {
+        let Some(start) = self.run_start.take() else {
+            return;
+        };
+        let exit = RsyncExit::from_code(exit_code);
+        self.finished_entry = Some(HistoryEntry {
+            started_at: start.started_at,
+            profile: start.profile.name,
+            dry_run: safety::is_dry_run(&start.command),
+            command: start.command,
+            duration_ms: start.started.elapsed().as_millis() as u64,
+            exit_code,
+            success: exit.is_success(start.profile.vanished_ok),
+            stats: self.stats,
+            bytes: self.stats.map(|stats| stats.transferred_size),
+            log_file: None,
+        });
+    }
+
+    /// Pause a running transfer or resume a paused one
+    pub fn toggle_pause(&mut self) {
+        let state = self.transfer_state;
+        match state {
+            TransferState::Running if self.signal_transfer(Signal::Stop) => {
+                self.transfer_state = TransferState::Paused;
+                self.log("Transfer paused".to_string());
+            }
+            TransferState::Paused if self.signal_transfer(Signal::Continue) => {
+                self.transfer_state = TransferState::Running;
+                self.log("Transfer resumed".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    /// Cancel the transfer: SIGINT first, SIGTERM if already cancelling
+    pub fn cancel_transfer(&mut self) {
+        let state = self.transfer_state;
+        match state {
+            TransferState::Running | TransferState::Paused => {
+                let paused = state == TransferState::Paused;
+                if self.signal_transfer(Signal::Interrupt) {
+                    // A stopped process only handles SIGINT once continued
+                    if paused {
+                        self.signal_transfer(Signal::Continue);
+                    }
+                    self.transfer_state = TransferState::Cancelling;
+                    self.log("Cancelling transfer (SIGINT)".to_string());
+                }
+            }
+            TransferState::Cancelling if self.signal_transfer(Signal::Terminate) => {
+                self.log("Forcing transfer to stop (SIGTERM)".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    /// Send a signal to the rsync process, logging failures
+    fn signal_transfer(&mut self, signal: Signal) -> bool {
+        let Some(pid) = self.transfer.as_ref().and_then(|t| t.pid) else {
+            return false;
+        };
+        match runner::send_signal(pid, signal) {
+            Ok(()) => true,
+            Err(e) => {
+                self.log(format!("Failed to send {:?} to rsync: {}", signal, e));
+                false
+            }
+        }
+    }
+
+    /// Name and `vanished_ok` of the profile the current run belongs to
+    fn running_profile(&self) -> (String, bool) {
+        match &self.run_start {
+            Some(start) => (start.profile.name.clone(), start.profile.vanished_ok),
+            None => (self.profile_name.clone(), self.vanished_ok),
+        }
+    }
+
+    /// Remember how a real run ended so it can be stored in its profile
+    fn record_run(&mut self, exit_code: Option<i32>) {
+        let run = LastRun {
+            finished_at: timestamp::now(),
+            exit_code,
+            stats: self.stats,
+        };
+        let (name, _) = self.running_profile();
+        if name == self.profile_name {
+            self.profile_last_run = Some(run.clone());
+        }
+        self.finished_run = Some((name, run));
+    }
+
+    /// Apply all pending events from the background transfer
+    pub fn poll_transfer(&mut self) {
+        let mut events = Vec::new();
+        if let Some(transfer) = self.transfer.as_mut() {
+            while let Ok(event) = transfer.events.try_recv() {
+                events.push(event);
+            }
+        }
+        for event in events {
+            self.apply_transfer_event(event);
+        }
+    }
+
+    /// Update progress and logs from a single transfer event
+    pub fn apply_transfer_event(&mut self, event: TransferEvent) {
+        match event {
+            TransferEvent::Line(line) => {
+                if line.stream == Stream::Stdout {
+                    if self.pending_plan.is_some() {
+                        if let Some((change, size)) = parse_itemize_sized(&line.text) {
+                            self.changes.push(change.clone());
+                            self.plan_changes.push((change, size));
+                        }
+                    } else if let Some(change) = parse_itemize(&line.text) {
+                        self.changes.push(change);
+                    }
+                    let mut stats = self.stats.unwrap_or_default();
+                    if stats.apply(&line.text) {
+                        self.stats = Some(stats);
+                    }
+                }
+                self.log(line.display());
+                self.progress_output.push(line);
+            }
+            TransferEvent::Progress(percent, info) => {
+                self.progress_percentage = percent;
+                self.transfer_info = info;
+            }
+            TransferEvent::Finished(code) => {
+                let exit = RsyncExit::from_code(code);
+                (_, self.run_vanished_ok) = self.running_profile();
+                if self.pending_plan.is_none() {
+                    self.record_run(code);
+                }
+                self.finish_history(code);
+                if self.transfer_state == TransferState::Cancelling {
+                    self.transfer_state = TransferState::Cancelled;
+                    self.log("Transfer cancelled".to_string());
+                } else if exit.is_success(self.run_vanished_ok) {
+                    self.transfer_state = TransferState::Finished(code);
+                    self.progress_percentage = 100.0;
+                    if exit == RsyncExit::Success {
+                        self.log("Sync completed successfully".to_string());
+                    } else {
+                        self.log(format!("Sync completed: {}", exit.explanation()));
+                    }
+                    if let Some(stats) = self.stats {
+                        self.log(format!("Stats: {}", stats.summary()));
+                    }
+                    if let Some(command) = self.pending_plan.take() {
+                        self.open_review(command);
+                    }
+                } else {
+                    self.transfer_state = TransferState::Finished(code);
+                    self.log(format!("Sync failed: {}. {}", exit.status(), exit.explanation()));
+                    if let Some(suggestion) = exit.suggestion() {
+                        self.log(format!("Next: {}", suggestion));
+                    }
+                }
+                self.pending_plan = None;
+                self.transfer = None;
+            }
+            TransferEvent::Failed(e) => {
+                if self.pending_plan.take().is_none() {
+                    self.record_run(None);
+                }
+                self.finish_history(None);
+                self.transfer_state = TransferState::Finished(None);
+                self.log(format!("Failed to wait for rsync: {}", e));
+                self.transfer = None;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rsync::itemize::ChangeKind;
+    use std::time::Duration;
+
+    fn line(stream: Stream, text: &str) -> TransferEvent {
+        TransferEvent::Line(OutputLine {
+            stream,
+            elapsed: Duration::from_secs(1),
+            text: text.to_string(),
+        })
+    }
 
     #[test]
     fn test_app_new_defaults() {
@@ -98,11 +1368,448 @@ mod tests {
         assert!(app.logs.is_empty());
         assert_eq!(app.active_panel, Panel::Source);
         assert_eq!(app.mode, Mode::Normal);
-        assert!(!app.running);
+        assert_eq!(app.transfer_state, TransferState::Idle);
         assert!(!app.should_quit);
         assert!(app.progress_output.is_empty());
         assert_eq!(app.progress_percentage, 0.0);
         assert!(app.transfer_info.is_empty());
+        assert!(app.transfer.is_none());
+    }
+
+    #[test]
+    fn test_host_picker() {
+        let mut app = App::new();
+        app.ssh_config = SshConfig::parse(
+            "Host web-1 web-2\n  User deploy\nHost nas\n",
+            std::path::Path::new("/nonexistent"),
+        );
+        app.active_panel = Panel::Destination;
+        app.destination = "root@we".to_string();
+        // Only offered while typing
+        assert!(app.host_matches().is_empty());
+
+        app.mode = Mode::Insert;
+        assert_eq!(app.host_matches(), vec!["web-1", "web-2"]);
+        app.move_host_selection(5);
+        let (alias, info) = app.highlighted_host().unwrap();
+        assert_eq!(alias, "web-2");
+        assert_eq!(info.user.as_deref(), Some("deploy"));
+
+        assert!(app.accept_host());
+        assert_eq!(app.destination, "root@web-2:");
+        assert!(app.host_matches().is_empty());
+        assert!(!app.accept_host());
+    }
+
+    #[tokio::test]
+    async fn test_remote_completion_uses_ssh_settings() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let ssh = dir.join("myssh");
+        std::fs::write(
+            &ssh,
+            format!(
+                "#!/bin/sh\necho \"$@\" > {}/args\necho backups/\n",
+                dir.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&ssh, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut app = App::new();
+        app.options
+            .set(
+                "ssh_program",
+                Some(OptionValue::Text(ssh.display().to_string())),
+            )
+            .unwrap();
+        app.options
+            .set("ssh_port", Some(OptionValue::Int(2222)))
+            .unwrap();
+        app.options.set_enabled("use_ssh", true);
+        app.active_panel = Panel::Destination;
+        app.destination = "web:/var/ba".to_string();
+
+        app.complete_active_path();
+        assert_eq!(app.destination, "web:/var/ba");
+        while app.remote.is_listing() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            app.poll_remote();
+        }
+        assert_eq!(app.destination, "web:/var/backups/");
+        let args = std::fs::read_to_string(dir.join("args")).unwrap();
+        assert!(args.starts_with("-p 2222 -o BatchMode=yes"));
+    }
+
+    #[tokio::test]
+    async fn test_module_browser() {
+        let mut app = App::new();
+        app.active_panel = Panel::Destination;
+        app.destination = "nas::ph".to_string();
+        app.complete_active_path();
+
+        let browser = app.modules.as_mut().unwrap();
+        assert_eq!(browser.base, "nas::");
+        assert_eq!(browser.filter, "ph");
+        browser.listing = None;
+        browser.modules = Some(Ok(daemon::parse_modules(&[
+            "backup\tNightly".to_string(),
+            "photos\tPictures".to_string(),
+            "phones".to_string(),
+        ])));
+        assert_eq!(browser.visible().len(), 2);
+        browser.move_selection(1);
+
+        app.choose_module();
+        assert!(app.modules.is_none());
+        assert_eq!(app.destination, "nas::phones/");
+    }
+
+    #[test]
+    fn test_ssh_setting_turns_on_ssh() {
+        let mut app = App::new();
+        app.option_cursor = CATALOG.iter().position(|s| s.id == "ssh_port").unwrap();
+        app.activate_option();
+        app.option_edit.as_mut().unwrap().text = "2222".to_string();
+        app.commit_option_edit();
+
+        assert_eq!(app.options.value("ssh_port"), Some(&OptionValue::Int(2222)));
+        assert!(app.options.is_enabled("use_ssh"));
+    }
+
+    #[test]
+    fn test_apply_command() {
+        let mut app = App::new();
+        app.options.filters = vec![FilterRule::new(RuleKind::Exclude, "*.old")];
+
+        app.apply_command("rsync -az --fuzzy --exclude '*.tmp' ~/docs/ nas:/backup/docs/")
+            .unwrap();
+        assert_eq!(app.source, "~/docs/");
+        assert_eq!(app.destination, "nas:/backup/docs/");
+        assert!(app.options.is_enabled("compress"));
+        assert!(!app.options.is_enabled("verbose"));
+        assert_eq!(
+            app.options.filters,
+            vec![FilterRule::new(RuleKind::Exclude, "*.tmp")]
+        );
+        assert_eq!(app.options.extra_args, ["--fuzzy"]);
+        assert!(app.logs.last().unwrap().contains("--fuzzy"));
+
+        // A command that doesn't parse leaves the setup alone
+        assert!(app.apply_command("rsync 'unterminated").is_err());
+        assert_eq!(app.source, "~/docs/");
+    }
+
+    #[test]
+    fn test_import_ignore_files_skips_existing_rules() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+
+        let mut app = App::new();
+        app.source = format!("{}/", root.display());
+        app.options.filters = vec![FilterRule::new(RuleKind::Exclude, "*.log")];
+        app.filter_editor = Some(FilterEditor::default());
+
+        app.import_ignore_files();
+        assert_eq!(
+            app.options.filters,
+            vec![
+                FilterRule::new(RuleKind::Exclude, "*.log"),
+                FilterRule::new(RuleKind::Exclude, "target/"),
+            ]
+        );
+        assert_eq!(
+            app.filter_editor.as_ref().unwrap().message.as_deref(),
+            Some("Imported 1 rules from .gitignore")
+        );
+
+        app.import_ignore_files();
+        assert_eq!(app.options.filters.len(), 2);
+    }
+
+    #[test]
+    fn test_filter_tester_needs_local_source() {
+        let rules = vec![FilterRule::new(RuleKind::Exclude, "*.tmp")];
+        let mut editor = FilterEditor::default();
+
+        editor.toggle_test("host:/data", &rules);
+        assert_eq!(
+            editor.test.as_ref().unwrap().as_ref().unwrap_err(),
+            "Only local sources can be tested"
+        );
+
+        editor.toggle_test("host:/data", &rules);
+        assert!(editor.test.is_none());
+
+        editor.toggle_test("", &rules);
+        assert!(editor.test.as_ref().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_filter_editor_add_edit_reorder_delete() {
+        let mut rules = Vec::new();
+        let mut editor = FilterEditor::default();
+
+        editor.start_add(&rules);
+        let edit = editor.edit.as_mut().unwrap();
+        assert_eq!(edit.kind, RuleKind::Exclude);
+        edit.text = "*".to_string();
+        editor.commit(&mut rules);
+
+        // New rules go after the selected one
+        editor.move_selection(&rules, -1);
+        editor.start_add(&rules);
+        let edit = editor.edit.as_mut().unwrap();
+        edit.kind = RuleKind::Include;
+        edit.text = " photos/ ".to_string();
+        editor.commit(&mut rules);
+        assert_eq!(
+            rules,
+            vec![
+                FilterRule::new(RuleKind::Exclude, "*"),
+                FilterRule::new(RuleKind::Include, "photos/"),
+            ]
+        );
+        assert_eq!(editor.selected, 1);
+
+        // The include must come first to take effect
+        editor.move_rule(&mut rules, -1);
+        assert_eq!(editor.selected, 0);
+        assert_eq!(rules[0].pattern, "photos/");
+        editor.move_rule(&mut rules, -1);
+        assert_eq!(rules[0].pattern, "photos/");
+
+        editor.start_edit(&rules);
+        assert_eq!(editor.edit.as_ref().unwrap().text, "photos/");
+        editor.edit.as_mut().unwrap().text = "photos/**".to_string();
+        editor.commit(&mut rules);
+        assert_eq!(rules[0].pattern, "photos/**");
+        assert_eq!(rules.len(), 2);
+
+        // Empty rules are not added
+        editor.start_add(&rules);
+        editor.commit(&mut rules);
+        assert_eq!(rules.len(), 2);
+
+        editor.move_selection(&rules, 5);
+        editor.delete_rule(&mut rules);
+        assert_eq!(rules, vec![FilterRule::new(RuleKind::Include, "photos/**")]);
+        assert_eq!(editor.selected, 0);
+        editor.delete_rule(&mut rules);
+        editor.delete_rule(&mut rules);
+        assert!(rules.is_empty());
+    }
+
+    fn select_option(app: &mut App, id: &str) {
+        app.option_cursor = CATALOG.iter().position(|spec| spec.id == id).unwrap();
+    }
+
+    #[test]
+    fn test_option_cursor_clamps() {
+        let mut app = App::new();
+        app.move_option_cursor(-1);
+        assert_eq!(app.option_cursor, 0);
+        app.move_option_cursor(1000);
+        assert_eq!(app.option_cursor, OptionRow::count() - 1);
+        assert!(matches!(app.selected_row(), OptionRow::VanishedOk));
+        app.move_option_cursor(-1);
+        assert!(matches!(
+            app.selected_row(),
+            OptionRow::Policy(PolicySetting::MaxDeletions)
+        ));
+        app.move_option_cursor(-(PolicySetting::ALL.len() as isize));
+        let last = CATALOG.last().unwrap().id;
+        assert!(matches!(app.selected_row(), OptionRow::Rsync(spec) if spec.id == last));
+    }
+
+    #[test]
+    fn test_toggle_vanished_ok() {
+        let mut app = App::new();
+        app.option_cursor = OptionRow::count() - 1;
+        app.activate_option();
+        assert!(app.vanished_ok);
+        assert!(app.to_profile().vanished_ok);
+        app.reset_option();
+        assert!(!app.vanished_ok);
+    }
+
+    #[test]
+    fn test_edit_safety_policy() {
+        let mut app = App::new();
+        app.option_cursor = CATALOG.len();
+        app.activate_option();
+        assert!(app.policy.require_dry_run);
+        app.reset_option();
+        assert!(!app.policy.require_dry_run);
+
+        app.move_option_cursor(1);
+        app.activate_option();
+        app.option_edit.as_mut().unwrap().text = "lots".to_string();
+        app.commit_option_edit();
+        assert!(app.option_edit.is_some());
+        assert_eq!(app.policy.max_deletions, None);
+
+        app.option_edit.as_mut().unwrap().text = " 50 ".to_string();
+        app.commit_option_edit();
+        assert!(app.option_edit.is_none());
+        assert_eq!(app.policy.max_deletions, Some(50));
+        assert_eq!(app.to_profile().policy.max_deletions, Some(50));
+
+        app.activate_option();
+        assert_eq!(app.option_edit.as_ref().unwrap().text, "50");
+        app.option_edit.as_mut().unwrap().text.clear();
+        app.commit_option_edit();
+        assert_eq!(app.policy.max_deletions, None);
+    }
+
+    #[test]
+    fn test_activate_and_reset_flag() {
+        let mut app = App::new();
+        select_option(&mut app, "compress");
+        app.activate_option();
+        assert!(app.options.is_enabled("compress"));
+        assert!(app.option_edit.is_none());
+
+        app.reset_option();
+        assert!(!app.options.is_enabled("compress"));
+    }
+
+    #[test]
+    fn test_unsupported_options_follow_the_installed_rsync() {
+        let mut app = App::new();
+        app.rsync = crate::rsync::binary::parse_version(
+            "/opt/rsync-2.6.9/bin/rsync",
+            "rsync  version 2.6.9  protocol version 29\n",
+        );
+
+        app.toggle_option("progress_total");
+        assert!(!app.options.is_enabled("progress_total"));
+        assert!(app.logs[0].starts_with("Cannot turn on --info=progress2: needs rsync 3.1.0"));
+
+        // Already-set values are kept but warned about
+        select_option(&mut app, "checksum_choice");
+        app.activate_option();
+        app.option_edit.as_mut().unwrap().text = "md5".to_string();
+        app.commit_option_edit();
+        assert!(app.options.value("checksum_choice").is_some());
+        assert!(app.logs[1].starts_with("Warning: --checksum-choice:"));
+
+        assert_eq!(app.command(&app.options)[0], "/opt/rsync-2.6.9/bin/rsync");
+    }
+
+    #[test]
+    fn test_edit_int_option() {
+        let mut app = App::new();
+        select_option(&mut app, "max_delete");
+        app.activate_option();
+        app.option_edit.as_mut().unwrap().text = "many".to_string();
+        app.commit_option_edit();
+        // Invalid numbers stay in the editor
+        assert!(app.option_edit.is_some());
+        assert!(app.options.value("max_delete").is_none());
+
+        app.option_edit.as_mut().unwrap().text = " 100 ".to_string();
+        app.commit_option_edit();
+        assert!(app.option_edit.is_none());
+        assert_eq!(
+            app.options.value("max_delete"),
+            Some(&OptionValue::Int(100))
+        );
+
+        // The editor starts from the current value; clearing it unsets
+        app.activate_option();
+        assert_eq!(app.option_edit.as_ref().unwrap().text, "100");
+        app.option_edit.as_mut().unwrap().text.clear();
+        app.commit_option_edit();
+        assert!(app.options.value("max_delete").is_none());
+    }
+
+    #[test]
+    fn test_edit_enum_option() {
+        let mut app = App::new();
+        select_option(&mut app, "checksum_choice");
+        app.activate_option();
+
+        let edit = app.option_edit.as_mut().unwrap();
+        edit.cycle(1);
+        assert_eq!(edit.text, "auto");
+        edit.cycle(-1);
+        assert_eq!(edit.text, "none");
+        edit.cycle(1);
+        edit.cycle(1);
+        assert_eq!(edit.text, "xxh128");
+        app.commit_option_edit();
+        assert_eq!(
+            app.options.value("checksum_choice"),
+            Some(&OptionValue::Text("xxh128".to_string()))
+        );
+
+        // Typed values outside the choices are rejected
+        app.activate_option();
+        app.option_edit.as_mut().unwrap().text = "sha1".to_string();
+        app.commit_option_edit();
+        assert!(app.option_edit.is_some());
+    }
+
+    #[test]
+    fn test_edit_list_option() {
+        let mut app = App::new();
+        select_option(&mut app, "link_dest");
+        app.activate_option();
+        app.option_edit.as_mut().unwrap().text = "/backup/1, /backup/2,,".to_string();
+        app.commit_option_edit();
+        assert_eq!(app.options.list("link_dest"), ["/backup/1", "/backup/2"]);
+    }
+
+    #[test]
+    fn test_export_script_refuses_to_overwrite() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let script = dir.join("sync.sh");
+        fs::write(&script, "keep me\n").unwrap();
+
+        let mut app = App::new();
+        app.source = "/src".to_string();
+        app.destination = "/dest".to_string();
+        let path = script.display().to_string();
+        assert!(app.export_script(&path, false).is_err());
+        assert_eq!(fs::read_to_string(&script).unwrap(), "keep me\n");
+
+        app.export_script(&path, true).unwrap();
+        assert!(fs::read_to_string(&script).unwrap().starts_with("#!/bin/sh\n"));
+        let mode = fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        let fresh = dir.join("new.sh");
+        app.export_script(&fresh.display().to_string(), false).unwrap();
+        assert!(fresh.is_file());
+    }
+
+    #[test]
+    fn test_profile_roundtrip() {
+        let mut app = App::new();
+        assert_eq!(app.profile_name, "default");
+        app.source = "/src".to_string();
+        app.destination = "nas:/dest".to_string();
+        app.options.set_enabled("compress", true);
+        app.policy.require_dry_run = true;
+
+        let mut profile = app.to_profile();
+        assert_eq!(profile.source, "/src");
+        assert!(profile.options.is_enabled("compress"));
+        assert!(profile.policy.require_dry_run);
+
+        profile.name = "nas".to_string();
+        let mut other = App::new();
+        other.apply_profile(&profile);
+        assert_eq!(other.profile_name, "nas");
+        assert_eq!(other.destination, "nas:/dest");
+        assert_eq!(other.options, app.options);
     }
 
     #[test]
@@ -152,4 +1859,396 @@ mod tests {
         assert_eq!(app.logs.len(), 2);
         assert_eq!(app.logs[1], "Second message");
     }
+
+    #[test]
+    fn test_transfer_output_and_progress() {
+        let mut app = App::new();
+        app.transfer_state = TransferState::Running;
+
+        app.apply_transfer_event(TransferEvent::Progress(
+            42.0,
+            "1.00MB/s 0:00:10".to_string(),
+        ));
+        app.apply_transfer_event(line(Stream::Stdout, "file.txt"));
+        app.apply_transfer_event(line(Stream::Stderr, "permission denied"));
+
+        assert_eq!(app.progress_percentage, 42.0);
+        assert_eq!(app.transfer_info, "1.00MB/s 0:00:10");
+        assert_eq!(app.progress_output.len(), 2);
+        assert_eq!(app.progress_output[1].stream, Stream::Stderr);
+        assert_eq!(
+            app.logs,
+            vec![
+                "[00:01.000] file.txt",
+                "[00:01.000] [ERR] permission denied"
+            ]
+        );
+        assert!(app.is_running());
+    }
+
+    #[test]
+    fn test_itemized_lines_collected_as_changes() {
+        let mut app = App::new();
+
+        app.apply_transfer_event(line(Stream::Stdout, "sending incremental file list"));
+        app.apply_transfer_event(line(Stream::Stdout, ">f+++++++++ new.txt"));
+        app.apply_transfer_event(line(Stream::Stdout, "*deleting   old.txt"));
+        app.apply_transfer_event(line(Stream::Stderr, ">f+++++++++ not-a-change"));
+
+        assert_eq!(app.changes.len(), 2);
+        assert_eq!(app.changes[0].kind, ChangeKind::Created);
+        assert_eq!(app.changes[1].kind, ChangeKind::Deleted);
+        assert_eq!(app.progress_output.len(), 4);
+
+        app.clear_progress();
+        assert!(app.changes.is_empty());
+    }
+
+    #[test]
+    fn test_stats_trailer_summarised_and_recorded() {
+        let mut app = App::new();
+        app.transfer_state = TransferState::Running;
+        for text in [
+            "Number of files: 3 (reg: 2, dir: 1)",
+            "Number of created files: 2",
+            "Number of regular files transferred: 2",
+            "Total transferred file size: 2,048 bytes",
+            "total size is 4,096  speedup is 1.90",
+        ] {
+            app.apply_transfer_event(line(Stream::Stdout, text));
+        }
+        // The same text on stderr is not the trailer
+        app.apply_transfer_event(line(Stream::Stderr, "Number of files: 99"));
+        app.apply_transfer_event(TransferEvent::Finished(Some(0)));
+
+        let stats = app.stats.unwrap();
+        assert_eq!((stats.files, stats.created, stats.transferred), (3, Some(2), 2));
+        assert_eq!(stats.total_size, 4096);
+        assert!(app.logs.last().unwrap().starts_with("Stats: 3 files, 2 created"));
+        assert_eq!(app.finished_run.take().unwrap().1.stats, Some(stats));
+
+        app.clear_progress();
+        assert!(app.stats.is_none());
+    }
+
+    #[test]
+    fn test_scroll_changes_clamps() {
+        let mut app = App::new();
+        for i in 0..5 {
+            app.apply_transfer_event(line(Stream::Stdout, &format!(">f+++++++++ file{}", i)));
+        }
+
+        app.scroll_changes(-1);
+        assert_eq!(app.changes_scroll, 0);
+        app.scroll_changes(3);
+        assert_eq!(app.changes_scroll, 3);
+        app.scroll_changes(10);
+        assert_eq!(app.changes_scroll, 4);
+    }
+
+    #[test]
+    fn test_transfer_finished_success() {
+        let mut app = App::new();
+        app.transfer_state = TransferState::Running;
+
+        app.apply_transfer_event(TransferEvent::Finished(Some(0)));
+
+        assert_eq!(app.transfer_state, TransferState::Finished(Some(0)));
+        assert_eq!(app.progress_percentage, 100.0);
+        assert_eq!(app.logs.last().unwrap(), "Sync completed successfully");
+    }
+
+    #[test]
+    fn test_dry_run_opens_review() {
+        let mut app = App::new();
+        app.pending_plan = Some(vec!["rsync".to_string(), "/src".to_string()]);
+        app.transfer_state = TransferState::Running;
+
+        app.apply_transfer_event(line(Stream::Stdout, "cd+++++++++ 0 photos/"));
+        app.apply_transfer_event(line(Stream::Stdout, ">f+++++++++ 2,000 photos/a.jpg"));
+        app.apply_transfer_event(line(Stream::Stdout, "*deleting   0 old.txt"));
+        app.apply_transfer_event(TransferEvent::Finished(Some(0)));
+
+        let review = app.review.as_ref().unwrap();
+        assert_eq!(review.plan.command, vec!["rsync", "/src"]);
+        assert_eq!(review.plan.totals().created, 2);
+        assert_eq!(review.plan.totals().deleted, 1);
+        assert_eq!(review.plan.totals().bytes, 2_000);
+        assert_eq!(app.last_dry_run.as_ref().unwrap().deletions, 1);
+        assert_eq!(app.changes.len(), 3);
+        assert!(app.pending_plan.is_none());
+        // A dry run is not a run of the profile
+        assert!(app.finished_run.is_none());
+    }
+
+    #[test]
+    fn test_failed_dry_run_has_no_review() {
+        let mut app = App::new();
+        app.pending_plan = Some(vec!["rsync".to_string()]);
+        app.transfer_state = TransferState::Running;
+
+        app.apply_transfer_event(TransferEvent::Finished(Some(23)));
+
+        assert!(app.review.is_none());
+        assert!(app.pending_plan.is_none());
+    }
+
+    #[test]
+    fn test_review_navigation() {
+        let changes: Vec<_> = [
+            ">f+++++++++ 1 a.txt",
+            ">f+++++++++ 1 docs/x/b.txt",
+            ">f+++++++++ 1 docs/c.txt",
+        ]
+        .iter()
+        .filter_map(|l| parse_itemize_sized(l))
+        .collect();
+        let mut review = Review::new(Plan::new(vec![], &changes));
+
+        // Root: [docs/, a.txt]; files can't be entered
+        review.move_selection(1);
+        review.enter();
+        assert!(review.path.is_empty());
+
+        review.move_selection(-1);
+        review.enter();
+        assert_eq!(review.path, vec!["docs"]);
+        assert_eq!(review.current().children.len(), 2);
+
+        review.enter();
+        assert_eq!(review.path, vec!["docs", "x"]);
+
+        review.back();
+        review.back();
+        assert!(review.path.is_empty());
+        assert_eq!(review.selected, 0);
+    }
+
+    #[test]
+    fn test_transfer_finished_failure() {
+        let mut app = App::new();
+        app.transfer_state = TransferState::Running;
+
+        app.apply_transfer_event(TransferEvent::Finished(Some(23)));
+
+        assert_eq!(app.transfer_state, TransferState::Finished(Some(23)));
+        assert_eq!(app.progress_percentage, 0.0);
+        assert_eq!(
+            app.logs[app.logs.len() - 2],
+            "Sync failed: Partial transfer (exit 23). \
+             Some files or attributes were not transferred because of errors."
+        );
+        assert!(app.logs.last().unwrap().starts_with("Next: "));
+    }
+
+    #[test]
+    fn test_vanished_files_accepted_per_profile() {
+        let mut app = App::new();
+        app.transfer_state = TransferState::Running;
+        app.apply_transfer_event(TransferEvent::Finished(Some(24)));
+        assert!(app.logs[0].starts_with("Sync failed: Source files vanished (exit 24)"));
+
+        let mut profile = app.to_profile();
+        profile.vanished_ok = true;
+        app.apply_profile(&profile);
+        app.logs.clear();
+        app.transfer_state = TransferState::Running;
+        app.apply_transfer_event(TransferEvent::Finished(Some(24)));
+        assert_eq!(app.transfer_state, TransferState::Finished(Some(24)));
+        assert_eq!(app.progress_percentage, 100.0);
+        assert!(app.logs[0].starts_with("Sync completed: "));
+        assert!(app.to_profile().vanished_ok);
+    }
+
+    #[test]
+    fn test_finished_run_recorded_for_profile() {
+        let mut app = App::new();
+        app.transfer_state = TransferState::Running;
+
+        app.apply_transfer_event(TransferEvent::Finished(Some(23)));
+
+        let (name, run) = app.finished_run.take().unwrap();
+        assert_eq!(name, DEFAULT_PROFILE);
+        assert_eq!(run.exit_code, Some(23));
+        assert!(run.finished_at > 0);
+        assert_eq!(app.to_profile().last_run, Some(run));
+    }
+
+    #[test]
+    fn test_profile_manager_selection() {
+        let profiles = ["laptop", "nas", "offsite"]
+            .iter()
+            .map(|name| Profile::new(name))
+            .collect();
+        let mut manager = ProfileManager::new(profiles);
+        assert_eq!(manager.selected().unwrap().name, "laptop");
+
+        manager.move_selection(5);
+        assert_eq!(manager.selected().unwrap().name, "offsite");
+        manager.move_selection(-1);
+        assert_eq!(manager.selected().unwrap().name, "nas");
+        manager.move_selection(-10);
+        assert_eq!(manager.selected, 0);
+
+        manager.select("offsite");
+        assert_eq!(manager.selected, 2);
+        manager.select("missing");
+        assert_eq!(manager.selected, 2);
+
+        let mut empty = ProfileManager::new(Vec::new());
+        empty.move_selection(1);
+        assert!(empty.selected().is_none());
+    }
+
+    #[test]
+    fn test_poll_transfer_drains_channel() {
+        let mut app = App::new();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        app.start_transfer(
+            Transfer {
+                pid: None,
+                events: rx,
+            },
+            vec!["rsync".to_string(), "-a".to_string()],
+            app.run_profile(),
+        );
+
+        tx.send(line(Stream::Stdout, "a")).unwrap();
+        tx.send(TransferEvent::Finished(Some(0))).unwrap();
+        app.poll_transfer();
+
+        assert!(!app.is_running());
+        assert!(app.transfer.is_none());
+        assert_eq!(app.progress_output[0].text, "a");
+
+        let entry = app.finished_entry.take().unwrap();
+        assert_eq!(entry.command, ["rsync", "-a"]);
+        assert_eq!(entry.profile, DEFAULT_PROFILE);
+        assert!(entry.success && !entry.dry_run);
+    }
+
+    #[test]
+    fn test_rerun_recorded_under_its_own_profile() {
+        let mut app = App::new();
+        let mut offsite = Profile::new("offsite");
+        offsite.vanished_ok = true;
+        offsite.policy.require_dry_run = true;
+        let run = RunProfile::from_profile(&offsite);
+        assert!(run.policy.require_dry_run);
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        app.start_transfer(
+            Transfer {
+                pid: None,
+                events: rx,
+            },
+            vec!["rsync".to_string(), "-a".to_string()],
+            run,
+        );
+        tx.send(TransferEvent::Finished(Some(24))).unwrap();
+        app.poll_transfer();
+
+        // Judged with offsite's vanished_ok, which the loaded profile lacks
+        assert!(!app.vanished_ok);
+        assert_eq!(app.transfer_state, TransferState::Finished(Some(24)));
+        let entry = app.finished_entry.take().unwrap();
+        assert_eq!(entry.profile, "offsite");
+        assert!(entry.success);
+        let (name, last_run) = app.finished_run.take().unwrap();
+        assert_eq!(name, "offsite");
+        assert_eq!(last_run.exit_code, Some(24));
+        // The loaded profile's last run is left alone
+        assert_eq!(app.profile_last_run, None);
+    }
+
+    #[test]
+    fn test_history_view_filters() {
+        let entry = |profile: &str, success: bool, command: &str| HistoryEntry {
+            started_at: 0,
+            profile: profile.to_string(),
+            command: command.split(' ').map(String::from).collect(),
+            dry_run: false,
+            duration_ms: 0,
+            exit_code: Some(if success { 0 } else { 23 }),
+            success,
+            stats: None,
+            bytes: None,
+            log_file: None,
+        };
+        let mut view = HistoryView::new(vec![
+            entry("offsite", false, "rsync -a /home/ offsite:/"),
+            entry("nas", true, "rsync -a /photos/ nas::photos/"),
+            entry("offsite", true, "rsync -a /home/ offsite:/"),
+        ]);
+        assert_eq!(view.visible().len(), 3);
+
+        view.cycle_profile();
+        assert_eq!(view.profile.as_deref(), Some("nas"));
+        view.cycle_profile();
+        assert_eq!(view.profile.as_deref(), Some("offsite"));
+        assert_eq!(view.visible().len(), 2);
+
+        view.cycle_status();
+        assert_eq!(view.status, StatusFilter::Succeeded);
+        assert_eq!(view.visible().len(), 1);
+        view.cycle_status();
+        assert_eq!(view.selected().unwrap().exit_code, Some(23));
+
+        view.cycle_profile();
+        assert!(view.profile.is_none());
+        view.cycle_status();
+        for c in "PHOTOS".chars() {
+            view.push_search(c);
+        }
+        assert_eq!(view.visible().len(), 1);
+        view.move_selection(5);
+        assert_eq!(view.selected, 0);
+        view.pop_search();
+        assert_eq!(view.search, "PHOTO");
+    }
+
+    #[test]
+    fn test_pause_resume_and_cancel() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let (_tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut app = App::new();
+        app.start_transfer(
+            Transfer {
+                pid: Some(child.id()),
+                events: rx,
+            },
+            vec!["sleep".to_string()],
+            app.run_profile(),
+        );
+
+        app.toggle_pause();
+        assert_eq!(app.transfer_state, TransferState::Paused);
+        app.toggle_pause();
+        assert_eq!(app.transfer_state, TransferState::Running);
+        app.toggle_pause();
+        app.cancel_transfer();
+        assert_eq!(app.transfer_state, TransferState::Cancelling);
+        assert!(app.is_running());
+
+        let status = child.wait().unwrap();
+        assert!(!status.success());
+
+        app.apply_transfer_event(TransferEvent::Finished(status.code()));
+        assert_eq!(app.transfer_state, TransferState::Cancelled);
+        assert_eq!(app.logs.last().unwrap(), "Transfer cancelled");
+    }
+
+    #[test]
+    fn test_pause_without_transfer_is_noop() {
+        let mut app = App::new();
+
+        app.toggle_pause();
+        app.cancel_transfer();
+
+        assert_eq!(app.transfer_state, TransferState::Idle);
+        assert!(app.logs.is_empty());
+    }
 }
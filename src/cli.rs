@@ -0,0 +1,147 @@
+/// Usage text printed for `--help` and argument errors
+pub const USAGE: &str = "\
+Usage: rsync_tui [OPTIONS]
+
+Options:
+  -p, --profile <NAME>  Load a saved profile instead of 'default'
+  -s, --source <PATH>   Override the source path
+  -d, --dest <PATH>     Override the destination path
+  -c, --from-command <CMD>
+                        Take source, destination and options from an rsync
+                        command line (quoted as one argument)
+      --rsync <PATH>    rsync binary to run (default: $RSYNC_TUI_RSYNC, then rsync on PATH)
+  -n, --dry-run         Start with a dry run (review screen, or rsync -n when headless)
+      --headless        Run the sync without the TUI and exit with rsync's exit code;
+                        a profile's safety policy is checked against a dry run
+                        made first
+      --json            With --headless, print a JSON result (exit status and
+                        --stats summary) on stdout; rsync's output goes to stderr
+  -h, --help            Print this help
+  -V, --version         Print the version
+";
+
+/// Command-line arguments
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Cli {
+    pub profile: Option<String>,
+    pub source: Option<String>,
+    pub destination: Option<String>,
+    pub from_command: Option<String>,
+    pub rsync: Option<String>,
+    pub dry_run: bool,
+    pub headless: bool,
+    pub json: bool,
+    pub help: bool,
+    pub version: bool,
+}
+
+impl Cli {
+    /// Parse arguments, excluding the program name
+    pub fn parse<I>(args: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            // Accept both `--flag value` and `--flag=value`
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let mut value = |name: &str| -> anyhow::Result<String> {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| anyhow::anyhow!("{} requires a value", name))
+            };
+
+            match flag.as_str() {
+                "-p" | "--profile" => cli.profile = Some(value("--profile")?),
+                "-s" | "--source" => cli.source = Some(value("--source")?),
+                "-d" | "--dest" | "--destination" => cli.destination = Some(value("--dest")?),
+                "-c" | "--from-command" => cli.from_command = Some(value("--from-command")?),
+                "--rsync" => cli.rsync = Some(value("--rsync")?),
+                "-n" | "--dry-run" => cli.dry_run = true,
+                "--headless" => cli.headless = true,
+                "--json" => cli.json = true,
+                "-h" | "--help" => cli.help = true,
+                "-V" | "--version" => cli.version = true,
+                _ => anyhow::bail!("unknown argument '{}'", arg),
+            }
+        }
+
+        if cli.json && !cli.headless {
+            anyhow::bail!("--json needs --headless");
+        }
+        Ok(cli)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Cli> {
+        Cli::parse(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_no_arguments() {
+        assert_eq!(parse(&[]).unwrap(), Cli::default());
+    }
+
+    #[test]
+    fn test_all_flags() {
+        let cli = parse(&[
+            "--profile",
+            "nas-backup",
+            "-s",
+            "/home/user/",
+            "--dest=nas:/backup",
+            "--dry-run",
+            "--headless",
+            "--json",
+            "--rsync",
+            "/opt/homebrew/bin/rsync",
+        ])
+        .unwrap();
+        assert_eq!(cli.profile.as_deref(), Some("nas-backup"));
+        assert_eq!(cli.source.as_deref(), Some("/home/user/"));
+        assert_eq!(cli.destination.as_deref(), Some("nas:/backup"));
+        assert!(cli.dry_run);
+        assert!(cli.headless);
+        assert!(cli.json);
+        assert_eq!(cli.rsync.as_deref(), Some("/opt/homebrew/bin/rsync"));
+    }
+
+    #[test]
+    fn test_value_containing_equals() {
+        let cli = parse(&["--source", "/data/a=b", "-d", "x=y"]).unwrap();
+        assert_eq!(cli.source.as_deref(), Some("/data/a=b"));
+        assert_eq!(cli.destination.as_deref(), Some("x=y"));
+    }
+
+    #[test]
+    fn test_from_command() {
+        let cli = parse(&["--from-command=rsync -a --max-delete=5 a/ b/"]).unwrap();
+        assert_eq!(
+            cli.from_command.as_deref(),
+            Some("rsync -a --max-delete=5 a/ b/")
+        );
+        let cli = parse(&["-c", "rsync -a a/ b/", "-d", "c/"]).unwrap();
+        assert_eq!(cli.from_command.as_deref(), Some("rsync -a a/ b/"));
+        assert_eq!(cli.destination.as_deref(), Some("c/"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse(&["--profile"]).is_err());
+        assert!(parse(&["--bogus"]).is_err());
+        assert!(parse(&["/some/path"]).is_err());
+        assert!(parse(&["--json"]).is_err());
+    }
+}
@@ -0,0 +1,367 @@
+use std::io::{IsTerminal, Write};
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::app::App;
+use crate::history::{self, HistoryEntry};
+use crate::profile::{self, LastRun};
+use crate::rsync::command::{command_env, format_command};
+use crate::rsync::exit::RsyncExit;
+use crate::rsync::itemize::parse_itemize_sized;
+use crate::rsync::plan::{Plan, PLAN_OUT_FORMAT};
+use crate::rsync::runner::{self, Stream, TransferEvent};
+use crate::rsync::stats::TransferStats;
+use crate::safety::{self, DryRunRecord, Verdict};
+use crate::timestamp;
+
+/// Exit code used when rsync could not be run or was killed by a signal
+const FAILURE: i32 = 1;
+
+/// Result printed by `--headless --json`
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub profile: String,
+    pub command: Vec<String>,
+    pub dry_run: bool,
+    /// rsync's exit code, or null if it was killed
+    pub exit_code: Option<i32>,
+    pub status: String,
+    pub success: bool,
+    pub stats: Option<TransferStats>,
+}
+
+/// Build the command a headless run executes for the current setup. JSON
+/// output always asks for `--stats` so the report has a summary.
+pub fn prepare(app: &App, dry_run: bool, json: bool) -> anyhow::Result<Vec<String>> {
+    if app.source.is_empty() || app.destination.is_empty() {
+        anyhow::bail!("source and destination must be set (use --source/--dest or a profile)");
+    }
+
+    let mut opts = app.options.clone();
+    opts.set_enabled("progress", true);
+    if dry_run {
+        opts.set_enabled("dry_run", true);
+    }
+    if json {
+        opts.set_enabled("stats", true);
+    }
+    Ok(app.command(&opts))
+}
+
+/// Whether the policy needs a dry run of `command` before it may run: the
+/// deletions it predicts are what `require_dry_run` and `max_deletions` check
+fn needs_dry_run(app: &App, command: &[String]) -> bool {
+    safety::is_destructive(command) && !app.policy.rules().is_empty()
+}
+
+/// There is nobody to confirm a --delete run, so only the policy applies,
+/// with `deletions` from the dry run made for it
+fn check_policy(app: &App, command: &[String], deletions: Option<usize>) -> anyhow::Result<()> {
+    let dry_run = deletions.map(|deletions| DryRunRecord {
+        command: command.to_vec(),
+        deletions,
+    });
+    match safety::check(command, &app.policy, dry_run.as_ref()) {
+        Verdict::Block(reason) => anyhow::bail!("{}", reason),
+        Verdict::Proceed | Verdict::Confirm => Ok(()),
+    }
+}
+
+/// Dry-run `command` the way the review screen does and count the
+/// deletions it predicts. rsync's messages go to stderr.
+fn count_deletions(
+    runtime: &tokio::runtime::Runtime,
+    app: &App,
+    command: &[String],
+) -> anyhow::Result<usize> {
+    let mut args = command.to_vec();
+    args.splice(1..1, ["-n".to_string(), PLAN_OUT_FORMAT.to_string()]);
+    let mut transfer = runner::spawn(&args, &command_env(&app.options))
+        .map_err(|e| anyhow::anyhow!("failed to execute rsync: {}", e))?;
+
+    let mut changes = Vec::new();
+    let code = loop {
+        let Some(event) = runtime.block_on(transfer.events.recv()) else {
+            break None;
+        };
+        match event {
+            TransferEvent::Line(line) => match line.stream {
+                Stream::Stdout => changes.extend(parse_itemize_sized(&line.text)),
+                Stream::Stderr => eprintln!("{}", line.text),
+            },
+            TransferEvent::Progress(..) => {}
+            TransferEvent::Finished(code) => break code,
+            TransferEvent::Failed(e) => anyhow::bail!("failed to wait for rsync: {}", e),
+        }
+    };
+    let exit = RsyncExit::from_code(code);
+    if !exit.is_success(app.vanished_ok) {
+        anyhow::bail!("dry run for the safety policy failed: {}", exit.status());
+    }
+    Ok(Plan::new(args, &changes).totals().deleted)
+}
+
+/// Run the sync without the TUI, printing output, and return rsync's exit
+/// code. With `json`, stdout carries only the final report and rsync's
+/// output goes to stderr.
+pub fn run(runtime: &tokio::runtime::Runtime, app: &App, dry_run: bool, json: bool) -> i32 {
+    let checked = prepare(app, dry_run, json).and_then(|command| {
+        let deletions = if needs_dry_run(app, &command) {
+            eprintln!("Dry-running first for the safety policy");
+            Some(count_deletions(runtime, app, &command)?)
+        } else {
+            None
+        };
+        check_policy(app, &command, deletions)?;
+        Ok(command)
+    });
+    let command = match checked {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("rsync_tui: {:#}", e);
+            return FAILURE;
+        }
+    };
+    // The profile may turn on -n itself
+    let dry_run = safety::is_dry_run(&command);
+
+    if json {
+        eprintln!("Running: {}", format_command(&command));
+    } else {
+        println!("Running: {}", format_command(&command));
+    }
+    let started_at = timestamp::now();
+    let started = Instant::now();
+    let mut transfer = match runner::spawn(&command, &command_env(&app.options)) {
+        Ok(transfer) => transfer,
+        Err(e) => {
+            eprintln!("rsync_tui: failed to execute rsync: {}", e);
+            return FAILURE;
+        }
+    };
+
+    // Redraw a single progress line only when a person is watching
+    let interactive = !json && std::io::stdout().is_terminal();
+    let mut progress_shown = false;
+    let mut stats: Option<TransferStats> = None;
+    let mut output = Vec::new();
+
+    let code = loop {
+        let Some(event) = runtime.block_on(transfer.events.recv()) else {
+            break None;
+        };
+        match event {
+            TransferEvent::Line(line) => {
+                output.push(line.display());
+                if progress_shown {
+                    println!();
+                    progress_shown = false;
+                }
+                match line.stream {
+                    Stream::Stdout => {
+                        let mut next = stats.unwrap_or_default();
+                        if next.apply(&line.text) {
+                            stats = Some(next);
+                        }
+                        if json {
+                            eprintln!("{}", line.text);
+                        } else {
+                            println!("{}", line.text);
+                        }
+                    }
+                    Stream::Stderr => eprintln!("{}", line.text),
+                }
+            }
+            TransferEvent::Progress(percent, info) if interactive => {
+                print!("\r\x1b[2K{:5.1}% {}", percent, info);
+                let _ = std::io::stdout().flush();
+                progress_shown = true;
+            }
+            TransferEvent::Progress(..) => {}
+            TransferEvent::Finished(code) => break code,
+            TransferEvent::Failed(e) => {
+                eprintln!("rsync_tui: failed to wait for rsync: {}", e);
+                break None;
+            }
+        }
+    };
+    if progress_shown {
+        println!();
+    }
+
+    if !dry_run {
+        record_run(&app.profile_name, code, stats);
+    }
+    let exit = RsyncExit::from_code(code);
+    let entry = HistoryEntry {
+        started_at,
+        profile: app.profile_name.clone(),
+        command: command.clone(),
+        dry_run,
+        duration_ms: started.elapsed().as_millis() as u64,
+        exit_code: code,
+        success: exit.is_success(app.vanished_ok),
+        stats,
+        bytes: stats.map(|stats| stats.transferred_size),
+        log_file: None,
+    };
+    if let Some(dir) = history::data_dir() {
+        if let Err(e) = history::record(&dir, &entry, &output) {
+            eprintln!("rsync_tui: failed to record run in history: {:#}", e);
+        }
+    }
+    if json {
+        let report = Report {
+            profile: app.profile_name.clone(),
+            command,
+            dry_run,
+            exit_code: code,
+            status: exit.status(),
+            success: exit.is_success(app.vanished_ok),
+            stats,
+        };
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{}", text),
+            Err(e) => eprintln!("rsync_tui: cannot write JSON report: {}", e),
+        }
+    }
+    if exit != RsyncExit::Success {
+        eprintln!("rsync_tui: {}: {}", exit.status(), exit.explanation());
+        if let Some(suggestion) = exit
+            .suggestion()
+            .filter(|_| !exit.is_success(app.vanished_ok))
+        {
+            eprintln!("rsync_tui: {}", suggestion);
+        }
+    }
+    exit_status(exit, app.vanished_ok)
+}
+
+/// Exit code of a headless run: rsync's own, or 0 for vanished files when
+/// the profile accepts them
+fn exit_status(exit: RsyncExit, vanished_ok: bool) -> i32 {
+    match exit.code() {
+        _ if exit.is_success(vanished_ok) => 0,
+        Some(code) => code,
+        None => FAILURE,
+    }
+}
+
+/// Store the result in the profile file, as the TUI does
+fn record_run(name: &str, exit_code: Option<i32>, stats: Option<TransferStats>) {
+    let Some(dir) = profile::profiles_dir() else {
+        return;
+    };
+    let run = LastRun {
+        finished_at: timestamp::now(),
+        exit_code,
+        stats,
+    };
+    if let Err(e) = profile::record_run(&dir, name, &run) {
+        eprintln!("rsync_tui: failed to record run in profile: {:#}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.source = "/src/".to_string();
+        app.destination = "/dest".to_string();
+        app
+    }
+
+    #[test]
+    fn test_prepare_requires_paths() {
+        assert!(prepare(&App::new(), false, false).is_err());
+    }
+
+    #[test]
+    fn test_prepare_forces_progress_and_dry_run() {
+        let command = prepare(&app(), true, false).unwrap();
+        assert!(command.contains(&"--progress".to_string()));
+        assert!(command.contains(&"-n".to_string()));
+        assert_eq!(command.last().unwrap(), "/dest");
+
+        let command = prepare(&app(), false, false).unwrap();
+        assert!(!command.contains(&"-n".to_string()));
+        assert!(!command.contains(&"--stats".to_string()));
+
+        // JSON reports need the --stats summary
+        let command = prepare(&app(), false, true).unwrap();
+        assert!(command.contains(&"--stats".to_string()));
+
+        // A profile can ask for a dry run without --dry-run
+        let mut app = app();
+        app.options.set_enabled("dry_run", true);
+        assert!(safety::is_dry_run(&prepare(&app, false, false).unwrap()));
+    }
+
+    #[test]
+    fn test_policy_checked_against_a_dry_run() {
+        let mut app = app();
+        app.options.set_enabled("delete", true);
+        // Without a policy a headless --delete run goes ahead
+        let command = prepare(&app, false, false).unwrap();
+        assert!(!needs_dry_run(&app, &command));
+        assert!(check_policy(&app, &command, None).is_ok());
+
+        app.policy.require_dry_run = true;
+        assert!(needs_dry_run(&app, &command));
+        assert!(check_policy(&app, &command, None).is_err());
+        assert!(check_policy(&app, &command, Some(3)).is_ok());
+
+        app.policy.max_deletions = Some(2);
+        let err = check_policy(&app, &command, Some(3)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Dry run predicted 3 deletions, policy allows at most 2"
+        );
+        assert!(check_policy(&app, &command, Some(2)).is_ok());
+
+        // A dry run is never destructive
+        let command = prepare(&app, true, false).unwrap();
+        assert!(!needs_dry_run(&app, &command));
+        assert!(check_policy(&app, &command, None).is_ok());
+    }
+
+    #[test]
+    fn test_count_deletions_with_a_dry_run() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Stand-in for rsync that lists what a --delete dry run would do,
+        // and fails if it is run for real
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let rsync = dir.join("rsync");
+        std::fs::write(
+            &rsync,
+            "#!/bin/sh\n[ \"$1\" = -n ] || exit 1\n\
+             echo '*deleting   0 old1'\necho '*deleting   0 old/old2'\n\
+             echo '>f+++++++++ 5 new'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&rsync, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut app = app();
+        app.rsync.program = rsync.display().to_string();
+        app.options.set_enabled("delete", true);
+        let command = prepare(&app, false, false).unwrap();
+        assert_eq!(command[0], app.rsync.program);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+        assert_eq!(count_deletions(&runtime, &app, &command).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_exit_status() {
+        assert_eq!(exit_status(RsyncExit::Success, false), 0);
+        assert_eq!(exit_status(RsyncExit::Vanished, false), 24);
+        assert_eq!(exit_status(RsyncExit::Vanished, true), 0);
+        assert_eq!(exit_status(RsyncExit::Partial, true), 23);
+        assert_eq!(exit_status(RsyncExit::Killed, false), FAILURE);
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::profile;
+use crate::rsync::exit::RsyncExit;
+use crate::rsync::stats::TransferStats;
+
+/// File in the data directory holding one JSON entry per line
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// Directory of full run logs, inside the data directory
+const LOGS_DIR: &str = "logs";
+
+/// One rsync run, as stored in the history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch when rsync was started
+    pub started_at: u64,
+    /// Profile loaded when the run was started
+    pub profile: String,
+    /// Exact command line, program first
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+    pub duration_ms: u64,
+    /// rsync exit code (None if it was killed or could not be waited on)
+    pub exit_code: Option<i32>,
+    /// Whether the run counted as successful, with the profile's
+    /// `vanished_ok` applied
+    pub success: bool,
+    #[serde(default)]
+    pub stats: Option<TransferStats>,
+    /// Size of the file data transferred, from `--stats`
+    #[serde(default)]
+    pub bytes: Option<u64>,
+    /// Full output of the run
+    #[serde(default)]
+    pub log_file: Option<PathBuf>,
+}
+
+impl HistoryEntry {
+    pub fn exit(&self) -> RsyncExit {
+        RsyncExit::from_code(self.exit_code)
+    }
+}
+
+/// Directory holding the history and run logs:
+/// `$XDG_DATA_HOME/rsync_tui`, or `~/.local/share/rsync_tui`
+pub fn data_dir() -> Option<PathBuf> {
+    let data = match std::env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var("HOME").ok()?)
+            .join(".local")
+            .join("share"),
+    };
+    Some(data.join("rsync_tui"))
+}
+
+/// Write the run's output to its log file and append the entry to the
+/// history, returning the entry with the log file set
+pub fn record(dir: &Path, entry: &HistoryEntry, output: &[String]) -> anyhow::Result<HistoryEntry> {
+    let logs = dir.join(LOGS_DIR);
+    fs::create_dir_all(&logs).with_context(|| format!("creating {}", logs.display()))?;
+
+    let mut entry = entry.clone();
+    // Runs of a profile started in the same second get numbered logs
+    let base = format!(
+        "{}-{}",
+        entry.started_at,
+        profile::safe_name(&entry.profile)
+    );
+    let mut log_file = logs.join(format!("{}.log", base));
+    let mut n = 1;
+    while log_file.exists() {
+        n += 1;
+        log_file = logs.join(format!("{}-{}.log", base, n));
+    }
+    let mut log = output.join("\n");
+    log.push('\n');
+    fs::write(&log_file, log).with_context(|| format!("writing {}", log_file.display()))?;
+    entry.log_file = Some(log_file);
+
+    let path = dir.join(HISTORY_FILE);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("opening {}", path.display()))?;
+    // One write per entry so concurrent runs can't interleave lines
+    let line = format!("{}\n", serde_json::to_string(&entry)?);
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("writing {}", path.display()))?;
+    Ok(entry)
+}
+
+/// Every recorded run, newest first. Lines that can't be parsed are
+/// skipped so one damaged entry doesn't hide the rest.
+pub fn load(dir: &Path) -> anyhow::Result<Vec<HistoryEntry>> {
+    let path = dir.join(HISTORY_FILE);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+    let mut entries: Vec<HistoryEntry> = text
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    entries.reverse();
+    Ok(entries)
+}
+
+/// Run durations such as "850ms", "42s" or "1h 02m"
+pub fn format_duration(ms: u64) -> String {
+    let secs = ms / 1000;
+    match secs {
+        0 => format!("{}ms", ms),
+        1..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, (secs / 60) % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(started_at: u64, profile: &str, exit_code: Option<i32>) -> HistoryEntry {
+        HistoryEntry {
+            started_at,
+            profile: profile.to_string(),
+            command: vec!["rsync".to_string(), "-a".to_string(), "a/".to_string()],
+            dry_run: false,
+            duration_ms: 1500,
+            exit_code,
+            success: exit_code == Some(0),
+            stats: None,
+            bytes: None,
+            log_file: None,
+        }
+    }
+
+    #[test]
+    fn test_record_and_load() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        assert!(load(dir).unwrap().is_empty());
+
+        let first = record(dir, &entry(100, "nas backup", Some(0)), &["a".to_string()]).unwrap();
+        let log_file = first.log_file.clone().unwrap();
+        assert!(log_file.ends_with("logs/100-nas-backup.log"));
+        assert_eq!(fs::read_to_string(&log_file).unwrap(), "a\n");
+        let second = record(dir, &entry(100, "nas backup", Some(23)), &[]).unwrap();
+        assert!(second
+            .log_file
+            .unwrap()
+            .ends_with("logs/100-nas-backup-2.log"));
+        record(dir, &entry(200, "offsite", Some(23)), &[]).unwrap();
+
+        // A damaged line is skipped
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.join(HISTORY_FILE))
+            .unwrap();
+        file.write_all(b"{not json\n").unwrap();
+
+        let entries = load(dir).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].profile, "offsite");
+        assert_eq!(entries[0].exit(), RsyncExit::Partial);
+        assert_eq!(entries[2], first);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(850), "850ms");
+        assert_eq!(format_duration(42_000), "42s");
+        assert_eq!(format_duration(125_000), "2m 05s");
+        assert_eq!(format_duration(3_720_000), "1h 02m");
+    }
+}
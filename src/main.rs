@@ -1,11 +1,23 @@
 mod app;
+mod cli;
+mod clipboard;
 mod event;
+mod headless;
+mod history;
 mod path;
+mod profile;
+mod remote;
 mod rsync;
+mod safety;
+mod ssh_config;
+mod timestamp;
 mod ui;
 
 use std::io;
-use app::{App, Mode, Panel};
+use app::{
+    App, Confirmation, FilterEditor, HistoryView, Mode, Panel, ProfileAction, ProfileManager,
+    RunProfile,
+};
 use crossterm::{
     event::{KeyCode, KeyModifiers},
     execute,
@@ -14,6 +26,71 @@ use crossterm::{
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 fn main() -> anyhow::Result<()> {
+    let cli = match cli::Cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("rsync_tui: {:#}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if cli.help {
+        print!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if cli.version {
+        println!("rsync_tui {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+
+    // Background transfers run on the tokio runtime
+    let runtime = tokio::runtime::Runtime::new()?;
+    let _guard = runtime.enter();
+
+    let mut app = App::new();
+    app.rsync = probe_rsync(cli.rsync.as_deref());
+    app.ssh_config = ssh_config::SshConfig::load();
+    match &cli.profile {
+        Some(name) => match load_profile(name) {
+            Ok(loaded) => app.apply_profile(&loaded),
+            Err(e) => {
+                eprintln!("rsync_tui: cannot load profile '{}': {:#}", name, e);
+                std::process::exit(1);
+            }
+        },
+        None => load_default_profile(&mut app),
+    }
+    if let Some(command) = &cli.from_command {
+        if let Err(e) = app.apply_command(command) {
+            eprintln!("rsync_tui: cannot parse --from-command: {:#}", e);
+            std::process::exit(2);
+        }
+    }
+    if let Some(source) = cli.source {
+        app.source = source;
+    }
+    if let Some(destination) = cli.destination {
+        app.destination = destination;
+    }
+
+    match &app.rsync.error {
+        Some(e) => app.log(format!("Cannot probe rsync: {}", e)),
+        None => {
+            let path = app.rsync.path.as_ref().map(|p| p.display().to_string());
+            let program = path.unwrap_or_else(|| app.rsync.program.clone());
+            app.log(format!("Using {} ({})", program, app.rsync.describe()));
+        }
+    }
+    for warning in app.rsync.warnings(&app.options) {
+        app.log(format!("Warning: {}", warning));
+    }
+
+    if cli.headless {
+        for message in &app.logs {
+            eprintln!("rsync_tui: {}", message);
+        }
+        std::process::exit(headless::run(&runtime, &app, cli.dry_run, cli.json));
+    }
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -21,8 +98,10 @@ fn main() -> anyhow::Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Run app
-    let mut app = App::new();
+    // Run app, starting with the dry-run review if asked for
+    if cli.dry_run {
+        run_rsync(&mut app, true);
+    }
     let result = run(&mut terminal, &mut app);
 
     // Restore terminal
@@ -33,8 +112,26 @@ fn main() -> anyhow::Result<()> {
     result
 }
 
+/// Find the rsync to run (`--rsync`, then `$RSYNC_TUI_RSYNC`, then `rsync` on
+/// PATH) and read its version, logging what was found
+fn probe_rsync(configured: Option<&str>) -> rsync::binary::RsyncInfo {
+    use crate::rsync::binary::{probe, RSYNC_ENV};
+
+    let from_env = std::env::var(RSYNC_ENV).ok().filter(|p| !p.is_empty());
+    let program = configured.map(String::from).or(from_env);
+    probe(program.as_deref().unwrap_or("rsync"))
+}
+
 fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> anyhow::Result<()> {
     loop {
+        app.poll_transfer();
+        app.poll_remote();
+        if let Some((name, run)) = app.finished_run.take() {
+            record_run(app, &name, &run);
+        }
+        if let Some(entry) = app.finished_entry.take() {
+            record_history(app, &entry);
+        }
         terminal.draw(|frame| ui::layout::render(frame, app))?;
 
         if let Some(key) = event::poll_event(100)? {
@@ -52,14 +149,70 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
                     run_rsync(app, true);
                     true
                 }
+                KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    save_profile(app);
+                    true
+                }
+                KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    open_profiles(app);
+                    true
+                }
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    open_history(app);
+                    true
+                }
+                KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.filter_editor = Some(FilterEditor::default());
+                    true
+                }
+                KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.command_input = Some(String::new());
+                    true
+                }
+                KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    copy_command(app);
+                    true
+                }
+                KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.script_path = Some(format!("{}.sh", app.profile_name));
+                    true
+                }
+                KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.cancel_transfer();
+                    true
+                }
+                KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.toggle_pause();
+                    true
+                }
                 _ => false,
             };
 
             // Mode-specific handling (if not handled globally)
             if !handled {
-                match app.mode {
-                    Mode::Normal => handle_normal_mode(app, &key),
-                    Mode::Insert => handle_insert_mode(app, &key),
+                if app.confirm.is_some() {
+                    handle_confirm(app, &key);
+                } else if app.profiles.is_some() {
+                    handle_profiles(app, &key);
+                } else if app.history.is_some() {
+                    handle_history(app, &key);
+                } else if app.command_input.is_some() {
+                    handle_command_input(app, &key);
+                } else if app.script_path.is_some() {
+                    handle_script_path(app, &key);
+                } else if app.modules.is_some() {
+                    handle_modules(app, &key);
+                } else if app.filter_editor.is_some() {
+                    handle_filter_editor(app, &key);
+                } else if app.option_edit.is_some() {
+                    handle_option_edit(app, &key);
+                } else if app.review.is_some() {
+                    handle_review(app, &key);
+                } else {
+                    match app.mode {
+                        Mode::Normal => handle_normal_mode(app, &key),
+                        Mode::Insert => handle_insert_mode(app, &key),
+                    }
                 }
             }
         }
@@ -72,6 +225,296 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) ->
     Ok(())
 }
 
+/// Read a named profile from the profiles directory
+fn load_profile(name: &str) -> anyhow::Result<profile::Profile> {
+    let dir = profile::profiles_dir()
+        .ok_or_else(|| anyhow::anyhow!("no config directory (set HOME)"))?;
+    profile::load_named(&dir, name)
+}
+
+/// Profile a command from the history re-runs with: the one it ran with, so
+/// it is checked by that profile's policy, recorded under its name and a
+/// saved `RSYNC_PASSWORD` only goes to the daemon it belongs to
+fn rerun_profile(app: &mut App, name: &str) -> RunProfile {
+    if name == app.profile_name {
+        return app.run_profile();
+    }
+    match load_profile(name) {
+        Ok(profile) => RunProfile::from_profile(&profile),
+        Err(e) => {
+            // Keep the loaded policy rather than run with none at all
+            app.log(format!(
+                "Profile {} not loaded, running without its environment: {:#}",
+                name, e
+            ));
+            RunProfile {
+                name: name.to_string(),
+                policy: app.policy.clone(),
+                vanished_ok: false,
+                env: Vec::new(),
+            }
+        }
+    }
+}
+
+/// Restore the default profile if one has been saved
+fn load_default_profile(app: &mut App) {
+    let Some(dir) = profile::profiles_dir() else {
+        return;
+    };
+    if !dir.join(profile::file_name(profile::DEFAULT_PROFILE)).exists() {
+        return;
+    }
+    match profile::load_named(&dir, profile::DEFAULT_PROFILE) {
+        Ok(loaded) => app.apply_profile(&loaded),
+        Err(e) => app.log(format!("Failed to load profile: {:#}", e)),
+    }
+}
+
+/// Save the current setup to its profile file
+fn save_profile(app: &mut App) {
+    let Some(dir) = profile::profiles_dir() else {
+        app.log("Cannot save profile: no config directory (set HOME)".to_string());
+        return;
+    };
+    match profile::save(&dir, &app.to_profile()) {
+        Ok(path) => app.log(format!("Saved profile '{}' to {}", app.profile_name, path.display())),
+        Err(e) => app.log(format!("Failed to save profile: {:#}", e)),
+    }
+}
+
+/// Store the result of a finished run in the file of the profile it ran with
+fn record_run(app: &mut App, name: &str, run: &profile::LastRun) {
+    let Some(dir) = profile::profiles_dir() else {
+        return;
+    };
+    if let Err(e) = profile::record_run(&dir, name, run) {
+        app.log(format!("Failed to record run in profile: {:#}", e));
+    }
+}
+
+/// Add a finished run to the history, with its output in a log file
+fn record_history(app: &mut App, entry: &history::HistoryEntry) {
+    let Some(dir) = history::data_dir() else {
+        return;
+    };
+    let output: Vec<String> = app.progress_output.iter().map(|line| line.display()).collect();
+    if let Err(e) = history::record(&dir, entry, &output) {
+        app.log(format!("Failed to record run in history: {:#}", e));
+    }
+}
+
+fn open_history(app: &mut App) {
+    let Some(dir) = history::data_dir() else {
+        app.log("Cannot read history: no data directory (set HOME)".to_string());
+        return;
+    };
+    match history::load(&dir) {
+        Ok(entries) => app.history = Some(HistoryView::new(entries)),
+        Err(e) => app.log(format!("Failed to read history: {:#}", e)),
+    }
+}
+
+fn handle_history(app: &mut App, key: &crossterm::event::KeyEvent) {
+    let Some(view) = app.history.as_mut() else {
+        return;
+    };
+
+    // Typing a search of profile names and command lines
+    if view.searching {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter => view.searching = false,
+            KeyCode::Backspace => view.pop_search(),
+            KeyCode::Char(c)
+                if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                view.push_search(c)
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => view.move_selection(1),
+        KeyCode::Char('k') | KeyCode::Up => view.move_selection(-1),
+        KeyCode::Char('p') => view.cycle_profile(),
+        KeyCode::Char('f') => view.cycle_status(),
+        KeyCode::Char('/') => view.searching = true,
+        KeyCode::Enter | KeyCode::Char('r') => {
+            let Some(entry) = view.selected().cloned() else {
+                return;
+            };
+            if app.is_running() {
+                app.log("A transfer is already running".to_string());
+                return;
+            }
+            app.history = None;
+            app.log(format!(
+                "Re-running {} run from {}",
+                entry.profile,
+                timestamp::format(entry.started_at)
+            ));
+            let run = rerun_profile(app, &entry.profile);
+            run_checked(app, entry.command, run);
+        }
+        KeyCode::Esc | KeyCode::Char('q') => app.history = None,
+        _ => {}
+    }
+}
+
+/// Put the exact command on the terminal's clipboard
+fn copy_command(app: &mut App) {
+    use crate::rsync::command::format_command;
+
+    let command = format_command(&app.command(&app.options));
+    match clipboard::copy(&command) {
+        Ok(()) => app.log("Copied command to the clipboard".to_string()),
+        Err(e) => app.log(format!("Failed to copy command: {}", e)),
+    }
+}
+
+/// Open the profile manager with the saved profiles
+fn open_profiles(app: &mut App) {
+    let Some(dir) = profile::profiles_dir() else {
+        app.log("Cannot list profiles: no config directory (set HOME)".to_string());
+        return;
+    };
+    match profile::list(&dir) {
+        Ok(profiles) => {
+            let mut manager = ProfileManager::new(profiles);
+            manager.select(&app.profile_name);
+            app.profiles = Some(manager);
+        }
+        Err(e) => app.log(format!("Failed to list profiles: {:#}", e)),
+    }
+}
+
+fn handle_profiles(app: &mut App, key: &crossterm::event::KeyEvent) {
+    let Some(dir) = profile::profiles_dir() else {
+        app.profiles = None;
+        return;
+    };
+    let Some(manager) = app.profiles.as_mut() else {
+        return;
+    };
+
+    // Typing a name for save-as, rename or duplicate
+    if let Some((action, name)) = manager.input.as_mut() {
+        match key.code {
+            KeyCode::Esc => manager.input = None,
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Char(c)
+                if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                name.push(c);
+            }
+            KeyCode::Enter => {
+                let (action, name) = (*action, name.trim().to_string());
+                manager.input = None;
+                let selected = manager.selected().map(|p| p.name.clone());
+                apply_profile_action(app, &dir, action, selected, name);
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    // Waiting for confirmation to delete the selected profile
+    if manager.confirm_delete {
+        manager.confirm_delete = false;
+        if key.code != KeyCode::Char('y') {
+            return;
+        }
+        let Some(name) = manager.selected().map(|p| p.name.clone()) else {
+            return;
+        };
+        match profile::delete(&dir, &name) {
+            Ok(()) => app.log(format!("Deleted profile '{}'", name)),
+            Err(e) => app.log(format!("Failed to delete profile: {:#}", e)),
+        }
+        reload_profiles(app, &dir, &name);
+        return;
+    }
+
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => manager.move_selection(1),
+        KeyCode::Char('k') | KeyCode::Up => manager.move_selection(-1),
+        KeyCode::Char('s') => manager.input = Some((ProfileAction::SaveAs, String::new())),
+        KeyCode::Char('r') if manager.selected().is_some() => {
+            manager.input = Some((ProfileAction::Rename, String::new()));
+        }
+        KeyCode::Char('c') if manager.selected().is_some() => {
+            manager.input = Some((ProfileAction::Duplicate, String::new()));
+        }
+        KeyCode::Char('d') if manager.selected().is_some() => manager.confirm_delete = true,
+
+        // Load the selected profile into the editor
+        KeyCode::Enter | KeyCode::Char('l') => {
+            let Some(selected) = manager.selected().cloned() else {
+                return;
+            };
+            if app.is_running() {
+                app.log("Cannot switch profiles while a transfer is running".to_string());
+                return;
+            }
+            app.apply_profile(&selected);
+            app.profiles = None;
+            app.log(format!("Loaded profile '{}'", selected.name));
+        }
+
+        KeyCode::Esc | KeyCode::Char('q') => app.profiles = None,
+        _ => {}
+    }
+}
+
+/// Run a named profile-manager action and refresh the list
+fn apply_profile_action(
+    app: &mut App,
+    dir: &std::path::Path,
+    action: ProfileAction,
+    selected: Option<String>,
+    name: String,
+) {
+    let result = match (action, selected) {
+        (ProfileAction::SaveAs, _) => {
+            profile::save_as(dir, &app.to_profile(), &name).map(|_| {
+                app.profile_name = name.clone();
+                format!("Saved current setup as profile '{}'", name)
+            })
+        }
+        (ProfileAction::Rename, Some(from)) => profile::rename(dir, &from, &name).map(|_| {
+            // Keep recording runs against the renamed profile
+            if app.profile_name == from {
+                app.profile_name = name.clone();
+            }
+            format!("Renamed profile '{}' to '{}'", from, name)
+        }),
+        (ProfileAction::Duplicate, Some(from)) => profile::duplicate(dir, &from, &name)
+            .map(|_| format!("Duplicated profile '{}' as '{}'", from, name)),
+        (_, None) => return,
+    };
+    match result {
+        Ok(message) => app.log(message),
+        Err(e) => app.log(format!("Profile {:?} failed: {:#}", action, e)),
+    }
+    reload_profiles(app, dir, &name);
+}
+
+/// Re-read the profile list after a change, keeping `select` highlighted
+fn reload_profiles(app: &mut App, dir: &std::path::Path, select: &str) {
+    match profile::list(dir) {
+        Ok(profiles) => {
+            let mut manager = ProfileManager::new(profiles);
+            manager.select(select);
+            app.profiles = Some(manager);
+        }
+        Err(e) => app.log(format!("Failed to list profiles: {:#}", e)),
+    }
+}
+
 fn handle_normal_mode(app: &mut App, key: &crossterm::event::KeyEvent) {
     match key.code {
         // Quit
@@ -88,6 +531,22 @@ fn handle_normal_mode(app: &mut App, key: &crossterm::event::KeyEvent) {
         KeyCode::Char('4') => app.active_panel = Panel::Logs,
         KeyCode::Char('5') => app.active_panel = Panel::Progress,
 
+        // Options panel: move the cursor, toggle flags, edit values
+        KeyCode::Char('j') | KeyCode::Down if app.active_panel == Panel::Options => {
+            app.move_option_cursor(1)
+        }
+        KeyCode::Char('k') | KeyCode::Up if app.active_panel == Panel::Options => {
+            app.move_option_cursor(-1)
+        }
+        KeyCode::PageDown if app.active_panel == Panel::Options => app.move_option_cursor(5),
+        KeyCode::PageUp if app.active_panel == Panel::Options => app.move_option_cursor(-5),
+        KeyCode::Char(' ') | KeyCode::Enter if app.active_panel == Panel::Options => {
+            app.activate_option()
+        }
+        KeyCode::Backspace | KeyCode::Delete if app.active_panel == Panel::Options => {
+            app.reset_option()
+        }
+
         // Vim-style navigation (j/k)
         KeyCode::Char('j') => app.next_panel(), // Move down
         KeyCode::Char('k') => app.prev_panel(), // Move up
@@ -104,16 +563,241 @@ fn handle_normal_mode(app: &mut App, key: &crossterm::event::KeyEvent) {
             run_rsync(app, false);
         }
 
-        // Option toggles with letter keys
-        KeyCode::Char('a') => app.options.toggle(0), // Archive
-        KeyCode::Char('v') => app.options.toggle(1), // Verbose
-        KeyCode::Char('z') => app.options.toggle(2), // Compress
-        KeyCode::Char('n') => app.options.toggle(3), // Dry-run
-        KeyCode::Char('p') => app.options.toggle(4), // Progress
-        KeyCode::Char('d') => app.options.toggle(5), // Delete
-        KeyCode::Char('h') => app.options.toggle(6), // Human-readable
-        KeyCode::Char('e') => app.options.toggle(7), // SSH
+        // Scroll the change list in the Progress panel
+        KeyCode::Up if app.active_panel == Panel::Progress => app.scroll_changes(-1),
+        KeyCode::Down if app.active_panel == Panel::Progress => app.scroll_changes(1),
+        KeyCode::PageUp if app.active_panel == Panel::Progress => app.scroll_changes(-10),
+        KeyCode::PageDown if app.active_panel == Panel::Progress => app.scroll_changes(10),
+
+        // Option toggles with the keys from the option catalog
+        KeyCode::Char(c) => {
+            if let Some(spec) = rsync::catalog::by_key(c) {
+                app.toggle_option(spec.id);
+            }
+        }
+
+        _ => {}
+    }
+}
+
+fn handle_review(app: &mut App, key: &crossterm::event::KeyEvent) {
+    let Some(review) = app.review.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => review.move_selection(1),
+        KeyCode::Char('k') | KeyCode::Up => review.move_selection(-1),
+        KeyCode::PageDown => review.move_selection(10),
+        KeyCode::PageUp => review.move_selection(-10),
+        KeyCode::Char('l') | KeyCode::Enter => review.enter(),
+        KeyCode::Char('h') | KeyCode::Backspace => review.back(),
+
+        // Execute exactly the reviewed command for real
+        KeyCode::Char('e') => {
+            let command = review.plan.command.clone();
+            app.review = None;
+            let run = app.run_profile();
+            run_checked(app, command, run);
+        }
+
+        KeyCode::Esc | KeyCode::Char('q') => app.review = None,
+        _ => {}
+    }
+}
+
+fn handle_confirm(app: &mut App, key: &crossterm::event::KeyEvent) {
+    let Some(confirm) = app.confirm.take() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Char('y') if confirm.blocked.is_none() => {
+            spawn_transfer(app, confirm.command, confirm.run);
+        }
+        KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => {
+            app.log("Sync cancelled".to_string());
+        }
+        // Ignore other keys and keep the modal open
+        _ => app.confirm = Some(confirm),
+    }
+}
+
+fn handle_filter_editor(app: &mut App, key: &crossterm::event::KeyEvent) {
+    let Some(editor) = app.filter_editor.as_mut() else {
+        return;
+    };
+    let rules = &mut app.options.filters;
+    editor.message = None;
+
+    // Typing a rule
+    if let Some(edit) = editor.edit.as_mut() {
+        match key.code {
+            KeyCode::Esc => editor.edit = None,
+            KeyCode::Enter => {
+                editor.commit(rules);
+                if editor.test.is_some() {
+                    editor.run_test(&app.source, rules);
+                }
+            }
+            KeyCode::Tab => edit.kind = edit.kind.cycle(1),
+            KeyCode::BackTab => edit.kind = edit.kind.cycle(-1),
+            KeyCode::Backspace => {
+                edit.text.pop();
+            }
+            KeyCode::Char(c)
+                if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                edit.text.push(c);
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => editor.move_selection(rules, 1),
+        KeyCode::Char('k') | KeyCode::Up => editor.move_selection(rules, -1),
+
+        KeyCode::Char('a') => editor.start_add(rules),
+        KeyCode::Char('e') | KeyCode::Enter => editor.start_edit(rules),
+
+        // Show what the rules do to the source tree
+        KeyCode::Char('t') => editor.toggle_test(&app.source, rules),
+        // Translate .gitignore / .rsyncignore files in the source
+        KeyCode::Char('g') => app.import_ignore_files(),
+
+        KeyCode::PageDown => editor.scroll_test(10),
+        KeyCode::PageUp => editor.scroll_test(-10),
+
+        // Keys that change the rules refresh the tester
+        KeyCode::Char('J') | KeyCode::Char('K') | KeyCode::Char('d') | KeyCode::Delete
+        | KeyCode::Char('r') => {
+            match key.code {
+                // Reorder: rsync applies the first matching rule
+                KeyCode::Char('J') => editor.move_rule(rules, 1),
+                KeyCode::Char('K') => editor.move_rule(rules, -1),
+                KeyCode::Char('d') | KeyCode::Delete => editor.delete_rule(rules),
+                // Re-read the source tree
+                _ => {}
+            }
+            if editor.test.is_some() {
+                editor.run_test(&app.source, rules);
+            }
+        }
+
+        KeyCode::Esc | KeyCode::Char('q') => app.filter_editor = None,
+        _ => {}
+    }
+}
+
+fn handle_command_input(app: &mut App, key: &crossterm::event::KeyEvent) {
+    let Some(line) = app.command_input.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Esc => app.command_input = None,
+        KeyCode::Enter => {
+            let line = std::mem::take(line);
+            match app.apply_command(&line) {
+                Ok(()) => app.command_input = None,
+                // Keep the text so it can be fixed
+                Err(e) => {
+                    app.log(format!("Cannot parse command: {:#}", e));
+                    app.command_input = Some(line);
+                }
+            }
+        }
+        KeyCode::Backspace => {
+            line.pop();
+        }
+        KeyCode::Char(c)
+            if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            line.push(c);
+        }
+        _ => {}
+    }
+}
+
+fn handle_script_path(app: &mut App, key: &crossterm::event::KeyEvent) {
+    let Some(path) = app.script_path.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Esc => {
+            app.script_path = None;
+            app.script_overwrite = false;
+        }
+        KeyCode::Enter => {
+            let path = path.trim().to_string();
+            if path.is_empty() {
+                return;
+            }
+            // An existing file is only replaced after a second Enter
+            let overwrite = app.script_overwrite;
+            if !overwrite && std::path::Path::new(&path::expand_tilde(&path)).exists() {
+                app.script_overwrite = true;
+                return;
+            }
+            app.script_path = None;
+            app.script_overwrite = false;
+            match app.export_script(&path, overwrite) {
+                Ok(path) => app.log(format!("Wrote script to {}", path.display())),
+                Err(e) => app.log(format!("Failed to write script: {:#}", e)),
+            }
+        }
+        KeyCode::Backspace => {
+            path.pop();
+            app.script_overwrite = false;
+        }
+        KeyCode::Char(c)
+            if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            path.push(c);
+            app.script_overwrite = false;
+        }
+        _ => {}
+    }
+}
+
+fn handle_modules(app: &mut App, key: &crossterm::event::KeyEvent) {
+    let Some(browser) = app.modules.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Esc => app.modules = None,
+        KeyCode::Enter | KeyCode::Tab => app.choose_module(),
+        KeyCode::Up => browser.move_selection(-1),
+        KeyCode::Down => browser.move_selection(1),
+        KeyCode::Backspace => browser.pop_filter(),
+        KeyCode::Char(c)
+            if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            browser.push_filter(c);
+        }
+        _ => {}
+    }
+}
+
+fn handle_option_edit(app: &mut App, key: &crossterm::event::KeyEvent) {
+    let Some(edit) = app.option_edit.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Esc => app.option_edit = None,
+        KeyCode::Enter => app.commit_option_edit(),
+
+        // Step through the choices of an enum
+        KeyCode::Left => edit.cycle(-1),
+        KeyCode::Right => edit.cycle(1),
 
+        KeyCode::Backspace => {
+            edit.text.pop();
+        }
+        KeyCode::Char(c)
+            if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            edit.text.push(c);
+        }
         _ => {}
     }
 }
@@ -133,25 +817,22 @@ fn handle_insert_mode(app: &mut App, key: &crossterm::event::KeyEvent) {
 
         // Tab - path autocomplete
         KeyCode::Tab => {
-            let current_path = match app.active_panel {
-                Panel::Source => app.source.clone(),
-                Panel::Destination => app.destination.clone(),
-                _ => return,
-            };
-
-            if let Some(completed) = path::complete_path(&current_path) {
-                match app.active_panel {
-                    Panel::Source => app.source = completed,
-                    Panel::Destination => app.destination = completed,
-                    _ => {}
-                }
+            // The host picker's highlighted alias wins over other completions
+            if app.active_panel == Panel::Destination && app.accept_host() {
+                return;
             }
+            app.complete_active_path();
         }
 
+        // Move through the host picker
+        KeyCode::Up => app.move_host_selection(-1),
+        KeyCode::Down => app.move_host_selection(1),
+
         // Text input (allow Shift for uppercase)
         KeyCode::Char(c)
             if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
         {
+            app.host_selected = 0;
             match app.active_panel {
                 Panel::Source => app.source.push(c),
                 Panel::Destination => app.destination.push(c),
@@ -161,6 +842,7 @@ fn handle_insert_mode(app: &mut App, key: &crossterm::event::KeyEvent) {
 
         // Backspace
         KeyCode::Backspace => {
+            app.host_selected = 0;
             match app.active_panel {
                 Panel::Source => { app.source.pop(); }
                 Panel::Destination => { app.destination.pop(); }
@@ -173,99 +855,82 @@ fn handle_insert_mode(app: &mut App, key: &crossterm::event::KeyEvent) {
 }
 
 fn run_rsync(app: &mut App, dry_run: bool) {
-    use std::io::{BufRead, BufReader};
-    use std::process::{Command, Stdio};
-    use crate::rsync::command::build_command;
+    use crate::rsync::plan::PLAN_OUT_FORMAT;
+
+    if app.is_running() {
+        app.log("A transfer is already running".to_string());
+        return;
+    }
 
     let mut opts = app.options.clone();
+
+    // Ensure progress flag is set to get progress output
+    opts.set_enabled("progress", true);
+    for warning in app.rsync.warnings(&opts) {
+        app.log(format!("Warning: {}", warning));
+    }
+
     if dry_run {
-        opts.dry_run = true;
+        // Dry-run the exact command a real run would use, printing each
+        // change with its size so the result can be reviewed as a plan
+        opts.set_enabled("dry_run", false);
+        let command = app.command(&opts);
+        let mut args = command.clone();
+        args.splice(1..1, ["-n".to_string(), PLAN_OUT_FORMAT.to_string()]);
+        let run = app.run_profile();
+        if spawn_transfer(app, args, run) {
+            app.pending_plan = Some(command);
+        }
+    } else {
+        let args = app.command(&opts);
+        let run = app.run_profile();
+        run_checked(app, args, run);
     }
+}
 
-    // Ensure progress flag is set to get progress output
-    opts.progress = true;
+/// Run a command, first asking for confirmation if it can delete files
+fn run_checked(app: &mut App, command: Vec<String>, run: RunProfile) {
+    use crate::safety::{check, summarize, Verdict};
 
-    let args = build_command(&app.source, &app.destination, &opts);
-    app.log(format!("Running: {}", args.join(" ")));
+    let blocked = match check(&command, &run.policy, app.last_dry_run.as_ref()) {
+        Verdict::Proceed => {
+            spawn_transfer(app, command, run);
+            return;
+        }
+        Verdict::Confirm => None,
+        Verdict::Block(reason) => {
+            app.log(format!("Blocked: {}", reason));
+            Some(reason)
+        }
+    };
+
+    let destination = command.last().cloned().unwrap_or_default();
+    let lines = summarize(&destination, &command, &run.policy, app.last_dry_run.as_ref());
+    app.confirm = Some(Confirmation {
+        command,
+        run,
+        lines,
+        blocked,
+    });
+}
+
+/// Start rsync with the given command line for the profile `run`, returning
+/// whether it spawned
+fn spawn_transfer(app: &mut App, args: Vec<String>, run: RunProfile) -> bool {
+    app.log(format!("Running: {}", rsync::command::format_command(&args)));
 
     // Clear progress state
     app.clear_progress();
-    app.running = true;
-
-    // Execute rsync with piped stdout for progress capture
-    let child = Command::new("rsync")
-        .args(&args[1..])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn();
-
-    match child {
-        Ok(mut proc) => {
-            // Read stdout for progress
-            if let Some(stdout) = proc.stdout.take() {
-                let reader = BufReader::new(stdout);
-                for line in reader.lines() {
-                    if let Ok(line_str) = line {
-                        // Parse progress from rsync output
-                        if let Some((percent, info)) = parse_progress(&line_str) {
-                            app.progress_percentage = percent;
-                            app.transfer_info = info;
-                        }
-                        app.progress_output.push(line_str.clone());
-                        app.log(line_str);
-                    }
-                }
-            }
 
-            // Read stderr
-            if let Some(stderr) = proc.stderr.take() {
-                let reader = BufReader::new(stderr);
-                for line in reader.lines() {
-                    if let Ok(line_str) = line {
-                        app.progress_output.push(format!("[ERR] {}", line_str));
-                        app.log(format!("[ERR] {}", line_str));
-                    }
-                }
-            }
-
-            // Wait for completion
-            match proc.wait() {
-                Ok(status) => {
-                    if status.success() {
-                        app.progress_percentage = 100.0;
-                        app.log("Sync completed successfully".to_string());
-                    } else {
-                        app.log(format!("Sync failed with exit code: {:?}", status.code()));
-                    }
-                }
-                Err(e) => {
-                    app.log(format!("Failed to wait for rsync: {}", e));
-                }
-            }
+    // Execute rsync in the background; output arrives via app.poll_transfer()
+    match rsync::runner::spawn(&args, &run.env) {
+        Ok(transfer) => {
+            app.start_transfer(transfer, args, run);
+            true
         }
         Err(e) => {
             app.log(format!("Failed to execute rsync: {}", e));
+            false
         }
     }
-
-    app.running = false;
-}
-
-/// Parse rsync progress output line
-/// Example: "     1,234,567  45%   12.34MB/s    0:01:23"
-fn parse_progress(line: &str) -> Option<(f64, String)> {
-    // Look for percentage pattern like "45%" or "100%"
-    let parts: Vec<&str> = line.split_whitespace().collect();
-
-    for (i, part) in parts.iter().enumerate() {
-        if part.ends_with('%') {
-            if let Ok(percent) = part.trim_end_matches('%').parse::<f64>() {
-                // Gather transfer info (speed and time if available)
-                let info: Vec<&str> = parts[i + 1..].iter().take(2).copied().collect();
-                return Some((percent.min(100.0), info.join(" ")));
-            }
-        }
-    }
-
-    None
 }
@@ -2,7 +2,7 @@ use std::fs;
 use std::path::Path;
 
 /// Expand tilde (~) to home directory path
-fn expand_tilde(path: &str) -> String {
+pub fn expand_tilde(path: &str) -> String {
     if path.starts_with('~') {
         if let Ok(home) = std::env::var("HOME") {
             return path.replacen('~', &home, 1);
@@ -11,9 +11,67 @@ fn expand_tilde(path: &str) -> String {
     path.to_string()
 }
 
-/// Complete a partial path using the filesystem.
+/// Whether rsync treats `path` as remote: `host:path`, `host::module` or an
+/// `rsync://` URL. A colon after the first slash is part of a local name.
+pub fn is_remote(path: &str) -> bool {
+    if path.starts_with("rsync://") {
+        return true;
+    }
+    match (path.find(':'), path.find('/')) {
+        (Some(colon), Some(slash)) => colon < slash,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+/// Complete a partial path using the filesystem, falling back to the ssh
+/// host aliases in `hosts` for a `host:` style remote path.
 /// Returns the completed path if matches found, None otherwise.
-pub fn complete_path(partial: &str) -> Option<String> {
+pub fn complete_path(partial: &str, hosts: &[String]) -> Option<String> {
+    complete_local(partial).or_else(|| complete_host(partial, hosts))
+}
+
+/// Split an optional `user@` prefix off a partial host name
+fn split_user(partial: &str) -> (&str, &str) {
+    match partial.rfind('@') {
+        Some(at) => partial.split_at(at + 1),
+        None => ("", partial),
+    }
+}
+
+/// Host aliases a partial remote path could be the start of. Only plain
+/// words qualify: once a '/' or ':' is typed the host part is done.
+pub fn matching_hosts<'a>(partial: &str, hosts: &'a [String]) -> Vec<&'a str> {
+    if partial.is_empty() || partial.contains(['/', ':']) || partial.starts_with('~') {
+        return Vec::new();
+    }
+    let (_, host) = split_user(partial);
+    hosts
+        .iter()
+        .filter(|alias| alias.starts_with(host))
+        .map(|alias| alias.as_str())
+        .collect()
+}
+
+/// Complete `[user@]host` to `[user@]alias:`
+fn complete_host(partial: &str, hosts: &[String]) -> Option<String> {
+    let (user, _) = split_user(partial);
+    let matches: Vec<String> = matching_hosts(partial, hosts)
+        .into_iter()
+        .map(|alias| format!("{}{}", user, alias))
+        .collect();
+    match matches.len() {
+        0 => None,
+        1 => Some(format!("{}:", matches[0])),
+        _ => {
+            let common = find_common_prefix(&matches);
+            (common.len() > partial.len()).then_some(common)
+        }
+    }
+}
+
+/// Complete a local path against the directory entries
+fn complete_local(partial: &str) -> Option<String> {
     if partial.is_empty() {
         return None;
     }
@@ -39,7 +97,8 @@ pub fn complete_path(partial: &str) -> Option<String> {
         } else {
             parent
         };
-        let prefix = path.file_name()
+        let prefix = path
+            .file_name()
             .map(|s| s.to_string_lossy().to_string())
             .unwrap_or_default();
         (parent, prefix)
@@ -88,7 +147,7 @@ pub fn complete_path(partial: &str) -> Option<String> {
 }
 
 /// Find the common prefix among multiple strings
-fn find_common_prefix(strings: &[String]) -> String {
+pub fn find_common_prefix(strings: &[String]) -> String {
     if strings.is_empty() {
         return String::new();
     }
@@ -122,23 +181,48 @@ mod tests {
         let home = env::var("HOME").unwrap_or_default();
         if !home.is_empty() {
             let partial = &home[..home.len().saturating_sub(2)];
-            let result = complete_path(partial);
+            let result = complete_path(partial, &[]);
             assert!(result.is_some());
         }
     }
 
     #[test]
     fn test_complete_nonexistent_path() {
-        let result = complete_path("/nonexistent_path_12345/");
+        let result = complete_path("/nonexistent_path_12345/", &[]);
         assert!(result.is_none());
     }
 
     #[test]
     fn test_complete_empty_string() {
-        let result = complete_path("");
+        let result = complete_path("", &[]);
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_complete_host_alias() {
+        let hosts = vec!["nas".to_string(), "web-1".to_string(), "web-2".to_string()];
+        let local = "rsync_tui_no_such_file";
+        assert_eq!(complete_path(local, &hosts), None);
+        assert_eq!(complete_path("na", &hosts).as_deref(), Some("nas:"));
+        assert_eq!(complete_path("bob@na", &hosts).as_deref(), Some("bob@nas:"));
+        assert_eq!(complete_path("we", &hosts).as_deref(), Some("web-"));
+        assert_eq!(complete_path("web-", &hosts), None);
+        assert_eq!(complete_path("nas:", &hosts), None);
+        assert_eq!(matching_hosts("bob@", &hosts).len(), 3);
+        assert!(matching_hosts("/na", &hosts).is_empty());
+    }
+
+    #[test]
+    fn test_is_remote() {
+        assert!(is_remote("host:/data"));
+        assert!(is_remote("user@host:backup"));
+        assert!(is_remote("host::module/path"));
+        assert!(is_remote("rsync://host/module"));
+        assert!(!is_remote("/home/user/a:b"));
+        assert!(!is_remote("./file:1"));
+        assert!(!is_remote("~/docs"));
+    }
+
     #[test]
     fn test_find_common_prefix() {
         let strings = vec![
@@ -165,7 +249,7 @@ mod tests {
     fn test_complete_relative_path() {
         // Test relative path completion (from current directory)
         // This assumes we're in a directory with a "src" folder
-        let result = complete_path("sr");
+        let result = complete_path("sr", &[]);
         // Should either complete to something or return None (depending on cwd)
         // Just verify it doesn't panic
         let _ = result;
@@ -0,0 +1,472 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use crate::rsync::command::command_env;
+use crate::rsync::options::RsyncOptions;
+use crate::rsync::stats::TransferStats;
+use crate::safety::SafetyPolicy;
+
+/// Version written to new profile files. Bump it when a change needs a
+/// migration; fields that only get added should use `#[serde(default)]`
+/// so older files keep loading.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Name of the profile used when none is chosen
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// A named, saved sync setup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Schema version the file was written with
+    #[serde(default = "first_version")]
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub source: String,
+    #[serde(default)]
+    pub destination: String,
+    #[serde(default)]
+    pub options: RsyncOptions,
+    #[serde(default)]
+    pub policy: SafetyPolicy,
+    /// Count rsync's exit code 24 (source files vanished during the
+    /// transfer) as success
+    #[serde(default)]
+    pub vanished_ok: bool,
+    #[serde(default)]
+    pub last_run: Option<LastRun>,
+}
+
+/// When the profile last ran and how it ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LastRun {
+    /// Seconds since the Unix epoch
+    pub finished_at: u64,
+    /// rsync exit code (None if it was killed or could not be waited on)
+    pub exit_code: Option<i32>,
+    /// Summary from `--stats`, when it was on
+    #[serde(default)]
+    pub stats: Option<TransferStats>,
+}
+
+/// Files written before versioning was introduced are version 1
+fn first_version() -> u32 {
+    1
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            name: name.to_string(),
+            description: String::new(),
+            source: String::new(),
+            destination: String::new(),
+            options: RsyncOptions::default(),
+            policy: SafetyPolicy::default(),
+            vanished_ok: false,
+            last_run: None,
+        }
+    }
+}
+
+/// Directory holding profile files:
+/// `$XDG_CONFIG_HOME/rsync_tui/profiles`, or `~/.config/rsync_tui/profiles`
+pub fn profiles_dir() -> Option<PathBuf> {
+    let config = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var("HOME").ok()?).join(".config"),
+    };
+    Some(config.join("rsync_tui").join("profiles"))
+}
+
+/// File name for a profile, with unsafe characters replaced
+pub fn file_name(name: &str) -> String {
+    format!("{}.json", safe_name(name))
+}
+
+/// Profile name with characters unsafe in file names replaced by '-'
+pub fn safe_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Write a profile to `dir`, returning the file path
+pub fn save(dir: &Path, profile: &Profile) -> anyhow::Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+
+    let path = dir.join(file_name(&profile.name));
+    check_overwrite(&path, profile)?;
+    let mut profile = profile.clone();
+    profile.version = SCHEMA_VERSION;
+    let json = serde_json::to_string_pretty(&profile)?;
+
+    // Write to a temporary file first so a crash can't leave a truncated profile
+    let tmp = path.with_extension("json.tmp");
+    // Profiles holding a daemon password are readable only by their owner,
+    // from before the password is written
+    let mode = if command_env(&profile.options).is_empty() {
+        0o666
+    } else {
+        0o600
+    };
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(mode)
+        .open(&tmp)
+        .with_context(|| format!("writing {}", tmp.display()))?;
+    // A temporary file left by a crash keeps its mode when opened
+    if mode == 0o600 {
+        file.set_permissions(fs::Permissions::from_mode(mode))
+            .with_context(|| format!("writing {}", tmp.display()))?;
+    }
+    file.write_all(json.as_bytes())
+        .with_context(|| format!("writing {}", tmp.display()))?;
+    fs::rename(&tmp, &path).with_context(|| format!("writing {}", path.display()))?;
+    Ok(path)
+}
+
+/// Refuse to replace a file written by a newer version, whose fields unknown
+/// here would be lost, or one holding another profile whose name maps to the
+/// same file
+fn check_overwrite(path: &Path, profile: &Profile) -> anyhow::Result<()> {
+    // A file that is missing or can't be parsed has nothing worth keeping
+    let existing = load(path).ok();
+    let version = existing
+        .as_ref()
+        .map_or(profile.version, |p| p.version.max(profile.version));
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "profile '{}' was saved by a newer version of rsync_tui (schema {}); \
+             this one (schema {}) would lose its settings",
+            profile.name,
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    match existing {
+        Some(other) if other.name != profile.name => anyhow::bail!(
+            "profile '{}' would replace profile '{}' in {}",
+            profile.name,
+            other.name,
+            path.display()
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Read a profile file
+pub fn load(path: &Path) -> anyhow::Result<Profile> {
+    let json = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let profile: Profile =
+        serde_json::from_str(&json).with_context(|| format!("parsing {}", path.display()))?;
+    Ok(profile)
+}
+
+/// Read the profile with the given name from `dir`
+pub fn load_named(dir: &Path, name: &str) -> anyhow::Result<Profile> {
+    load(&dir.join(file_name(name)))
+}
+
+/// Whether a profile with this name has been saved
+pub fn exists(dir: &Path, name: &str) -> bool {
+    dir.join(file_name(name)).exists()
+}
+
+/// All saved profiles sorted by name. Files that fail to parse are skipped.
+pub fn list(dir: &Path) -> anyhow::Result<Vec<Profile>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))?;
+    let mut profiles: Vec<Profile> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|p| load(&p).ok())
+        .collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(profiles)
+}
+
+/// Delete a saved profile
+pub fn delete(dir: &Path, name: &str) -> anyhow::Result<()> {
+    let path = dir.join(file_name(name));
+    fs::remove_file(&path).with_context(|| format!("deleting {}", path.display()))
+}
+
+/// Save `profile` under a new name, refusing to overwrite another profile
+pub fn save_as(dir: &Path, profile: &Profile, name: &str) -> anyhow::Result<PathBuf> {
+    if name.trim().is_empty() {
+        anyhow::bail!("profile name cannot be empty");
+    }
+    if exists(dir, name) {
+        match load_named(dir, name) {
+            Ok(other) if other.name != name => anyhow::bail!(
+                "'{}' would be saved in the same file as profile '{}'",
+                name,
+                other.name
+            ),
+            _ => anyhow::bail!("a profile named '{}' already exists", name),
+        }
+    }
+    let mut profile = profile.clone();
+    profile.name = name.to_string();
+    save(dir, &profile)
+}
+
+/// Rename a saved profile
+pub fn rename(dir: &Path, from: &str, to: &str) -> anyhow::Result<()> {
+    let profile = load_named(dir, from)?;
+    save_as(dir, &profile, to)?;
+    delete(dir, from)
+}
+
+/// Copy a saved profile under a new name, without its run status
+pub fn duplicate(dir: &Path, from: &str, to: &str) -> anyhow::Result<()> {
+    let mut profile = load_named(dir, from)?;
+    profile.last_run = None;
+    save_as(dir, &profile, to)?;
+    Ok(())
+}
+
+/// Store the result of a run in the profile file, if it has been saved
+pub fn record_run(dir: &Path, name: &str, run: &LastRun) -> anyhow::Result<()> {
+    if !exists(dir, name) {
+        return Ok(());
+    }
+    let mut profile = load_named(dir, name)?;
+    // The file belongs to another profile whose name maps to it
+    if profile.name != name {
+        return Ok(());
+    }
+    profile.last_run = Some(run.clone());
+    save(dir, &profile)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rsync::filter::{FilterRule, RuleKind};
+
+    #[test]
+    fn test_file_name_sanitized() {
+        assert_eq!(file_name("nas-backup"), "nas-backup.json");
+        assert_eq!(file_name("laptop → NAS/photos"), "laptop---NAS-photos.json");
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut profile = Profile::new("nas-backup");
+        profile.description = "Laptop to NAS".to_string();
+        profile.source = "/home/user/".to_string();
+        profile.destination = "nas:/backup".to_string();
+        profile.options.set_enabled("delete", true);
+        profile.options.filters = vec![FilterRule::new(RuleKind::Exclude, "*.tmp")];
+        profile.policy.max_deletions = Some(100);
+
+        let path = save(dir, &profile).unwrap();
+        assert_eq!(path, dir.join("nas-backup.json"));
+
+        let loaded = load_named(dir, "nas-backup").unwrap();
+        assert_eq!(loaded, profile);
+    }
+
+    #[test]
+    fn test_profile_with_password_is_private() {
+        use crate::rsync::options::OptionValue;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut profile = Profile::new("nas");
+        profile
+            .options
+            .set("daemon_password", Some(OptionValue::Text("s3cret".into())))
+            .unwrap();
+
+        // A readable temporary file left behind by an interrupted save
+        let stale = dir.join("nas.json.tmp");
+        fs::write(&stale, "").unwrap();
+        fs::set_permissions(&stale, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let path = save(dir, &profile).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(load(&path).unwrap(), profile);
+    }
+
+    #[test]
+    fn test_load_old_file_without_new_fields() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("old.json");
+        fs::write(
+            &path,
+            r#"{"name": "old", "source": "/a", "options": {"delete": true}}"#,
+        )
+        .unwrap();
+
+        let profile = load(&path).unwrap();
+        assert_eq!(profile.version, 1);
+        assert_eq!(profile.source, "/a");
+        assert!(profile.destination.is_empty());
+        assert!(profile.options.is_enabled("delete"));
+        assert!(profile.options.is_enabled("archive"));
+        assert_eq!(profile.policy, SafetyPolicy::default());
+    }
+
+    #[test]
+    fn test_load_ignores_unknown_fields() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("future.json");
+        fs::write(
+            &path,
+            r#"{"version": 9, "name": "future", "colour": "blue"}"#,
+        )
+        .unwrap();
+
+        let profile = load(&path).unwrap();
+        assert_eq!(profile.version, 9);
+        assert_eq!(profile.name, "future");
+    }
+
+    #[test]
+    fn test_list_sorted_and_skips_invalid() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        save(dir, &Profile::new("zeta")).unwrap();
+        save(dir, &Profile::new("alpha")).unwrap();
+        fs::write(dir.join("broken.json"), "{not json").unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let names: Vec<String> = list(dir).unwrap().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["alpha", "zeta"]);
+    }
+
+    #[test]
+    fn test_list_missing_dir_is_empty() {
+        assert!(list(&tempfile::tempdir().unwrap().path().join("absent"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_rename_duplicate_delete() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut profile = Profile::new("nas");
+        profile.source = "/data".to_string();
+        profile.last_run = Some(LastRun {
+            finished_at: 1,
+            exit_code: Some(0),
+            stats: None,
+        });
+        save(dir, &profile).unwrap();
+
+        rename(dir, "nas", "nas-backup").unwrap();
+        assert!(!exists(dir, "nas"));
+        assert_eq!(load_named(dir, "nas-backup").unwrap().source, "/data");
+
+        duplicate(dir, "nas-backup", "offsite").unwrap();
+        let copy = load_named(dir, "offsite").unwrap();
+        assert_eq!(copy.name, "offsite");
+        assert_eq!(copy.source, "/data");
+        assert_eq!(copy.last_run, None);
+
+        // Existing names are not overwritten
+        assert!(duplicate(dir, "nas-backup", "offsite").is_err());
+        assert!(save_as(dir, &profile, " ").is_err());
+
+        delete(dir, "offsite").unwrap();
+        assert!(!exists(dir, "offsite"));
+    }
+
+    #[test]
+    fn test_record_run_only_for_saved_profiles() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let run = LastRun {
+            finished_at: 42,
+            exit_code: Some(23),
+            stats: None,
+        };
+
+        record_run(dir, "unsaved", &run).unwrap();
+        assert!(!exists(dir, "unsaved"));
+
+        save(dir, &Profile::new("saved")).unwrap();
+        record_run(dir, "saved", &run).unwrap();
+        assert_eq!(load_named(dir, "saved").unwrap().last_run, Some(run));
+    }
+
+    #[test]
+    fn test_newer_profile_is_not_overwritten() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("future.json");
+        let json = r#"{"version": 9, "name": "future", "colour": "blue"}"#;
+        fs::write(&path, json).unwrap();
+
+        let err = save(dir, &Profile::new("future")).unwrap_err();
+        assert!(err.to_string().contains("newer version"));
+        let run = LastRun {
+            finished_at: 42,
+            exit_code: Some(0),
+            stats: None,
+        };
+        assert!(record_run(dir, "future", &run).is_err());
+        let mut loaded = load(&path).unwrap();
+        assert!(save(dir, &loaded).is_err());
+        loaded.name = "copy".to_string();
+        assert!(save(dir, &loaded).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), json);
+    }
+
+    #[test]
+    fn test_names_sharing_a_file_are_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        save(dir, &Profile::new("nas backup")).unwrap();
+        assert_eq!(file_name("nas/backup"), file_name("nas backup"));
+
+        let err = save_as(dir, &Profile::new("x"), "nas/backup").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'nas/backup' would be saved in the same file as profile 'nas backup'"
+        );
+        assert!(save(dir, &Profile::new("nas/backup")).is_err());
+        let run = LastRun {
+            finished_at: 42,
+            exit_code: Some(0),
+            stats: None,
+        };
+        record_run(dir, "nas/backup", &run).unwrap();
+        let kept = load_named(dir, "nas backup").unwrap();
+        assert_eq!(kept.name, "nas backup");
+        assert_eq!(kept.last_run, None);
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        assert!(load_named(&tempfile::tempdir().unwrap().path().join("missing"), "nope").is_err());
+    }
+}
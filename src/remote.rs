@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+use std::fs::{self, DirBuilder};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use tokio::process::Command;
+use tokio::sync::oneshot;
+
+use crate::path::{self, find_common_prefix};
+use crate::rsync::command::shell_quote;
+
+/// How long a directory listing is reused before it is read again
+pub const CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Listing a remote directory gives up after this long
+pub const LIST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Split `[user@]host:path` into the ssh target and the path on the host.
+/// Local paths, `host::module` and `rsync://` URLs give None.
+pub fn split_remote(path: &str) -> Option<(&str, &str)> {
+    if !path::is_remote(path) || path.starts_with("rsync://") {
+        return None;
+    }
+    let (host, rest) = path.split_once(':')?;
+    if host.is_empty() || rest.starts_with(':') {
+        return None;
+    }
+    Some((host, rest))
+}
+
+/// Directory to list and the name being completed: `/var/ba` gives
+/// (`/var/`, `ba`), and a bare name is looked up in the home directory
+fn split_dir(path: &str) -> (&str, &str) {
+    match path.rfind('/') {
+        Some(slash) => path.split_at(slash + 1),
+        None => ("", path),
+    }
+}
+
+/// ssh command listing `dir` on `host`, one name per line with `/` after
+/// directories. Connections are shared through a ControlMaster socket kept
+/// open for a minute, so each Tab after the first reuses the login.
+pub fn list_command(shell: &[String], host: &str, dir: &str) -> Vec<String> {
+    let mut args = shell.to_vec();
+    args.extend(["-o".to_string(), "BatchMode=yes".to_string()]);
+    // Without a private directory for the socket every listing logs in again
+    if let Some(control_dir) = control_dir() {
+        for option in [
+            "ControlMaster=auto".to_string(),
+            format!("ControlPath={}", control_dir.join("%C").display()),
+            "ControlPersist=60".to_string(),
+        ] {
+            args.extend(["-o".to_string(), option]);
+        }
+    }
+    // rsync resolves relative remote paths from the home directory, which is
+    // where the remote shell starts
+    let dir = dir.strip_prefix("~/").unwrap_or(dir);
+    let dir = if dir.is_empty() || dir == "~" {
+        "."
+    } else {
+        dir
+    };
+    args.push(host.to_string());
+    args.push(format!("ls -1Ap -- {}", shell_quote(dir)));
+    args
+}
+
+/// Directory holding the ControlMaster sockets: `$XDG_RUNTIME_DIR/rsync_tui`,
+/// or `rsync_tui-<uid>` in the temporary directory when that isn't set
+fn control_dir() -> Option<PathBuf> {
+    // SAFETY: geteuid has no preconditions and can't fail
+    let uid = unsafe { libc::geteuid() };
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from) {
+        Some(runtime) if runtime.is_absolute() => runtime.join("rsync_tui"),
+        _ => std::env::temp_dir().join(format!("rsync_tui-{}", uid)),
+    };
+    private_dir(&dir, uid).then_some(dir)
+}
+
+/// Create `dir` with mode 0700 if it is missing, and check that it is a real
+/// directory owned by `uid` that nobody else can use. Anyone able to write
+/// there could plant a socket ssh would send our sessions through.
+fn private_dir(dir: &Path, uid: u32) -> bool {
+    let _ = DirBuilder::new().mode(0o700).create(dir);
+    match fs::symlink_metadata(dir) {
+        Ok(meta) => meta.is_dir() && meta.uid() == uid && meta.mode() & 0o077 == 0,
+        Err(_) => false,
+    }
+}
+
+/// Run a listing command, giving up after `timeout`
+pub async fn list(args: Vec<String>, timeout: Duration) -> Listing {
+    let child = Command::new(&args[0])
+        .args(&args[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("cannot run {}: {}", args[0], e))?;
+
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output.map_err(|e| e.to_string())?,
+        Err(_) => return Err(format!("timed out after {}s", timeout.as_secs_f32())),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match stderr.lines().find(|l| !l.trim().is_empty()) {
+            Some(line) => line.trim().to_string(),
+            None => format!("exited with {}", output.status),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Complete `host:dir` + `name` from the entries of `dir`
+fn complete_name(host: &str, dir: &str, name: &str, entries: &[String]) -> Option<String> {
+    let matches: Vec<String> = entries
+        .iter()
+        .filter(|entry| entry.starts_with(name))
+        .map(|entry| format!("{}:{}{}", host, dir, entry))
+        .collect();
+    match matches.len() {
+        0 => None,
+        1 => Some(matches[0].clone()),
+        _ => {
+            let common = find_common_prefix(&matches);
+            (common.len() > host.len() + 1 + dir.len() + name.len()).then_some(common)
+        }
+    }
+}
+
+/// Result of asking for a remote completion
+#[derive(Debug, PartialEq)]
+pub enum Completion {
+    /// Completed text, or None when nothing more can be added
+    Done(Option<String>),
+    /// The directory is being listed; `poll` gives the completion later
+    Listing,
+    Failed(String),
+}
+
+/// Entries of a remote directory, or why it couldn't be listed
+pub type Listing = Result<Vec<String>, String>;
+
+/// Listing in flight and the text it was started for
+struct Pending {
+    key: (String, String),
+    partial: String,
+    result: oneshot::Receiver<Listing>,
+}
+
+/// Remote directory listings, cached per host and directory, fetched in the
+/// background so a slow host never blocks typing
+#[derive(Default)]
+pub struct RemoteCompleter {
+    cache: HashMap<(String, String), (Instant, Listing)>,
+    pending: Option<Pending>,
+}
+
+impl RemoteCompleter {
+    /// Complete a `host:path` from the cache, or start listing its directory
+    /// with `shell` (the ssh program and its options). Must be called from
+    /// within a tokio runtime.
+    pub fn complete(&mut self, partial: &str, shell: &[String]) -> Completion {
+        let Some((host, path)) = split_remote(partial) else {
+            return Completion::Done(None);
+        };
+        if let Some(completion) = self.cached(partial) {
+            return completion;
+        }
+
+        let key = (host.to_string(), split_dir(path).0.to_string());
+        if self.pending.as_ref().map(|p| &p.key) != Some(&key) {
+            let (tx, rx) = oneshot::channel();
+            let args = list_command(shell, &key.0, &key.1);
+            tokio::spawn(async move {
+                let _ = tx.send(list(args, LIST_TIMEOUT).await);
+            });
+            self.pending = Some(Pending {
+                key,
+                partial: String::new(),
+                result: rx,
+            });
+        }
+        if let Some(pending) = self.pending.as_mut() {
+            pending.partial = partial.to_string();
+        }
+        Completion::Listing
+    }
+
+    /// Completion from a listing fetched less than `CACHE_TTL` ago
+    fn cached(&self, partial: &str) -> Option<Completion> {
+        let (host, path) = split_remote(partial)?;
+        let (dir, name) = split_dir(path);
+        let (fetched, result) = self.cache.get(&(host.to_string(), dir.to_string()))?;
+        if fetched.elapsed() >= CACHE_TTL {
+            return None;
+        }
+        Some(match result {
+            Ok(entries) => Completion::Done(complete_name(host, dir, name, entries)),
+            Err(e) => Completion::Failed(e.clone()),
+        })
+    }
+
+    /// Whether a listing is in flight
+    pub fn is_listing(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Collect a finished listing: the text it was asked for and its
+    /// completion
+    pub fn poll(&mut self) -> Option<(String, Completion)> {
+        let pending = self.pending.as_mut()?;
+        let result = match pending.result.try_recv() {
+            Ok(result) => result,
+            Err(oneshot::error::TryRecvError::Empty) => return None,
+            Err(oneshot::error::TryRecvError::Closed) => Err("listing stopped".to_string()),
+        };
+        let pending = self.pending.take()?;
+        self.cache.insert(pending.key, (Instant::now(), result));
+        let completion = self
+            .cached(&pending.partial)
+            .unwrap_or(Completion::Done(None));
+        Some((pending.partial, completion))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Executable stand-in for ssh that records its arguments
+    fn stub_ssh(body: &str) -> (tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("ssh");
+        fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho \"$@\" > {}/args\n{}\n",
+                dir.path().display(),
+                body
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        (dir, script.display().to_string())
+    }
+
+    #[test]
+    fn test_split_remote() {
+        assert_eq!(split_remote("web:/var/ba"), Some(("web", "/var/ba")));
+        assert_eq!(split_remote("bob@web:docs"), Some(("bob@web", "docs")));
+        assert_eq!(split_remote("web::module"), None);
+        assert_eq!(split_remote("rsync://web/module"), None);
+        assert_eq!(split_remote("/local/a:b"), None);
+        assert_eq!(split_dir("/var/ba"), ("/var/", "ba"));
+        assert_eq!(split_dir("ba"), ("", "ba"));
+    }
+
+    #[test]
+    fn test_list_command() {
+        let args = list_command(
+            &["ssh".to_string(), "-p".to_string(), "2222".to_string()],
+            "web",
+            "/srv/my files/",
+        );
+        assert_eq!(&args[..3], ["ssh", "-p", "2222"]);
+        assert!(args.contains(&"ControlMaster=auto".to_string()));
+        assert_eq!(args[args.len() - 2], "web");
+        assert_eq!(args[args.len() - 1], "ls -1Ap -- '/srv/my files/'");
+        assert_eq!(
+            list_command(&["ssh".to_string()], "web", "~/")
+                .last()
+                .unwrap(),
+            "ls -1Ap -- ."
+        );
+    }
+
+    #[test]
+    fn test_private_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        let uid = fs::metadata(base).unwrap().uid();
+
+        // Created private
+        let dir = base.join("control");
+        assert!(private_dir(&dir, uid));
+        assert_eq!(fs::metadata(&dir).unwrap().mode() & 0o777, 0o700);
+        assert!(private_dir(&dir, uid));
+
+        // Someone else's directory
+        assert!(!private_dir(&dir, uid + 1));
+
+        // Readable or writable by others
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(!private_dir(&dir, uid));
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).unwrap();
+
+        // A symlink, even to a private directory
+        let link = base.join("link");
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+        assert!(!private_dir(&link, uid));
+
+        // A file in the way
+        let file = base.join("file");
+        fs::write(&file, "").unwrap();
+        assert!(!private_dir(&file, uid));
+    }
+
+    #[test]
+    fn test_complete_name() {
+        let entries: Vec<String> = ["backups/", "bak.tar", "log/"].map(String::from).to_vec();
+        assert_eq!(
+            complete_name("web", "/var/", "l", &entries).as_deref(),
+            Some("web:/var/log/")
+        );
+        assert_eq!(
+            complete_name("web", "/var/", "b", &entries).as_deref(),
+            Some("web:/var/ba")
+        );
+        assert_eq!(complete_name("web", "/var/", "ba", &entries), None);
+        assert_eq!(complete_name("web", "/var/", "x", &entries), None);
+    }
+
+    #[tokio::test]
+    async fn test_completion_is_listed_then_cached() {
+        let (dir, ssh) = stub_ssh("printf 'backups/\\nlog/\\n'");
+        let mut completer = RemoteCompleter::default();
+
+        assert_eq!(
+            completer.complete("web:/var/ba", std::slice::from_ref(&ssh)),
+            Completion::Listing
+        );
+        assert!(completer.is_listing());
+        let (partial, completion) = loop {
+            if let Some(done) = completer.poll() {
+                break done;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(partial, "web:/var/ba");
+        assert_eq!(
+            completion,
+            Completion::Done(Some("web:/var/backups/".to_string()))
+        );
+        assert!(fs::read_to_string(dir.path().join("args"))
+            .unwrap()
+            .ends_with("web ls -1Ap -- /var/\n"));
+
+        // Served from the cache without running ssh again
+        fs::remove_file(&ssh).unwrap();
+        assert_eq!(
+            completer.complete("web:/var/l", &[ssh]),
+            Completion::Done(Some("web:/var/log/".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_times_out_and_reports_errors() {
+        let (_dir, ssh) = stub_ssh("sleep 5");
+        let started = Instant::now();
+        let result = list(vec![ssh], Duration::from_millis(200)).await;
+        assert!(result.unwrap_err().starts_with("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let (_failing_dir, ssh) = stub_ssh("echo 'Permission denied' >&2; exit 255");
+        assert_eq!(
+            list(vec![ssh], LIST_TIMEOUT).await.unwrap_err(),
+            "Permission denied"
+        );
+    }
+}
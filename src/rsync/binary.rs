@@ -0,0 +1,402 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::catalog::{OptionSpec, CATALOG};
+use super::options::{OptionValue, RsyncOptions};
+
+/// Environment variable naming the rsync binary; `--rsync` takes precedence
+pub const RSYNC_ENV: &str = "RSYNC_TUI_RSYNC";
+
+/// An rsync release such as (3, 2, 7)
+pub type Version = (u32, u32, u32);
+
+/// The rsync binary transfers run with and what its `--version` reports
+#[derive(Debug, Clone, PartialEq)]
+pub struct RsyncInfo {
+    /// Program as configured, run for every transfer and listing
+    pub program: String,
+    /// Where the program was found on PATH
+    pub path: Option<PathBuf>,
+    /// None until probed, or when the output wasn't recognised
+    pub version: Option<Version>,
+    pub protocol: Option<u32>,
+    /// openrsync, which only claims compatibility with an rsync version
+    pub openrsync: bool,
+    /// "Capabilities:" entries, normalised by `capability_key`
+    pub capabilities: Vec<String>,
+    pub checksums: Vec<String>,
+    pub compressions: Vec<String>,
+    /// Why the binary couldn't be run
+    pub error: Option<String>,
+}
+
+impl Default for RsyncInfo {
+    fn default() -> Self {
+        Self::new("rsync")
+    }
+}
+
+impl RsyncInfo {
+    /// An rsync that hasn't been probed: nothing is gated
+    pub fn new(program: &str) -> Self {
+        Self {
+            program: program.to_string(),
+            path: None,
+            version: None,
+            protocol: None,
+            openrsync: false,
+            capabilities: Vec::new(),
+            checksums: Vec::new(),
+            compressions: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Short description for the title bar and logs
+    pub fn describe(&self) -> String {
+        let name = if self.openrsync { "openrsync" } else { "rsync" };
+        let mut text = match self.version {
+            Some(version) if self.openrsync => {
+                format!("{} ({} compatible)", name, format_version(version))
+            }
+            Some(version) => format!("{} {}", name, format_version(version)),
+            None => format!("{} (unknown version)", name),
+        };
+        if let Some(protocol) = self.protocol {
+            text.push_str(&format!(", protocol {}", protocol));
+        }
+        text
+    }
+
+    fn has(&self, capability: &str) -> bool {
+        let key = capability_key(capability);
+        self.capabilities.contains(&key)
+    }
+
+    /// Values the installed rsync accepts for an enum option, when its
+    /// `--version` lists them
+    fn choices(&self, spec: &OptionSpec) -> &[String] {
+        match spec.flag {
+            "--checksum-choice" => &self.checksums,
+            "--compress-choice" => &self.compressions,
+            _ => &[],
+        }
+    }
+
+    /// Why `spec`, set to `value`, won't work with this rsync. Nothing is
+    /// reported for a binary whose version is unknown.
+    pub fn unsupported(&self, spec: &OptionSpec, value: Option<&OptionValue>) -> Option<String> {
+        let version = self.version?;
+        if let Some(since) = spec.since {
+            if version < since {
+                return Some(format!(
+                    "needs rsync {}, found {}",
+                    format_version(since),
+                    self.describe()
+                ));
+            }
+        }
+        // openrsync prints no capabilities and supports none of them
+        if let Some(capability) = spec.capability {
+            if (self.openrsync || !self.capabilities.is_empty()) && !self.has(capability) {
+                return Some(format!(
+                    "{} was built without {}",
+                    self.describe(),
+                    capability
+                ));
+            }
+        }
+        if let Some(OptionValue::Text(choice)) = value {
+            let choices = self.choices(spec);
+            if choice != "auto" && !choices.is_empty() && !choices.contains(choice) {
+                return Some(format!(
+                    "{} supports only {}",
+                    self.describe(),
+                    choices.join(", ")
+                ));
+            }
+        }
+        None
+    }
+
+    /// Warnings for every option that is on or set but unsupported
+    pub fn warnings(&self, options: &RsyncOptions) -> Vec<String> {
+        CATALOG
+            .iter()
+            .filter_map(|spec| {
+                let value = options.value(spec.id);
+                let active = match value {
+                    Some(OptionValue::Bool(on)) => *on,
+                    Some(_) => true,
+                    None => false,
+                };
+                if !active {
+                    return None;
+                }
+                let reason = self.unsupported(spec, value)?;
+                Some(format!("{}: {}", spec.flag, reason))
+            })
+            .collect()
+    }
+}
+
+/// "3.2.7"
+pub fn format_version((major, minor, patch): Version) -> String {
+    format!("{}.{}.{}", major, minor, patch)
+}
+
+/// Capabilities compare without case, spaces or dashes, so 2.6.9's
+/// "hard links" matches 3.x's "hardlinks"
+fn capability_key(capability: &str) -> String {
+    capability
+        .chars()
+        .filter(|c| *c != ' ' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// "3.2.7pre1" → (3, 2, 7); a missing patch level is 0
+fn parse_release(text: &str) -> Option<Version> {
+    let mut numbers = text.split('.').map(|part| {
+        let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse::<u32>().ok()
+    });
+    let major = numbers.next()??;
+    let minor = numbers.next()??;
+    let patch = numbers.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+/// Read the output of `rsync --version`
+pub fn parse_version(program: &str, output: &str) -> RsyncInfo {
+    let mut info = RsyncInfo::new(program);
+    let mut section = String::new();
+
+    for line in output.lines() {
+        if line.contains("openrsync") {
+            info.openrsync = true;
+        }
+        let indented = line.starts_with(char::is_whitespace);
+        let mut content = line.trim();
+
+        if !indented {
+            section.clear();
+            // A header such as "Capabilities:" may carry entries after it
+            if let Some((name, rest)) = content.split_once(':') {
+                if !name.contains(char::is_whitespace) || name.ends_with("list") {
+                    section = name.to_ascii_lowercase();
+                    content = rest.trim();
+                }
+            }
+            let words: Vec<&str> = content.split_whitespace().collect();
+            for (i, pair) in words.windows(2).enumerate() {
+                if pair[0] != "version" {
+                    continue;
+                }
+                if i > 0 && words[i - 1] == "protocol" {
+                    info.protocol = info.protocol.or_else(|| pair[1].parse().ok());
+                } else {
+                    info.version = info.version.or_else(|| parse_release(pair[1]));
+                }
+            }
+        }
+
+        match section.as_str() {
+            "capabilities" => info.capabilities.extend(
+                content
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|c| !c.is_empty() && !c.starts_with("no "))
+                    .map(|c| capability_key(c.trim_start_matches("optional "))),
+            ),
+            "checksum list" | "compress list" => {
+                let names = content
+                    .split_whitespace()
+                    .filter(|name| !name.starts_with('('))
+                    .map(String::from);
+                if section == "checksum list" {
+                    info.checksums.extend(names);
+                } else {
+                    info.compressions.extend(names);
+                }
+            }
+            _ => {}
+        }
+    }
+    info
+}
+
+/// First executable named `program` in `PATH`, or `program` itself when it
+/// is a path
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = Path::new(program);
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
+
+/// Find `program` and run `program --version`
+pub fn probe(program: &str) -> RsyncInfo {
+    let mut info = match Command::new(program).arg("--version").output() {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            // openrsync prints its version to stderr
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            let info = parse_version(program, &text);
+            if info.version.is_none() && !output.status.success() {
+                RsyncInfo {
+                    error: Some(format!("{} --version failed ({})", program, output.status)),
+                    ..info
+                }
+            } else {
+                info
+            }
+        }
+        Err(e) => RsyncInfo {
+            error: Some(format!("cannot run {}: {}", program, e)),
+            ..RsyncInfo::new(program)
+        },
+    };
+    info.path = find_in_path(program);
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rsync::catalog;
+
+    const RSYNC_3_2: &str = "\
+rsync  version 3.2.7  protocol version 31
+Copyright (C) 1996-2022 by Andrew Tridgell, Wayne Davison, and others.
+Web site: https://rsync.samba.org/
+Capabilities:
+    64-bit files, 64-bit inums, 64-bit timestamps, 64-bit long ints,
+    socketpairs, symlinks, symtimes, hardlinks, hardlink-specials,
+    hardlink-symlinks, IPv6, atimes, batchfiles, inplace, append, ACLs,
+    xattrs, optional secluded-args, iconv, prealloc, stop-at, no crtimes
+Optimizations:
+    SIMD-roll, no asm-roll, openssl-crypto, no asm-MD5
+Checksum list:
+    xxh128 xxh3 xxh64 (xxhash) md5 md4 sha1 none
+Compress list:
+    zstd lz4 zlibx zlib none
+Daemon auth list:
+    sha512 sha256 sha1 md5 md4
+
+rsync comes with ABSOLUTELY NO WARRANTY.  This is free software, and you
+are welcome to redistribute it under certain conditions.  See the GNU
+General Public Licence for details.
+";
+
+    const RSYNC_2_6_9: &str = "\
+rsync  version 2.6.9  protocol version 29
+Copyright (C) 1996-2006 by Andrew Tridgell, Wayne Davison, and others.
+<http://rsync.samba.org/>
+Capabilities: 64-bit files, socketpairs, hard links, symlinks, batchfiles,
+              inplace, IPv6, 64-bit system inums, 64-bit internal inums
+";
+
+    const OPENRSYNC: &str = "\
+openrsync: protocol version 29
+rsync version 2.6.9 compatible
+";
+
+    #[test]
+    fn test_parse_rsync_3_2() {
+        let info = parse_version("rsync", RSYNC_3_2);
+        assert_eq!(info.version, Some((3, 2, 7)));
+        assert_eq!(info.protocol, Some(31));
+        assert!(!info.openrsync);
+        assert!(info.has("ACLs") && info.has("xattrs") && info.has("iconv"));
+        assert!(info.has("secluded-args"));
+        assert!(!info.has("crtimes"));
+        assert!(!info.has("asm-roll"));
+        assert_eq!(
+            info.checksums,
+            ["xxh128", "xxh3", "xxh64", "md5", "md4", "sha1", "none"]
+        );
+        assert_eq!(info.compressions, ["zstd", "lz4", "zlibx", "zlib", "none"]);
+        assert_eq!(info.describe(), "rsync 3.2.7, protocol 31");
+    }
+
+    #[test]
+    fn test_parse_old_and_openrsync() {
+        let old = parse_version("rsync", RSYNC_2_6_9);
+        assert_eq!(old.version, Some((2, 6, 9)));
+        assert_eq!(old.protocol, Some(29));
+        assert!(old.has("hardlinks") && old.has("inplace"));
+        assert!(!old.has("ACLs"));
+
+        let open = parse_version("rsync", OPENRSYNC);
+        assert!(open.openrsync);
+        assert_eq!(open.version, Some((2, 6, 9)));
+        assert_eq!(open.protocol, Some(29));
+        assert_eq!(open.describe(), "openrsync (2.6.9 compatible), protocol 29");
+    }
+
+    #[test]
+    fn test_unsupported_options() {
+        let spec = |id| catalog::find(id).unwrap();
+        let text = |t: &str| OptionValue::Text(t.to_string());
+
+        let old = parse_version("rsync", RSYNC_2_6_9);
+        assert!(old
+            .unsupported(spec("progress_total"), None)
+            .unwrap()
+            .starts_with("needs rsync 3.1.0"));
+        assert!(old
+            .unsupported(spec("acls"), None)
+            .unwrap()
+            .ends_with("built without ACLs"));
+        assert_eq!(old.unsupported(spec("hard_links"), None), None);
+        assert_eq!(old.unsupported(spec("delete"), None), None);
+
+        let new = parse_version("rsync", RSYNC_3_2);
+        assert_eq!(new.unsupported(spec("acls"), None), None);
+        assert_eq!(
+            new.unsupported(spec("compress_choice"), Some(&text("zstd"))),
+            None
+        );
+        assert!(new
+            .unsupported(spec("checksum_choice"), Some(&text("blake3")))
+            .is_some());
+
+        let open = parse_version("rsync", OPENRSYNC);
+        assert!(open.unsupported(spec("hard_links"), None).is_some());
+
+        // Without a version nothing is gated
+        assert_eq!(
+            RsyncInfo::new("rsync").unsupported(spec("acls"), None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_warnings_for_active_options() {
+        let old = parse_version("rsync", RSYNC_2_6_9);
+        let mut options = RsyncOptions::default();
+        assert!(old.warnings(&options).is_empty());
+        options.set_enabled("xattrs", true);
+        options
+            .set("checksum_choice", Some(OptionValue::Text("md5".into())))
+            .unwrap();
+        let warnings = old.warnings(&options);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("--xattrs: "));
+        assert!(warnings[1].starts_with("--checksum-choice: needs rsync 3.2.0"));
+    }
+
+    #[test]
+    fn test_probe_missing_binary() {
+        let info = probe("rsync_tui_no_such_rsync");
+        assert!(info.error.unwrap().starts_with("cannot run"));
+        assert_eq!(info.version, None);
+        assert_eq!(info.path, None);
+        assert_eq!(find_in_path("sh").map(|p| p.is_absolute()), Some(true));
+    }
+}
@@ -0,0 +1,506 @@
+/// Kind of value an option takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    /// On/off flag
+    Bool,
+    /// Whole number, passed as `--flag=N`
+    Int,
+    /// Free text, passed as `--flag=TEXT`
+    Text,
+    /// One of a fixed set of choices, passed as `--flag=CHOICE`
+    Enum(&'static [&'static str]),
+    /// Repeatable, passed as `--flag VALUE` once per entry
+    List,
+}
+
+/// One rsync option and how the TUI presents it
+#[derive(Debug)]
+pub struct OptionSpec {
+    /// Stable identifier, also the key in saved profiles
+    pub id: &'static str,
+    /// Argument added to the command; split on spaces for flags that take a
+    /// fixed value (e.g. `-e ssh`)
+    pub flag: &'static str,
+    /// Key that toggles the option in Normal mode (Bool options only)
+    pub key: Option<char>,
+    /// Short label for the Options panel
+    pub label: &'static str,
+    pub description: &'static str,
+    pub value: ValueType,
+    /// Initial state of Bool options; other kinds start unset
+    pub default: bool,
+    /// Options that are switched off when this one is switched on
+    pub conflicts: &'static [&'static str],
+    /// rsync version that introduced the option (None if older than 3.0)
+    pub since: Option<(u32, u32, u32)>,
+    /// Entry `rsync --version` lists under Capabilities when the build
+    /// supports the option (None if every build does)
+    pub capability: Option<&'static str>,
+    /// Part of the `-e` remote shell command rather than an rsync argument;
+    /// `flag` is then the ssh option
+    pub ssh: bool,
+    /// Passed to rsync in the environment variable named by `flag` instead
+    /// of as an argument. Such values are secrets and are shown masked.
+    pub env: bool,
+}
+
+const fn flag(
+    id: &'static str,
+    flag: &'static str,
+    key: Option<char>,
+    label: &'static str,
+    description: &'static str,
+) -> OptionSpec {
+    OptionSpec {
+        id,
+        flag,
+        key,
+        label,
+        description,
+        value: ValueType::Bool,
+        default: false,
+        conflicts: &[],
+        since: None,
+        capability: None,
+        ssh: false,
+        env: false,
+    }
+}
+
+const fn valued(
+    id: &'static str,
+    flag: &'static str,
+    label: &'static str,
+    description: &'static str,
+    value: ValueType,
+) -> OptionSpec {
+    OptionSpec {
+        id,
+        flag,
+        key: None,
+        label,
+        description,
+        value,
+        default: false,
+        conflicts: &[],
+        since: None,
+        capability: None,
+        ssh: false,
+        env: false,
+    }
+}
+
+/// Setting of the ssh command passed with `-e`
+const fn ssh_setting(
+    id: &'static str,
+    flag: &'static str,
+    label: &'static str,
+    description: &'static str,
+    value: ValueType,
+) -> OptionSpec {
+    OptionSpec {
+        ssh: true,
+        ..valued(id, flag, label, description, value)
+    }
+}
+
+/// Every option the TUI knows, in the order they appear on the command line
+pub const CATALOG: &[OptionSpec] = &[
+    OptionSpec {
+        default: true,
+        ..flag(
+            "archive",
+            "-a",
+            Some('a'),
+            "Archive",
+            "Recursive, preserving permissions, times, owners, links and devices",
+        )
+    },
+    OptionSpec {
+        default: true,
+        ..flag(
+            "verbose",
+            "-v",
+            Some('v'),
+            "Verbose",
+            "List files as they are transferred",
+        )
+    },
+    flag(
+        "compress",
+        "-z",
+        Some('z'),
+        "Compress",
+        "Compress file data during the transfer",
+    ),
+    flag(
+        "dry_run",
+        "-n",
+        Some('n'),
+        "Dry-run",
+        "Show what would be transferred without changing anything",
+    ),
+    OptionSpec {
+        default: true,
+        ..flag(
+            "progress",
+            "--progress",
+            Some('p'),
+            "Progress",
+            "Show progress during the transfer",
+        )
+    },
+    // Must follow --progress so it overrides the per-file progress level
+    OptionSpec {
+        since: Some((3, 1, 0)),
+        ..flag(
+            "progress_total",
+            "--info=progress2",
+            Some('t'),
+            "Total",
+            "Show progress for the whole transfer instead of per file",
+        )
+    },
+    flag(
+        "delete",
+        "--delete",
+        Some('d'),
+        "Delete",
+        "Delete files on the destination that are not in the source",
+    ),
+    OptionSpec {
+        default: true,
+        ..flag(
+            "human_readable",
+            "-h",
+            None,
+            "Human",
+            "Print sizes in human-readable units",
+        )
+    },
+    flag(
+        "itemize_changes",
+        "-i",
+        Some('c'),
+        "Itemize",
+        "Print a change summary for every updated file",
+    ),
+    flag(
+        "stats",
+        "--stats",
+        Some('s'),
+        "Stats",
+        "Print a summary of the transfer when it finishes",
+    ),
+    flag(
+        "checksum",
+        "--checksum",
+        Some('C'),
+        "Checksum",
+        "Compare files by checksum instead of size and time",
+    ),
+    flag(
+        "partial",
+        "--partial",
+        Some('P'),
+        "Partial",
+        "Keep partially transferred files to resume later",
+    ),
+    OptionSpec {
+        capability: Some("hardlinks"),
+        ..flag(
+            "hard_links",
+            "--hard-links",
+            Some('H'),
+            "Hard links",
+            "Preserve hard links",
+        )
+    },
+    OptionSpec {
+        capability: Some("ACLs"),
+        ..flag(
+            "acls",
+            "--acls",
+            Some('A'),
+            "ACLs",
+            "Preserve access control lists",
+        )
+    },
+    OptionSpec {
+        capability: Some("xattrs"),
+        ..flag(
+            "xattrs",
+            "--xattrs",
+            Some('X'),
+            "Xattrs",
+            "Preserve extended attributes",
+        )
+    },
+    OptionSpec {
+        conflicts: &["inplace"],
+        ..flag(
+            "sparse",
+            "--sparse",
+            Some('S'),
+            "Sparse",
+            "Store runs of zeros as sparse blocks",
+        )
+    },
+    OptionSpec {
+        conflicts: &["sparse"],
+        capability: Some("inplace"),
+        ..flag(
+            "inplace",
+            "--inplace",
+            Some('I'),
+            "Inplace",
+            "Write updates directly into destination files",
+        )
+    },
+    flag(
+        "whole_file",
+        "--whole-file",
+        Some('W'),
+        "Whole file",
+        "Copy whole files without the delta algorithm",
+    ),
+    flag(
+        "update",
+        "--update",
+        Some('U'),
+        "Update",
+        "Skip files that are newer on the destination",
+    ),
+    flag(
+        "numeric_ids",
+        "--numeric-ids",
+        None,
+        "Numeric IDs",
+        "Keep numeric user and group ids instead of mapping names",
+    ),
+    flag(
+        "one_file_system",
+        "--one-file-system",
+        None,
+        "One FS",
+        "Don't cross filesystem boundaries",
+    ),
+    flag(
+        "delete_excluded",
+        "--delete-excluded",
+        None,
+        "Delete excluded",
+        "Also delete excluded files on the destination",
+    ),
+    OptionSpec {
+        ssh: true,
+        ..flag(
+            "use_ssh",
+            "-e ssh",
+            Some('e'),
+            "SSH",
+            "Use ssh as the remote shell, with the SSH settings below",
+        )
+    },
+    ssh_setting(
+        "ssh_program",
+        "-e",
+        "SSH program",
+        "ssh binary to run instead of the ssh on PATH",
+        ValueType::Text,
+    ),
+    ssh_setting(
+        "ssh_port",
+        "-p",
+        "SSH port",
+        "Port of the ssh server",
+        ValueType::Int,
+    ),
+    ssh_setting(
+        "ssh_user",
+        "-l",
+        "SSH user",
+        "User to log in as on the remote host",
+        ValueType::Text,
+    ),
+    ssh_setting(
+        "ssh_identity",
+        "-i",
+        "SSH key",
+        "Private key file to authenticate with",
+        ValueType::Text,
+    ),
+    ssh_setting(
+        "ssh_jump",
+        "-J",
+        "Jump host",
+        "Connect through this bastion ([user@]host[:port], comma-separated for several hops)",
+        ValueType::Text,
+    ),
+    ssh_setting(
+        "ssh_options",
+        "-o",
+        "SSH options",
+        "ssh -o options such as StrictHostKeyChecking=accept-new or ControlMaster=auto",
+        ValueType::List,
+    ),
+    valued(
+        "port",
+        "--port",
+        "Daemon port",
+        "TCP port of the rsync daemon for host::module targets (default 873)",
+        ValueType::Int,
+    ),
+    valued(
+        "password_file",
+        "--password-file",
+        "Password file",
+        "File holding the rsync daemon password",
+        ValueType::Text,
+    ),
+    OptionSpec {
+        env: true,
+        ..valued(
+            "daemon_password",
+            "RSYNC_PASSWORD",
+            "Daemon password",
+            "rsync daemon password, passed in RSYNC_PASSWORD and saved with the profile in plain text",
+            ValueType::Text,
+        )
+    },
+    valued(
+        "max_delete",
+        "--max-delete",
+        "Max delete",
+        "Don't delete more than this many files",
+        ValueType::Int,
+    ),
+    valued(
+        "bwlimit",
+        "--bwlimit",
+        "Bandwidth",
+        "Limit the transfer rate (e.g. 1.5m)",
+        ValueType::Text,
+    ),
+    valued(
+        "timeout",
+        "--timeout",
+        "Timeout",
+        "Give up after this many seconds without I/O",
+        ValueType::Int,
+    ),
+    valued(
+        "backup_dir",
+        "--backup-dir",
+        "Backup dir",
+        "Move replaced and deleted files into this directory",
+        ValueType::Text,
+    ),
+    OptionSpec {
+        since: Some((3, 2, 0)),
+        ..valued(
+            "checksum_choice",
+            "--checksum-choice",
+            "Checksum algo",
+            "Checksum algorithm used for transfers and --checksum",
+            ValueType::Enum(&["auto", "xxh128", "xxh3", "xxh64", "md5", "md4", "none"]),
+        )
+    },
+    OptionSpec {
+        since: Some((3, 2, 0)),
+        ..valued(
+            "compress_choice",
+            "--compress-choice",
+            "Compress algo",
+            "Compression algorithm used with -z",
+            ValueType::Enum(&["auto", "zstd", "lz4", "zlibx", "zlib", "none"]),
+        )
+    },
+    valued(
+        "link_dest",
+        "--link-dest",
+        "Link dest",
+        "Hard-link files unchanged from these directories instead of copying",
+        ValueType::List,
+    ),
+];
+
+/// Value as shown in the TUI, masked for secrets
+pub fn display_value(spec: &OptionSpec, value: &str) -> String {
+    if spec.env {
+        "*".repeat(value.chars().count())
+    } else {
+        value.to_string()
+    }
+}
+
+/// Look up an option by id
+pub fn find(id: &str) -> Option<&'static OptionSpec> {
+    CATALOG.iter().find(|spec| spec.id == id)
+}
+
+/// The Bool option toggled by `key` in Normal mode
+pub fn by_key(key: char) -> Option<&'static OptionSpec> {
+    CATALOG.iter().find(|spec| spec.key == Some(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_ids_and_keys_unique() {
+        let mut ids = HashSet::new();
+        let mut keys = HashSet::new();
+        for spec in CATALOG {
+            assert!(ids.insert(spec.id), "duplicate id {}", spec.id);
+            if let Some(key) = spec.key {
+                assert!(keys.insert(key), "duplicate key {}", key);
+                assert_eq!(spec.value, ValueType::Bool, "{} has a key", spec.id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_keys_avoid_navigation() {
+        for key in ['q', 'h', 'j', 'k', 'l', 'i', '1', '2', '3', '4', '5'] {
+            assert!(by_key(key).is_none(), "{} is a navigation key", key);
+        }
+    }
+
+    #[test]
+    fn test_conflicts_are_symmetric() {
+        for spec in CATALOG {
+            for other in spec.conflicts {
+                let other = find(other).expect("conflict refers to a known option");
+                assert!(other.conflicts.contains(&spec.id));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ssh_settings_follow_the_toggle() {
+        let ssh: Vec<&str> = CATALOG.iter().filter(|s| s.ssh).map(|s| s.id).collect();
+        assert_eq!(ssh[0], "use_ssh");
+        // Settings are listed together under the toggle
+        let start = CATALOG.iter().position(|s| s.id == "use_ssh").unwrap();
+        for (offset, id) in ssh.iter().enumerate() {
+            assert_eq!(CATALOG[start + offset].id, *id);
+        }
+        assert_eq!(
+            CATALOG
+                .iter()
+                .filter(|s| s.ssh && s.value == ValueType::Bool)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(find("delete").unwrap().flag, "--delete");
+        assert_eq!(by_key('z').unwrap().id, "compress");
+        assert!(find("nope").is_none());
+    }
+}
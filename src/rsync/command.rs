@@ -1,38 +1,49 @@
-use super::options::RsyncOptions;
+use std::borrow::Cow;
+
+use super::catalog::{ValueType, CATALOG};
+use super::options::{OptionValue, RsyncOptions};
 
 /// Build rsync command from options
 pub fn build_command(source: &str, destination: &str, options: &RsyncOptions) -> Vec<String> {
     let mut args = vec!["rsync".to_string()];
 
-    if options.archive {
-        args.push("-a".to_string());
-    }
-    if options.verbose {
-        args.push("-v".to_string());
-    }
-    if options.compress {
-        args.push("-z".to_string());
-    }
-    if options.dry_run {
-        args.push("-n".to_string());
-    }
-    if options.progress {
-        args.push("--progress".to_string());
-    }
-    if options.delete {
-        args.push("--delete".to_string());
-    }
-    if options.human_readable {
-        args.push("-h".to_string());
-    }
-    if options.use_ssh {
-        args.push("-e".to_string());
-        args.push("ssh".to_string());
+    for spec in CATALOG {
+        // SSH settings go into a single -e argument where the toggle is
+        if spec.ssh {
+            if spec.value == ValueType::Bool && options.is_enabled(spec.id) {
+                args.push("-e".to_string());
+                args.push(remote_shell(options));
+            }
+            continue;
+        }
+        if spec.env {
+            continue;
+        }
+        match spec.value {
+            ValueType::Bool => {
+                if options.is_enabled(spec.id) {
+                    args.extend(spec.flag.split(' ').map(String::from));
+                }
+            }
+            ValueType::List => {
+                for item in options.list(spec.id) {
+                    args.push(spec.flag.to_string());
+                    args.push(item.clone());
+                }
+            }
+            ValueType::Int | ValueType::Text | ValueType::Enum(_) => match options.value(spec.id) {
+                Some(OptionValue::Int(n)) => args.push(format!("{}={}", spec.flag, n)),
+                Some(OptionValue::Text(text)) => args.push(format!("{}={}", spec.flag, text)),
+                _ => {}
+            },
+        }
     }
 
-    for pattern in &options.exclude {
-        args.push("--exclude".to_string());
-        args.push(pattern.clone());
+    args.extend(options.extra_args.iter().cloned());
+
+    // Filter rules keep their order: the first matching rule wins
+    for rule in &options.filters {
+        args.extend(rule.args());
     }
 
     args.push(source.to_string());
@@ -41,14 +52,88 @@ pub fn build_command(source: &str, destination: &str, options: &RsyncOptions) ->
     args
 }
 
-/// Format command as display string
-pub fn format_command(source: &str, destination: &str, options: &RsyncOptions) -> String {
-    build_command(source, destination, options).join(" ")
+/// Environment variables the command needs, such as `RSYNC_PASSWORD`
+pub fn command_env(options: &RsyncOptions) -> Vec<(String, String)> {
+    CATALOG
+        .iter()
+        .filter(|spec| spec.env)
+        .filter_map(|spec| Some((spec.flag.to_string(), options.value(spec.id)?.to_string())))
+        .collect()
+}
+
+/// Quote a word of the `-e` command the way rsync splits it: on spaces,
+/// with single quotes kept together and a doubled quote standing for one
+pub fn rsh_quote(word: &str) -> Cow<'_, str> {
+    if !word.is_empty() && !word.contains([' ', '\'', '"']) {
+        return Cow::Borrowed(word);
+    }
+    Cow::Owned(format!("'{}'", word.replace('\'', "''")))
+}
+
+/// The ssh command rsync runs for remote paths, built from the SSH settings
+pub fn remote_shell(options: &RsyncOptions) -> String {
+    let program = match options.value("ssh_program") {
+        Some(OptionValue::Text(program)) => program.clone(),
+        _ => "ssh".to_string(),
+    };
+    let mut words = vec![program];
+    for spec in CATALOG.iter().filter(|s| s.ssh && s.id != "ssh_program") {
+        match options.value(spec.id) {
+            Some(OptionValue::Int(n)) => words.extend([spec.flag.to_string(), n.to_string()]),
+            Some(OptionValue::Text(text)) => words.extend([spec.flag.to_string(), text.clone()]),
+            Some(OptionValue::List(items)) => {
+                for item in items {
+                    words.extend([spec.flag.to_string(), item.clone()]);
+                }
+            }
+            _ => {}
+        }
+    }
+    words
+        .iter()
+        .map(|word| rsh_quote(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quote an argument for a POSIX shell. Words made only of characters the
+/// shell treats literally are left alone; anything else is single-quoted.
+pub fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let literal = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c);
+    if !arg.is_empty() && arg.chars().all(literal) {
+        return Cow::Borrowed(arg);
+    }
+    Cow::Owned(format!("'{}'", arg.replace('\'', "'\\''")))
+}
+
+/// Format command as display string, quoted so it can be pasted into a shell
+pub fn format_command(command: &[String]) -> String {
+    command
+        .iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Executable shell script that runs the command built from `options`
+pub fn format_script(command: &[String], options: &RsyncOptions, profile: &str) -> String {
+    // Secrets stay in the profile; the script only names them
+    let env: String = command_env(options)
+        .iter()
+        .map(|(name, _)| format!("# Set {} before running\n", name))
+        .collect();
+    format!(
+        "#!/bin/sh\n# Written by rsync_tui from profile '{}'\n{}exec {}\n",
+        profile.replace('\n', " "),
+        env,
+        format_command(command)
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rsync::filter::{FilterRule, RuleKind};
 
     #[test]
     fn test_default_options() {
@@ -75,17 +160,80 @@ mod tests {
     #[test]
     fn test_ssh_option() {
         let mut opts = RsyncOptions::default();
-        opts.use_ssh = true;
+        opts.set_enabled("use_ssh", true);
         let cmd = build_command("/src", "/dest", &opts);
 
         let ssh_idx = cmd.iter().position(|x| x == "-e").unwrap();
         assert_eq!(cmd[ssh_idx + 1], "ssh");
     }
 
+    #[test]
+    fn test_ssh_settings() {
+        let mut opts = RsyncOptions::default();
+        opts.set_enabled("use_ssh", true);
+        opts.set("ssh_port", Some(OptionValue::Int(2222))).unwrap();
+        opts.set("ssh_user", Some(OptionValue::Text("backup".into())))
+            .unwrap();
+        opts.set(
+            "ssh_identity",
+            Some(OptionValue::Text("/home/me/.ssh/My Key".into())),
+        )
+        .unwrap();
+        opts.set(
+            "ssh_jump",
+            Some(OptionValue::Text("admin@bastion:2200".into())),
+        )
+        .unwrap();
+        opts.set(
+            "ssh_options",
+            Some(OptionValue::List(vec![
+                "StrictHostKeyChecking=accept-new".into(),
+                "ControlMaster=auto".into(),
+            ])),
+        )
+        .unwrap();
+        let cmd = build_command("/src", "host:/dest", &opts);
+
+        let e = cmd.iter().position(|x| x == "-e").unwrap();
+        assert_eq!(
+            cmd[e + 1],
+            "ssh -p 2222 -l backup -i '/home/me/.ssh/My Key' -J admin@bastion:2200 \
+             -o StrictHostKeyChecking=accept-new -o ControlMaster=auto"
+        );
+        assert_eq!(cmd.iter().filter(|x| *x == "-e").count(), 1);
+    }
+
+    #[test]
+    fn test_ssh_settings_need_the_toggle() {
+        let mut opts = RsyncOptions::default();
+        opts.set("ssh_port", Some(OptionValue::Int(2222))).unwrap();
+        let cmd = build_command("/src", "host:/dest", &opts);
+        assert!(!cmd.contains(&"-e".to_string()));
+
+        opts.set_enabled("use_ssh", true);
+        opts.set(
+            "ssh_program",
+            Some(OptionValue::Text("/opt/ssh/bin/ssh".into())),
+        )
+        .unwrap();
+        let cmd = build_command("/src", "host:/dest", &opts);
+        assert!(cmd.contains(&"/opt/ssh/bin/ssh -p 2222".to_string()));
+    }
+
+    #[test]
+    fn test_rsh_quote() {
+        assert_eq!(rsh_quote("ssh"), "ssh");
+        assert_eq!(rsh_quote("a b"), "'a b'");
+        assert_eq!(rsh_quote("it's"), "'it''s'");
+    }
+
     #[test]
     fn test_exclude_patterns() {
         let mut opts = RsyncOptions::default();
-        opts.exclude = vec!["*.log".to_string(), "tmp/".to_string()];
+        opts.filters = vec![
+            FilterRule::new(RuleKind::Exclude, "*.log"),
+            FilterRule::new(RuleKind::Exclude, "tmp/"),
+        ];
         let cmd = build_command("/src", "/dest", &opts);
 
         assert!(cmd.contains(&"--exclude".to_string()));
@@ -93,42 +241,154 @@ mod tests {
         assert!(cmd.contains(&"tmp/".to_string()));
     }
 
+    #[test]
+    fn test_filter_rules_keep_order() {
+        let mut opts = RsyncOptions::default();
+        opts.filters = vec![
+            FilterRule::new(RuleKind::Include, "photos/"),
+            FilterRule::new(RuleKind::Include, "photos/**"),
+            FilterRule::new(RuleKind::DirMerge, ".rsync-filter"),
+            FilterRule::new(RuleKind::Exclude, "*"),
+        ];
+        let cmd = build_command("/src", "/dest", &opts);
+
+        let start = cmd.iter().position(|x| x == "--include").unwrap();
+        assert_eq!(
+            cmd[start..cmd.len() - 2],
+            [
+                "--include",
+                "photos/",
+                "--include",
+                "photos/**",
+                "--filter",
+                "dir-merge .rsync-filter",
+                "--exclude",
+                "*",
+            ]
+        );
+    }
+
     #[test]
     fn test_dry_run_flag() {
         let mut opts = RsyncOptions::default();
-        opts.dry_run = true;
+        opts.set_enabled("dry_run", true);
         let cmd = build_command("/src", "/dest", &opts);
 
         assert!(cmd.contains(&"-n".to_string()));
     }
 
+    #[test]
+    fn test_progress_total_follows_progress() {
+        let mut opts = RsyncOptions::default();
+        opts.set_enabled("progress_total", true);
+        let cmd = build_command("/src", "/dest", &opts);
+
+        let progress = cmd.iter().position(|x| x == "--progress").unwrap();
+        let total = cmd.iter().position(|x| x == "--info=progress2").unwrap();
+        assert!(total > progress);
+    }
+
     #[test]
     fn test_format_command() {
         let mut opts = RsyncOptions::default();
-        opts.archive = true;
-        opts.verbose = false;
-        opts.progress = false;
-        opts.human_readable = false;
-        let formatted = format_command("/src", "/dest", &opts);
+        opts.set_enabled("archive", true);
+        opts.set_enabled("verbose", false);
+        opts.set_enabled("progress", false);
+        opts.set_enabled("human_readable", false);
+        let formatted = format_command(&build_command("/src", "/dest", &opts));
 
         assert_eq!(formatted, "rsync -a /src /dest");
     }
 
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/home/user/docs"), "/home/user/docs");
+        assert_eq!(shell_quote("host:/srv/a_b-c.d"), "host:/srv/a_b-c.d");
+        assert_eq!(shell_quote("--max-delete=5"), "--max-delete=5");
+        assert_eq!(shell_quote("My Documents/"), "'My Documents/'");
+        assert_eq!(shell_quote("*.log"), "'*.log'");
+        assert_eq!(shell_quote("~/docs"), "'~/docs'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_format_command_quotes_for_the_shell() {
+        let mut opts = RsyncOptions::default();
+        opts.filters = vec![
+            FilterRule::new(RuleKind::Exclude, "*.log"),
+            FilterRule::new(RuleKind::DirMerge, ".rsync-filter"),
+        ];
+        let formatted = format_command(&build_command("/home/me/My Files/", "nas:/backup", &opts));
+
+        assert_eq!(
+            formatted,
+            "rsync -a -v --progress -h --exclude '*.log' --filter 'dir-merge .rsync-filter' \
+             '/home/me/My Files/' nas:/backup"
+        );
+        // Pasting it into a shell gives back the exact arguments
+        let words = crate::rsync::parse::tokenize(&formatted).unwrap();
+        assert_eq!(
+            words,
+            build_command("/home/me/My Files/", "nas:/backup", &opts)
+        );
+    }
+
+    #[test]
+    fn test_format_script() {
+        let opts = RsyncOptions::default();
+        let script = format_script(&build_command("/src dir", "/dest", &opts), &opts, "nightly");
+
+        assert_eq!(
+            script,
+            "#!/bin/sh\n# Written by rsync_tui from profile 'nightly'\n\
+             exec rsync -a -v --progress -h '/src dir' /dest\n"
+        );
+    }
+
+    #[test]
+    fn test_daemon_password_goes_in_the_environment() {
+        let mut opts = RsyncOptions::default();
+        opts.set("port", Some(OptionValue::Int(8730))).unwrap();
+        opts.set("daemon_password", Some(OptionValue::Text("s3cret".into())))
+            .unwrap();
+        let cmd = build_command("/src", "nas::backup/", &opts);
+
+        assert!(cmd.contains(&"--port=8730".to_string()));
+        assert!(!cmd.iter().any(|arg| arg.contains("s3cret")));
+        assert_eq!(
+            command_env(&opts),
+            vec![("RSYNC_PASSWORD".to_string(), "s3cret".to_string())]
+        );
+        let script = format_script(&cmd, &opts, "nas");
+        assert!(script.contains("# Set RSYNC_PASSWORD before running\n"));
+        assert!(!script.contains("s3cret"));
+    }
+
     #[test]
     fn test_all_options_disabled() {
-        let opts = RsyncOptions {
-            archive: false,
-            verbose: false,
-            compress: false,
-            dry_run: false,
-            progress: false,
-            delete: false,
-            human_readable: false,
-            use_ssh: false,
-            exclude: vec![],
-        };
+        let mut opts = RsyncOptions::default();
+        for spec in CATALOG.iter().filter(|s| s.value == ValueType::Bool) {
+            opts.set_enabled(spec.id, false);
+        }
         let cmd = build_command("/src", "/dest", &opts);
 
         assert_eq!(cmd, vec!["rsync", "/src", "/dest"]);
     }
+
+    #[test]
+    fn test_catalog_flags() {
+        let mut opts = RsyncOptions::default();
+        opts.set_enabled("checksum", true);
+        opts.set_enabled("hard_links", true);
+        opts.set("max_delete", Some(OptionValue::Int(50))).unwrap();
+        opts.set("bwlimit", Some(OptionValue::Text("1m".to_string())))
+            .unwrap();
+        let cmd = build_command("/src", "/dest", &opts);
+
+        assert!(cmd.contains(&"--checksum".to_string()));
+        assert!(cmd.contains(&"--hard-links".to_string()));
+        assert!(cmd.contains(&"--max-delete=50".to_string()));
+        assert!(cmd.contains(&"--bwlimit=1m".to_string()));
+    }
 }
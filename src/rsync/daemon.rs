@@ -0,0 +1,126 @@
+use super::options::{OptionValue, RsyncOptions};
+
+/// A module exported by an rsync daemon, as listed by `rsync host::`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub name: String,
+    pub comment: String,
+}
+
+/// Split a daemon target whose module is still being typed into the part
+/// before the module and the partial name: `nas::ba` gives (`nas::`, `ba`)
+/// and `rsync://nas:8730/ba` gives (`rsync://nas:8730/`, `ba`). Once a '/'
+/// follows the module name the target is a path inside it and gives None.
+pub fn split_module(path: &str) -> Option<(&str, &str)> {
+    let split = match path.strip_prefix("rsync://") {
+        Some(rest) => {
+            let slash = rest.find('/')?;
+            if slash == 0 {
+                return None;
+            }
+            "rsync://".len() + slash + 1
+        }
+        None => {
+            let colons = path.find("::")?;
+            if colons == 0 || path[..colons].contains('/') {
+                return None;
+            }
+            colons + 2
+        }
+    };
+    let (base, module) = path.split_at(split);
+    if module.contains('/') {
+        return None;
+    }
+    Some((base, module))
+}
+
+/// Command listing the modules of the daemon at `base` (`host::` or
+/// `rsync://host/`) with the rsync `program`, one per line with its comment
+pub fn list_command(program: &str, base: &str, options: &RsyncOptions) -> Vec<String> {
+    let mut args = vec![program.to_string(), "--no-motd".to_string()];
+    if let Some(OptionValue::Int(port)) = options.value("port") {
+        args.push(format!("--port={}", port));
+    }
+    args.push(base.to_string());
+    args
+}
+
+/// Modules from the output of `rsync --no-motd host::`, where each line is
+/// the name padded with spaces and an optional comment
+pub fn parse_modules(lines: &[String]) -> Vec<Module> {
+    lines
+        .iter()
+        // "@ERROR" and "@RSYNCD" lines come from the protocol, not modules
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('@'))
+        .map(|line| {
+            let line = line.trim();
+            let (name, comment) = line
+                .split_once(|c: char| c.is_whitespace())
+                .unwrap_or((line, ""));
+            Module {
+                name: name.to_string(),
+                comment: comment.trim().to_string(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_module() {
+        assert_eq!(split_module("nas::"), Some(("nas::", "")));
+        assert_eq!(split_module("user@nas::ba"), Some(("user@nas::", "ba")));
+        assert_eq!(
+            split_module("rsync://nas:8730/ba"),
+            Some(("rsync://nas:8730/", "ba"))
+        );
+        assert_eq!(split_module("nas::backup/"), None);
+        assert_eq!(split_module("rsync://nas/backup/x"), None);
+        assert_eq!(split_module("rsync://nas"), None);
+        assert_eq!(split_module("/local/a::b"), None);
+        assert_eq!(split_module("nas:/srv"), None);
+    }
+
+    #[test]
+    fn test_list_command() {
+        let mut options = RsyncOptions::default();
+        assert_eq!(
+            list_command("rsync", "nas::", &options),
+            ["rsync", "--no-motd", "nas::"]
+        );
+        options.set("port", Some(OptionValue::Int(8730))).unwrap();
+        assert_eq!(
+            list_command("rsync", "nas::", &options),
+            ["rsync", "--no-motd", "--port=8730", "nas::"]
+        );
+    }
+
+    #[test]
+    fn test_parse_modules() {
+        let lines: Vec<String> = [
+            "backup         \tNightly backups",
+            "photos",
+            "",
+            "@ERROR: protocol startup error",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(
+            parse_modules(&lines),
+            vec![
+                Module {
+                    name: "backup".to_string(),
+                    comment: "Nightly backups".to_string()
+                },
+                Module {
+                    name: "photos".to_string(),
+                    comment: String::new()
+                },
+            ]
+        );
+    }
+}
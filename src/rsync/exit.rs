@@ -0,0 +1,276 @@
+/// How an rsync run ended, from the exit codes documented in rsync(1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RsyncExit {
+    Success,
+    /// 1: syntax or usage error
+    Syntax,
+    /// 2: protocol incompatibility
+    Protocol,
+    /// 3: errors selecting input/output files or directories
+    FileSelection,
+    /// 4: requested action not supported
+    Unsupported,
+    /// 5: error starting the client-server protocol
+    StartProtocol,
+    /// 6: daemon unable to append to its log file
+    DaemonLog,
+    /// 10: error in socket I/O
+    SocketIo,
+    /// 11: error in file I/O
+    FileIo,
+    /// 12: error in the rsync protocol data stream
+    Stream,
+    /// 13: errors with program diagnostics
+    Diagnostics,
+    /// 14: error in IPC code
+    Ipc,
+    /// 20: received SIGUSR1 or SIGINT
+    Interrupted,
+    /// 21: some error returned by waitpid()
+    Waitpid,
+    /// 22: error allocating core memory buffers
+    Memory,
+    /// 23: partial transfer due to error
+    Partial,
+    /// 24: partial transfer due to vanished source files
+    Vanished,
+    /// 25: the --max-delete limit stopped deletions
+    MaxDelete,
+    /// 30: timeout in data send/receive
+    Timeout,
+    /// 35: timeout waiting for daemon connection
+    ConnectTimeout,
+    /// 255: the remote shell failed, usually ssh
+    RemoteShell,
+    /// Any other exit code
+    Other(i32),
+    /// Killed by a signal, or it could not be waited on
+    Killed,
+}
+
+/// How bad an exit is, which decides its colour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Ok,
+    /// Finished, but not everything was transferred
+    Warning,
+    Error,
+}
+
+impl RsyncExit {
+    pub fn from_code(code: Option<i32>) -> Self {
+        match code {
+            None => Self::Killed,
+            Some(0) => Self::Success,
+            Some(1) => Self::Syntax,
+            Some(2) => Self::Protocol,
+            Some(3) => Self::FileSelection,
+            Some(4) => Self::Unsupported,
+            Some(5) => Self::StartProtocol,
+            Some(6) => Self::DaemonLog,
+            Some(10) => Self::SocketIo,
+            Some(11) => Self::FileIo,
+            Some(12) => Self::Stream,
+            Some(13) => Self::Diagnostics,
+            Some(14) => Self::Ipc,
+            Some(20) => Self::Interrupted,
+            Some(21) => Self::Waitpid,
+            Some(22) => Self::Memory,
+            Some(23) => Self::Partial,
+            Some(24) => Self::Vanished,
+            Some(25) => Self::MaxDelete,
+            Some(30) => Self::Timeout,
+            Some(35) => Self::ConnectTimeout,
+            Some(255) => Self::RemoteShell,
+            Some(code) => Self::Other(code),
+        }
+    }
+
+    pub fn code(&self) -> Option<i32> {
+        Some(match self {
+            Self::Success => 0,
+            Self::Syntax => 1,
+            Self::Protocol => 2,
+            Self::FileSelection => 3,
+            Self::Unsupported => 4,
+            Self::StartProtocol => 5,
+            Self::DaemonLog => 6,
+            Self::SocketIo => 10,
+            Self::FileIo => 11,
+            Self::Stream => 12,
+            Self::Diagnostics => 13,
+            Self::Ipc => 14,
+            Self::Interrupted => 20,
+            Self::Waitpid => 21,
+            Self::Memory => 22,
+            Self::Partial => 23,
+            Self::Vanished => 24,
+            Self::MaxDelete => 25,
+            Self::Timeout => 30,
+            Self::ConnectTimeout => 35,
+            Self::RemoteShell => 255,
+            Self::Other(code) => *code,
+            Self::Killed => return None,
+        })
+    }
+
+    /// Whether the run counts as successful. Files vanishing from the
+    /// source while it is read (24) is normal for live directories, so a
+    /// profile can choose to accept it.
+    pub fn is_success(&self, vanished_ok: bool) -> bool {
+        match self {
+            Self::Success => true,
+            Self::Vanished => vanished_ok,
+            _ => false,
+        }
+    }
+
+    pub fn severity(&self, vanished_ok: bool) -> Severity {
+        match self {
+            _ if self.is_success(vanished_ok) => Severity::Ok,
+            Self::Partial | Self::Vanished | Self::MaxDelete => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    /// Short status, such as "Partial transfer"
+    pub fn summary(&self) -> String {
+        match self {
+            Self::Success => "Completed".to_string(),
+            Self::Syntax => "Syntax or usage error".to_string(),
+            Self::Protocol => "Protocol incompatibility".to_string(),
+            Self::FileSelection => "Cannot select files".to_string(),
+            Self::Unsupported => "Action not supported".to_string(),
+            Self::StartProtocol => "Cannot start the protocol".to_string(),
+            Self::DaemonLog => "Daemon log error".to_string(),
+            Self::SocketIo => "Connection error".to_string(),
+            Self::FileIo => "File I/O error".to_string(),
+            Self::Stream => "Protocol stream error".to_string(),
+            Self::Diagnostics => "Diagnostics error".to_string(),
+            Self::Ipc => "IPC error".to_string(),
+            Self::Interrupted => "Interrupted".to_string(),
+            Self::Waitpid => "waitpid error".to_string(),
+            Self::Memory => "Out of memory".to_string(),
+            Self::Partial => "Partial transfer".to_string(),
+            Self::Vanished => "Source files vanished".to_string(),
+            Self::MaxDelete => "Deletions stopped".to_string(),
+            Self::Timeout => "Timed out".to_string(),
+            Self::ConnectTimeout => "Daemon connection timed out".to_string(),
+            Self::RemoteShell => "Remote shell failed".to_string(),
+            Self::Other(code) => format!("Exit code {}", code),
+            Self::Killed => "Killed".to_string(),
+        }
+    }
+
+    /// What the exit code means
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            Self::Success => "All files were transferred.",
+            Self::Syntax => "rsync rejected the command line or an option value.",
+            Self::Protocol => "The two rsync versions could not agree on a protocol.",
+            Self::FileSelection => "A source or destination path could not be used.",
+            Self::Unsupported => "An option is not supported by one of the two rsync binaries.",
+            Self::StartProtocol => "The connection opened but the rsync protocol never started.",
+            Self::DaemonLog => "The rsync daemon could not write to its log file.",
+            Self::SocketIo => "The network connection to the other side failed.",
+            Self::FileIo => "Reading or writing a file failed.",
+            Self::Stream => "The connection dropped or the other side sent unexpected data.",
+            Self::Diagnostics => "rsync could not write its own diagnostics.",
+            Self::Ipc => "rsync's processes could not talk to each other.",
+            Self::Interrupted => "rsync received SIGINT or SIGUSR1 and stopped.",
+            Self::Waitpid => "rsync could not wait for one of its processes.",
+            Self::Memory => "rsync could not allocate memory for its buffers.",
+            Self::Partial => "Some files or attributes were not transferred because of errors.",
+            Self::Vanished => "Some source files were deleted while rsync was reading them.",
+            Self::MaxDelete => "The --max-delete limit stopped deletions part way.",
+            Self::Timeout => "No data was sent or received within --timeout.",
+            Self::ConnectTimeout => "The rsync daemon did not answer within --contimeout.",
+            Self::RemoteShell => "ssh (or the -e remote shell) failed to connect or log in.",
+            Self::Other(_) => "rsync exited with a code it does not document.",
+            Self::Killed => "rsync was killed by a signal or could not be waited on.",
+        }
+    }
+
+    /// Suggested next step, or None when there is nothing to do
+    pub fn suggestion(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::Success => return None,
+            Self::Syntax => "Check the command preview and option values.",
+            Self::Protocol | Self::Unsupported => {
+                "Compare rsync --version on both sides and upgrade the older one."
+            }
+            Self::FileSelection => "Check that the paths exist and are readable and writable.",
+            Self::StartProtocol | Self::Stream => {
+                "Check for login banners printing to stdout and that rsync is installed remotely."
+            }
+            Self::DaemonLog => "Check the daemon's log file setting and permissions.",
+            Self::SocketIo | Self::ConnectTimeout => {
+                "Check the host, port and firewall, then retry."
+            }
+            Self::FileIo => "Check free space and permissions on the destination.",
+            Self::Partial => "Look for [ERR] lines in the output for the files that failed.",
+            Self::Vanished => {
+                "Usually harmless; turn on \"Vanished OK\" in the Options panel to accept it."
+            }
+            Self::MaxDelete => "Dry-run to review the deletions, then raise --max-delete.",
+            Self::Timeout => "Retry, or raise --timeout for slow links.",
+            Self::RemoteShell => {
+                "Run ssh to the host by hand to check keys, host keys and the SSH settings."
+            }
+            Self::Interrupted => "Run again to finish the transfer.",
+            Self::Diagnostics | Self::Ipc | Self::Waitpid | Self::Memory | Self::Other(_) => {
+                "Check the output for rsync's own error message."
+            }
+            Self::Killed => "Run again; --partial keeps interrupted files to resume.",
+        })
+    }
+
+    /// Status with the exit code, such as "Partial transfer (exit 23)"
+    pub fn status(&self) -> String {
+        match self.code() {
+            Some(code) if *self != Self::Other(code) => {
+                format!("{} (exit {})", self.summary(), code)
+            }
+            _ => self.summary(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_roundtrip() {
+        for code in [
+            0, 1, 2, 3, 4, 5, 6, 10, 11, 12, 13, 14, 20, 21, 22, 23, 24, 25, 30, 35, 255, 7,
+        ] {
+            assert_eq!(RsyncExit::from_code(Some(code)).code(), Some(code));
+        }
+        assert_eq!(RsyncExit::from_code(Some(7)), RsyncExit::Other(7));
+        assert_eq!(RsyncExit::from_code(None), RsyncExit::Killed);
+        assert_eq!(RsyncExit::Killed.code(), None);
+    }
+
+    #[test]
+    fn test_vanished_files_can_count_as_success() {
+        let vanished = RsyncExit::from_code(Some(24));
+        assert!(!vanished.is_success(false));
+        assert_eq!(vanished.severity(false), Severity::Warning);
+        assert!(vanished.is_success(true));
+        assert_eq!(vanished.severity(true), Severity::Ok);
+
+        // Other partial transfers are never accepted
+        assert!(!RsyncExit::Partial.is_success(true));
+        assert_eq!(RsyncExit::RemoteShell.severity(true), Severity::Error);
+    }
+
+    #[test]
+    fn test_status() {
+        assert_eq!(RsyncExit::Partial.status(), "Partial transfer (exit 23)");
+        assert_eq!(RsyncExit::Other(7).status(), "Exit code 7");
+        assert_eq!(RsyncExit::Killed.status(), "Killed");
+        assert!(RsyncExit::Success.suggestion().is_none());
+        assert!(RsyncExit::RemoteShell.suggestion().unwrap().contains("ssh"));
+    }
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of filter rule, matching the rsync option that adds it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleKind {
+    /// `--exclude PATTERN`
+    Exclude,
+    /// `--include PATTERN`
+    Include,
+    /// `--filter RULE`, a raw rsync filter rule such as `- *.tmp`
+    Filter,
+    /// `--exclude-from FILE`
+    ExcludeFrom,
+    /// `--include-from FILE`
+    IncludeFrom,
+    /// `--filter 'dir-merge FILE'`, read rules from FILE in every directory
+    DirMerge,
+}
+
+impl RuleKind {
+    pub const ALL: [RuleKind; 6] = [
+        RuleKind::Exclude,
+        RuleKind::Include,
+        RuleKind::Filter,
+        RuleKind::ExcludeFrom,
+        RuleKind::IncludeFrom,
+        RuleKind::DirMerge,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RuleKind::Exclude => "exclude",
+            RuleKind::Include => "include",
+            RuleKind::Filter => "filter",
+            RuleKind::ExcludeFrom => "exclude-from",
+            RuleKind::IncludeFrom => "include-from",
+            RuleKind::DirMerge => "dir-merge",
+        }
+    }
+
+    /// The kind `delta` steps away, wrapping around
+    pub fn cycle(self, delta: isize) -> Self {
+        let len = Self::ALL.len() as isize;
+        let index = Self::ALL.iter().position(|k| *k == self).unwrap_or(0) as isize;
+        Self::ALL[(index + delta).rem_euclid(len) as usize]
+    }
+}
+
+/// One filter rule. Rules are passed to rsync in order, and the first one
+/// matching a file decides whether it is transferred.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterRule {
+    pub kind: RuleKind,
+    /// Pattern, raw rule or file name depending on the kind
+    pub pattern: String,
+}
+
+impl FilterRule {
+    pub fn new(kind: RuleKind, pattern: &str) -> Self {
+        Self {
+            kind,
+            pattern: pattern.to_string(),
+        }
+    }
+
+    /// Arguments adding this rule to an rsync command
+    pub fn args(&self) -> [String; 2] {
+        let (flag, value) = match self.kind {
+            RuleKind::Exclude => ("--exclude", self.pattern.clone()),
+            RuleKind::Include => ("--include", self.pattern.clone()),
+            RuleKind::Filter => ("--filter", self.pattern.clone()),
+            RuleKind::ExcludeFrom => ("--exclude-from", self.pattern.clone()),
+            RuleKind::IncludeFrom => ("--include-from", self.pattern.clone()),
+            RuleKind::DirMerge => ("--filter", format!("dir-merge {}", self.pattern)),
+        };
+        [flag.to_string(), value]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let args = |kind, pattern| FilterRule::new(kind, pattern).args();
+        assert_eq!(args(RuleKind::Exclude, "*.tmp"), ["--exclude", "*.tmp"]);
+        assert_eq!(args(RuleKind::Include, "*/"), ["--include", "*/"]);
+        assert_eq!(args(RuleKind::Filter, "- .git/"), ["--filter", "- .git/"]);
+        assert_eq!(
+            args(RuleKind::ExcludeFrom, "/etc/backup.excl"),
+            ["--exclude-from", "/etc/backup.excl"]
+        );
+        assert_eq!(
+            args(RuleKind::DirMerge, ".rsync-filter"),
+            ["--filter", "dir-merge .rsync-filter"]
+        );
+    }
+
+    #[test]
+    fn test_cycle_wraps() {
+        assert_eq!(RuleKind::Exclude.cycle(1), RuleKind::Include);
+        assert_eq!(RuleKind::Exclude.cycle(-1), RuleKind::DirMerge);
+        assert_eq!(RuleKind::DirMerge.cycle(1), RuleKind::Exclude);
+    }
+
+    #[test]
+    fn test_serialized_kind_names() {
+        let rule = FilterRule::new(RuleKind::DirMerge, ".rsync-filter");
+        let json = serde_json::to_string(&rule).unwrap();
+        assert_eq!(json, r#"{"kind":"dir-merge","pattern":".rsync-filter"}"#);
+    }
+}
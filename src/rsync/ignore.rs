@@ -0,0 +1,368 @@
+use anyhow::Context;
+use std::fs;
+use std::path::Path;
+
+use super::filter::{FilterRule, RuleKind};
+use super::matcher::MAX_ENTRIES;
+
+/// Project-level ignore file read from the root of the source
+pub const RSYNCIGNORE: &str = ".rsyncignore";
+
+/// Rules translated from the ignore files of a source tree
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub rules: Vec<FilterRule>,
+    /// Ignore files that were read, relative to the source
+    pub files: Vec<String>,
+    /// Patterns rsync can't express exactly, with where they came from
+    pub warnings: Vec<String>,
+}
+
+/// One gitignore line as rsync rules
+#[derive(Debug, PartialEq)]
+struct Translation {
+    rules: Vec<FilterRule>,
+    warning: Option<&'static str>,
+}
+
+/// Strip trailing spaces unless they are escaped with a backslash
+fn trim_trailing_spaces(line: &str) -> &str {
+    let mut end = line.len();
+    while line[..end].ends_with(' ') && !line[..end - 1].ends_with('\\') {
+        end -= 1;
+    }
+    &line[..end]
+}
+
+/// Translate a gitignore line found in the directory `base` (relative to the
+/// transfer root, empty for the root itself). git applies the last matching
+/// line while rsync applies the first matching rule, so callers must reverse
+/// the rules of each file.
+fn translate(line: &str, base: &str) -> Option<Translation> {
+    let line = trim_trailing_spaces(line.trim_end_matches('\r'));
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    // A leading "\#" or "\!" is a literal character
+    let pattern = match pattern.strip_prefix('\\') {
+        Some(rest) if rest.starts_with('#') || rest.starts_with('!') => rest,
+        _ => pattern,
+    };
+    let (pattern, dir_only) = match pattern.strip_suffix('/') {
+        Some(rest) => (rest, true),
+        None => (pattern, false),
+    };
+    if pattern.is_empty() {
+        return None;
+    }
+
+    let mut warning = None;
+
+    // git treats "**" that isn't a whole path component as "*", rsync lets
+    // it cross directories
+    let mut components = Vec::new();
+    for component in pattern.split('/') {
+        if component.contains("**") && component != "**" {
+            warning = Some("\"**\" inside a name matches across directories in rsync");
+            let mut single = component.to_string();
+            while single.contains("**") {
+                single = single.replace("**", "*");
+            }
+            components.push(single);
+        } else {
+            components.push(component.to_string());
+        }
+    }
+    let pattern = components.join("/");
+
+    // A slash at the start or in the middle anchors the pattern to the
+    // directory of the ignore file
+    let (floating, pattern) = match pattern.strip_prefix("**/") {
+        Some(rest) => (true, rest.to_string()),
+        None if pattern.contains('/') => (false, pattern.trim_start_matches('/').to_string()),
+        None => (true, pattern),
+    };
+
+    // git's "a/**/b" also matches "a/b", rsync's needs a directory between
+    let mut variants = vec![pattern.clone()];
+    if pattern.contains("/**/") {
+        variants.push(pattern.replacen("/**/", "/", 1));
+        if pattern.matches("/**/").count() > 1 {
+            warning = Some("only the first \"/**/\" is expanded to also match no directory");
+        }
+    }
+
+    let mut patterns = Vec::new();
+    for variant in variants {
+        if !floating {
+            patterns.push(join_anchored(base, &variant));
+        } else if base.is_empty() {
+            patterns.push(variant);
+        } else {
+            // Matches at any depth below the directory of the ignore file
+            patterns.push(join_anchored(base, &variant));
+            patterns.push(join_anchored(base, &format!("**/{}", variant)));
+        }
+    }
+
+    let kind = if negated {
+        RuleKind::Include
+    } else {
+        RuleKind::Exclude
+    };
+    let rules = patterns
+        .into_iter()
+        .map(|p| {
+            let p = if dir_only { format!("{}/", p) } else { p };
+            FilterRule::new(kind, &p)
+        })
+        .collect();
+    Some(Translation { rules, warning })
+}
+
+fn join_anchored(base: &str, pattern: &str) -> String {
+    if base.is_empty() {
+        format!("/{}", pattern)
+    } else {
+        format!("/{}/{}", base, pattern)
+    }
+}
+
+/// Every directory holding a `.gitignore`, relative to `root`, without
+/// looking inside `.git`
+fn find_gitignores(root: &Path) -> Vec<String> {
+    let mut found = Vec::new();
+    let mut pending = vec![String::new()];
+    let mut visited = 0;
+
+    while let Some(relative) = pending.pop() {
+        visited += 1;
+        if visited > MAX_ENTRIES {
+            break;
+        }
+        let dir = root.join(&relative);
+        if dir.join(".gitignore").is_file() {
+            found.push(relative.clone());
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            // Symlinked directories are not followed, as with rsync -a
+            if !entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == ".git" {
+                continue;
+            }
+            pending.push(if relative.is_empty() {
+                name
+            } else {
+                format!("{}/{}", relative, name)
+            });
+        }
+    }
+    found
+}
+
+/// Read the ignore file `name` whose patterns are relative to `base` and
+/// add its rules, last line first
+fn import_file(imported: &mut Imported, path: &Path, name: &str, base: &str) -> anyhow::Result<()> {
+    let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let mut rules = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let Some(translation) = translate(line, base) else {
+            continue;
+        };
+        if let Some(warning) = translation.warning {
+            imported.warnings.push(format!(
+                "{}:{}: {}: {}",
+                name,
+                number + 1,
+                line.trim(),
+                warning
+            ));
+        }
+        rules.push(translation.rules);
+    }
+    imported.rules.extend(rules.into_iter().rev().flatten());
+    imported.files.push(name.to_string());
+    Ok(())
+}
+
+/// Translate `.rsyncignore` at the root of a local source and every
+/// `.gitignore` below it into filter rules. The project-level
+/// `.rsyncignore` comes first, then deeper `.gitignore` files before
+/// shallower ones, since the most specific file decides in git.
+pub fn import(source: &str) -> anyhow::Result<Imported> {
+    let root = Path::new(source);
+    if !root.is_dir() {
+        anyhow::bail!("{} is not a local directory", source);
+    }
+
+    // Without a trailing slash rsync transfers the directory itself, so its
+    // name is the first component of every path
+    let prefix = if source.ends_with('/') {
+        String::new()
+    } else {
+        root.canonicalize()
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_default()
+    };
+    let base_for = |relative: &str| match (prefix.is_empty(), relative.is_empty()) {
+        (true, _) => relative.to_string(),
+        (false, true) => prefix.clone(),
+        (false, false) => format!("{}/{}", prefix, relative),
+    };
+
+    let mut imported = Imported::default();
+
+    let rsyncignore = root.join(RSYNCIGNORE);
+    if rsyncignore.is_file() {
+        import_file(&mut imported, &rsyncignore, RSYNCIGNORE, &base_for(""))?;
+    }
+
+    let mut dirs = find_gitignores(root);
+    dirs.sort_by(|a, b| {
+        let depth = |d: &str| {
+            if d.is_empty() {
+                0
+            } else {
+                d.matches('/').count() + 1
+            }
+        };
+        depth(b).cmp(&depth(a)).then_with(|| a.cmp(b))
+    });
+    for dir in dirs {
+        let name = if dir.is_empty() {
+            ".gitignore".to_string()
+        } else {
+            format!("{}/.gitignore", dir)
+        };
+        import_file(&mut imported, &root.join(&name), &name, &base_for(&dir))?;
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rsync::matcher::test_filters;
+
+    fn patterns(line: &str, base: &str) -> Vec<String> {
+        translate(line, base)
+            .unwrap()
+            .rules
+            .into_iter()
+            .map(|r| match r.kind {
+                RuleKind::Include => format!("+ {}", r.pattern),
+                _ => format!("- {}", r.pattern),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_translate_root_patterns() {
+        assert_eq!(patterns("*.log", ""), vec!["- *.log"]);
+        assert_eq!(patterns("target/", ""), vec!["- target/"]);
+        assert_eq!(patterns("/build", ""), vec!["- /build"]);
+        assert_eq!(patterns("docs/*.html", ""), vec!["- /docs/*.html"]);
+        assert_eq!(patterns("**/cache", ""), vec!["- cache"]);
+        assert_eq!(patterns("!keep.log", ""), vec!["+ keep.log"]);
+        assert_eq!(patterns("\\#notes", ""), vec!["- #notes"]);
+        assert_eq!(patterns("a/**/b", ""), vec!["- /a/**/b", "- /a/b"]);
+        assert!(translate("# comment", "").is_none());
+        assert!(translate("   ", "").is_none());
+    }
+
+    #[test]
+    fn test_translate_nested_patterns() {
+        assert_eq!(patterns("*.o", "src"), vec!["- /src/*.o", "- /src/**/*.o"]);
+        assert_eq!(patterns("/gen", "src"), vec!["- /src/gen"]);
+        assert_eq!(
+            patterns("out/", "src/lib"),
+            vec!["- /src/lib/out/", "- /src/lib/**/out/"]
+        );
+    }
+
+    #[test]
+    fn test_translate_flags_inexact_patterns() {
+        let translation = translate("foo**bar", "").unwrap();
+        assert_eq!(
+            translation.rules,
+            vec![FilterRule::new(RuleKind::Exclude, "foo*bar")]
+        );
+        assert!(translation.warning.is_some());
+        assert!(translate("*.log", "").unwrap().warning.is_none());
+    }
+
+    #[test]
+    fn test_trailing_spaces() {
+        assert_eq!(trim_trailing_spaces("foo  "), "foo");
+        assert_eq!(trim_trailing_spaces("foo\\ "), "foo\\ ");
+    }
+
+    #[test]
+    fn test_import_matches_git() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for file in [
+            "a.log",
+            "keep.log",
+            "src/main.o",
+            "src/gen/x.rs",
+            "src/vendor/v.o",
+            "notes.txt",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "").unwrap();
+        }
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".git/.gitignore"), "*\n").unwrap();
+        fs::write(root.join(".gitignore"), "*.log\n!keep.log\n*.o\n").unwrap();
+        fs::write(root.join("src/.gitignore"), "/gen\n!*.o\n").unwrap();
+        fs::write(root.join(".rsyncignore"), "notes.txt\n").unwrap();
+
+        let source = format!("{}/", root.display());
+        let imported = import(&source).unwrap();
+        assert_eq!(
+            imported.files,
+            vec![".rsyncignore", "src/.gitignore", ".gitignore"]
+        );
+        assert!(imported.warnings.is_empty());
+
+        let report = test_filters(&source, &imported.rules).unwrap();
+        let excluded: Vec<&str> = report
+            .entries
+            .iter()
+            .filter(|e| !e.included)
+            .map(|e| e.path.as_str())
+            .collect();
+        // The nested "!*.o" re-includes object files under src only
+        assert_eq!(excluded, vec!["a.log", "notes.txt", "src/gen"]);
+    }
+
+    #[test]
+    fn test_import_without_trailing_slash() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("proj")).unwrap();
+        fs::write(root.join("proj/.gitignore"), "/dist\n*.tmp\n").unwrap();
+
+        let imported = import(&root.join("proj").display().to_string()).unwrap();
+        let patterns: Vec<&str> = imported.rules.iter().map(|r| r.pattern.as_str()).collect();
+        assert_eq!(
+            patterns,
+            vec!["/proj/*.tmp", "/proj/**/*.tmp", "/proj/dist"]
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! Parsing of `--itemize-changes` (`-i`) output lines such as
+//! ">f.st...... docs/report.pdf" or "*deleting   old/file.txt".
+
+use super::progress::parse_size;
+
+/// Who performed the update (first character of the change string)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,        // '<' file sent to the remote
+    Received,    // '>' file received from the remote / local copy
+    LocalChange, // 'c' local change or creation (directory, symlink)
+    HardLink,    // 'h' hard link to another item
+    NoUpdate,    // '.' not updated, attributes may have changed
+    Message,     // '*' message such as "deleting"
+}
+
+/// Type of the item (second character of the change string)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    File,
+    Directory,
+    Symlink,
+    Device,
+    Special,
+}
+
+/// What happened to the item overall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Deleted,
+    Updated,
+    /// Only attributes changed (permissions, times, ...)
+    Attributes,
+    Unchanged,
+}
+
+/// Which attributes differ (remaining characters of the change string)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Attributes {
+    pub checksum: bool,    // c
+    pub size: bool,        // s
+    pub time: bool,        // t or T
+    pub permissions: bool, // p
+    pub owner: bool,       // o
+    pub group: bool,       // g
+    pub access_time: bool, // u, n or b
+    pub acl: bool,         // a
+    pub xattr: bool,       // x
+}
+
+impl Attributes {
+    /// Short names of the changed attributes, e.g. ["size", "time"]
+    pub fn names(&self) -> Vec<&'static str> {
+        let flags = [
+            (self.checksum, "checksum"),
+            (self.size, "size"),
+            (self.time, "time"),
+            (self.permissions, "perms"),
+            (self.owner, "owner"),
+            (self.group, "group"),
+            (self.access_time, "atime"),
+            (self.acl, "acl"),
+            (self.xattr, "xattr"),
+        ];
+        flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+/// A single itemized change record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemizedChange {
+    pub direction: Direction,
+    pub file_type: FileType,
+    pub kind: ChangeKind,
+    pub attributes: Attributes,
+    pub path: String,
+}
+
+/// Parse one line of `--itemize-changes` output
+pub fn parse_itemize(line: &str) -> Option<ItemizedChange> {
+    let (code, path) = split_change(line)?;
+    build_change(&code, path.to_string())
+}
+
+/// Parse a line printed with `--out-format="%i %l %n"` (change, size, name)
+pub fn parse_itemize_sized(line: &str) -> Option<(ItemizedChange, u64)> {
+    let (code, rest) = split_change(line)?;
+    let (size, path) = rest.split_once(' ')?;
+    let size = parse_size(size)?;
+    Some((build_change(&code, path.to_string())?, size))
+}
+
+/// Split a line into its change string and the text after it
+fn split_change(line: &str) -> Option<(Vec<char>, &str)> {
+    if let Some(rest) = line.strip_prefix("*deleting ") {
+        let rest = rest.trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+        return Some(("*deleting".chars().collect(), rest));
+    }
+
+    // Fixed-width "YXcstpoguax" (rsync 3.1+) or "YXcstpoga" (older releases)
+    // followed by a space. Attributes are either all spaces or none are, so a
+    // space followed by a name at column 9 means the short format.
+    let chars: Vec<char> = line.chars().collect();
+    let width = if chars.len() > 10 && chars[9] == ' ' && chars[10] != ' ' {
+        9
+    } else {
+        11
+    };
+    if chars.len() <= width + 1 || chars[width] != ' ' {
+        return None;
+    }
+    let offset: usize = chars[..=width].iter().map(|c| c.len_utf8()).sum();
+    Some((chars[..width].to_vec(), &line[offset..]))
+}
+
+/// Build a change record from a change string and path
+fn build_change(chars: &[char], path: String) -> Option<ItemizedChange> {
+    if chars.iter().collect::<String>() == "*deleting" {
+        let file_type = if path.ends_with('/') {
+            FileType::Directory
+        } else {
+            FileType::File
+        };
+        return Some(ItemizedChange {
+            direction: Direction::Message,
+            file_type,
+            kind: ChangeKind::Deleted,
+            attributes: Attributes::default(),
+            path,
+        });
+    }
+
+    let direction = match chars[0] {
+        '<' => Direction::Sent,
+        '>' => Direction::Received,
+        'c' => Direction::LocalChange,
+        'h' => Direction::HardLink,
+        '.' => Direction::NoUpdate,
+        _ => return None,
+    };
+    let file_type = match chars[1] {
+        'f' => FileType::File,
+        'd' => FileType::Directory,
+        'L' => FileType::Symlink,
+        'D' => FileType::Device,
+        'S' => FileType::Special,
+        _ => return None,
+    };
+
+    let flags = &chars[2..];
+    if !flags
+        .iter()
+        .all(|c| c.is_ascii_alphabetic() || ".+? ".contains(*c))
+    {
+        return None;
+    }
+
+    let mut attributes = Attributes::default();
+    for c in flags {
+        match c {
+            'c' => attributes.checksum = true,
+            's' => attributes.size = true,
+            't' | 'T' => attributes.time = true,
+            'p' => attributes.permissions = true,
+            'o' => attributes.owner = true,
+            'g' => attributes.group = true,
+            'u' | 'n' | 'b' => attributes.access_time = true,
+            'a' => attributes.acl = true,
+            'x' => attributes.xattr = true,
+            _ => {}
+        }
+    }
+
+    let kind = if flags.iter().all(|c| *c == '+') {
+        ChangeKind::Created
+    } else if direction == Direction::NoUpdate {
+        if attributes == Attributes::default() {
+            ChangeKind::Unchanged
+        } else {
+            ChangeKind::Attributes
+        }
+    } else {
+        ChangeKind::Updated
+    };
+
+    Some(ItemizedChange {
+        direction,
+        file_type,
+        kind,
+        attributes,
+        path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_received_update() {
+        let change = parse_itemize(">f.st...... docs/report.pdf").unwrap();
+        assert_eq!(change.direction, Direction::Received);
+        assert_eq!(change.file_type, FileType::File);
+        assert_eq!(change.kind, ChangeKind::Updated);
+        assert!(change.attributes.size);
+        assert!(change.attributes.time);
+        assert!(!change.attributes.permissions);
+        assert_eq!(change.attributes.names(), vec!["size", "time"]);
+        assert_eq!(change.path, "docs/report.pdf");
+    }
+
+    #[test]
+    fn test_parse_created_file_and_directory() {
+        let change = parse_itemize(">f+++++++++ new file.txt").unwrap();
+        assert_eq!(change.kind, ChangeKind::Created);
+        assert_eq!(change.path, "new file.txt");
+
+        let change = parse_itemize("cd+++++++++ photos/").unwrap();
+        assert_eq!(change.direction, Direction::LocalChange);
+        assert_eq!(change.file_type, FileType::Directory);
+        assert_eq!(change.kind, ChangeKind::Created);
+    }
+
+    #[test]
+    fn test_parse_deleting() {
+        let change = parse_itemize("*deleting   old/file.txt").unwrap();
+        assert_eq!(change.kind, ChangeKind::Deleted);
+        assert_eq!(change.file_type, FileType::File);
+        assert_eq!(change.path, "old/file.txt");
+
+        let change = parse_itemize("*deleting   old/").unwrap();
+        assert_eq!(change.file_type, FileType::Directory);
+    }
+
+    #[test]
+    fn test_parse_attribute_only_change() {
+        let change = parse_itemize(".d..t...... src/").unwrap();
+        assert_eq!(change.direction, Direction::NoUpdate);
+        assert_eq!(change.kind, ChangeKind::Attributes);
+
+        let change = parse_itemize(".f          unchanged.txt").unwrap();
+        assert_eq!(change.kind, ChangeKind::Unchanged);
+    }
+
+    #[test]
+    fn test_parse_symlink_and_old_format() {
+        let change = parse_itemize("cL+++++++++ link -> target").unwrap();
+        assert_eq!(change.file_type, FileType::Symlink);
+        assert_eq!(change.path, "link -> target");
+
+        // rsync 2.6.x uses 9-character change strings
+        let change = parse_itemize(">f.st.... file.txt").unwrap();
+        assert_eq!(change.kind, ChangeKind::Updated);
+        assert_eq!(change.path, "file.txt");
+    }
+
+    #[test]
+    fn test_parse_sized_lines() {
+        let (change, size) =
+            parse_itemize_sized(">f+++++++++ 1,048,576 docs/big file.iso").unwrap();
+        assert_eq!(change.kind, ChangeKind::Created);
+        assert_eq!(change.path, "docs/big file.iso");
+        assert_eq!(size, 1_048_576);
+
+        let (change, size) = parse_itemize_sized("*deleting   0 old/").unwrap();
+        assert_eq!(change.kind, ChangeKind::Deleted);
+        assert_eq!(change.path, "old/");
+        assert_eq!(size, 0);
+
+        assert_eq!(parse_itemize_sized(">f+++++++++ file.txt"), None);
+    }
+
+    #[test]
+    fn test_parse_non_itemize_lines() {
+        assert_eq!(parse_itemize("sending incremental file list"), None);
+        assert_eq!(parse_itemize("sent 1,234 bytes  received 56 bytes"), None);
+        assert_eq!(
+            parse_itemize("      1,048,576 100%   10.00MB/s    0:00:00"),
+            None
+        );
+        assert_eq!(parse_itemize(""), None);
+    }
+}
@@ -0,0 +1,1029 @@
+use anyhow::Context;
+use std::fs;
+use std::path::Path;
+
+use super::filter::{FilterRule, RuleKind};
+
+/// Stop walking the source after this many entries so the tester stays fast
+pub const MAX_ENTRIES: usize = 5000;
+
+/// Match `text` against an rsync wildcard pattern: `*` matches anything but
+/// `/`, `**` matches anything, `?` one character other than `/`, `[...]` a
+/// character class and `\` escapes the next character.
+pub fn wildmatch(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    wildmatch_at(&pattern, &text)
+}
+
+fn wildmatch_at(pattern: &[char], text: &[char]) -> bool {
+    let Some(&first) = pattern.first() else {
+        return text.is_empty();
+    };
+    match first {
+        '*' => {
+            let double = pattern.get(1) == Some(&'*');
+            let rest = if double { &pattern[2..] } else { &pattern[1..] };
+            // Try every split, stopping at '/' for a single star
+            for skip in 0..=text.len() {
+                if wildmatch_at(rest, &text[skip..]) {
+                    return true;
+                }
+                if skip < text.len() && !double && text[skip] == '/' {
+                    return false;
+                }
+            }
+            false
+        }
+        '?' => !text.is_empty() && text[0] != '/' && wildmatch_at(&pattern[1..], &text[1..]),
+        '[' => match (text.first(), class_end(pattern)) {
+            (Some(&c), Some(end)) => {
+                c != '/'
+                    && class_matches(&pattern[1..end], c)
+                    && wildmatch_at(&pattern[end + 1..], &text[1..])
+            }
+            // An unterminated class is a literal '['
+            (Some(&c), None) => c == '[' && wildmatch_at(&pattern[1..], &text[1..]),
+            (None, _) => false,
+        },
+        '\\' if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && wildmatch_at(&pattern[2..], &text[1..])
+        }
+        c => text.first() == Some(&c) && wildmatch_at(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Index of the `]` closing the class that starts at `pattern[0]`
+fn class_end(pattern: &[char]) -> Option<usize> {
+    let mut i = 1;
+    if matches!(pattern.get(i), Some('!') | Some('^')) {
+        i += 1;
+    }
+    // A ']' right after the opening bracket is part of the class
+    if pattern.get(i) == Some(&']') {
+        i += 1;
+    }
+    while i < pattern.len() {
+        if pattern[i] == ']' {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Whether `c` is in a class body such as `a-z0-9` or `!abc`
+fn class_matches(body: &[char], c: char) -> bool {
+    let (negated, body) = match body.first() {
+        Some('!') | Some('^') => (true, &body[1..]),
+        _ => (false, body),
+    };
+    let mut found = false;
+    let mut i = 0;
+    while i < body.len() {
+        if i + 2 < body.len() && body[i + 1] == '-' {
+            found |= body[i] <= c && c <= body[i + 2];
+            i += 3;
+        } else {
+            found |= body[i] == c;
+            i += 1;
+        }
+    }
+    found != negated
+}
+
+/// Whether a single include/exclude pattern matches `path` (relative to the
+/// transfer root, without a leading slash), following rsync's rules:
+/// a leading `/` anchors to the root, a trailing `/` matches directories
+/// only, `dir/***` matches a directory and everything in it, and patterns
+/// without a `/` or `**` match the final path component.
+pub fn pattern_matches(pattern: &str, path: &str, is_dir: bool) -> bool {
+    if let Some(base) = pattern.strip_suffix("/***") {
+        return pattern_matches(&format!("{}/", base), path, is_dir)
+            || pattern_matches(&format!("{}/**", base), path, is_dir);
+    }
+
+    let (pattern, dir_only) = match pattern.strip_suffix('/') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    if dir_only && !is_dir {
+        return false;
+    }
+
+    if let Some(anchored) = pattern.strip_prefix('/') {
+        return wildmatch(anchored, path);
+    }
+    if !pattern.contains('/') && !pattern.contains("**") {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        return wildmatch(pattern, name);
+    }
+
+    // Unanchored patterns with a slash match the end of the path at a
+    // component boundary
+    if wildmatch(pattern, path) {
+        return true;
+    }
+    path.match_indices('/')
+        .any(|(i, _)| wildmatch(pattern, &path[i + 1..]))
+}
+
+/// Rule modifiers that change which files a rule matches
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Modifiers {
+    /// `!`: the rule applies to files the pattern does not match
+    negate: bool,
+    /// `/`: the pattern is matched against the absolute path
+    absolute: bool,
+}
+
+/// One include or exclude rule ready for matching
+#[derive(Debug, Clone, PartialEq)]
+struct Compiled {
+    include: bool,
+    pattern: String,
+    modifiers: Modifiers,
+    /// Directory (relative to the root) a per-directory merge file was read
+    /// from; its rules only apply below it
+    base: String,
+    /// Description shown in the tester
+    origin: String,
+}
+
+impl Compiled {
+    /// Whether the rule applies to `path`. `root` is the absolute path of
+    /// the transfer root without its leading slash, for `/` rules.
+    fn matches(&self, path: &str, is_dir: bool, root: &str) -> bool {
+        let relative = if self.base.is_empty() {
+            path
+        } else {
+            match path
+                .strip_prefix(&self.base)
+                .and_then(|p| p.strip_prefix('/'))
+            {
+                Some(relative) => relative,
+                // Rules of a merge file don't apply outside its directory
+                None => return false,
+            }
+        };
+        let matched = if self.modifiers.absolute && !root.is_empty() {
+            pattern_matches(&self.pattern, &format!("{}/{}", root, path), is_dir)
+        } else {
+            pattern_matches(&self.pattern, relative, is_dir)
+        };
+        matched != self.modifiers.negate
+    }
+}
+
+/// How the lines of a rule file are parsed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Syntax {
+    /// Full filter rules, as in `--filter` values and merge files
+    Rules,
+    /// Patterns that may start with `- ` or `+ `, as in `--exclude-from`
+    /// files; `true` when other lines include
+    Prefixed(bool),
+    /// Nothing but patterns, as in merge files with the `-` or `+`
+    /// modifier; `true` when they include
+    Patterns(bool),
+}
+
+/// File named by a merge or dir-merge rule, and how to read it
+#[derive(Debug, Clone, PartialEq)]
+struct MergeFile {
+    name: String,
+    syntax: Syntax,
+    /// Modifiers given to every rule read from the file
+    modifiers: Modifiers,
+    /// `e`: the merge file itself is not transferred
+    exclude_self: bool,
+}
+
+impl MergeFile {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            syntax: Syntax::Rules,
+            modifiers: Modifiers::default(),
+            exclude_self: false,
+        }
+    }
+}
+
+/// A compiled rule, or the place per-directory merge files are read
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Rule(Compiled),
+    DirMerge(MergeFile),
+}
+
+/// A rule line as written in a `--filter` value or a merge file
+#[derive(Debug, PartialEq)]
+enum Line {
+    Include(String, Modifiers),
+    Exclude(String, Modifiers),
+    Clear,
+    Merge(MergeFile),
+    DirMerge(MergeFile),
+    /// Rules that don't affect what is transferred (protect, risk and
+    /// receiver-side rules)
+    Ignored,
+    /// A rule the tester can't emulate, with the reason
+    Untestable(String),
+}
+
+/// Long rule names, as opposed to the one-character short names
+const LONG_NAMES: &[&str] = &[
+    "exclude",
+    "include",
+    "merge",
+    "dir-merge",
+    "hide",
+    "show",
+    "protect",
+    "risk",
+];
+
+/// Split a rule into its name, modifiers and pattern: `exclude,/ foo`,
+/// `-! foo` and `-!_foo` all have modifiers
+fn split_rule(line: &str) -> (&str, &str, &str) {
+    let word = &line[..line.find([' ', ',']).unwrap_or(line.len())];
+    if LONG_NAMES.contains(&word) {
+        let rest = &line[word.len()..];
+        let (modifiers, pattern) = match rest.strip_prefix(',') {
+            Some(rest) => rest.split_once(' ').unwrap_or((rest, "")),
+            None => ("", rest.strip_prefix(' ').unwrap_or(rest)),
+        };
+        return (word, modifiers, pattern);
+    }
+    let Some(first) = line.chars().next() else {
+        return ("", "", "");
+    };
+    let (name, rest) = line.split_at(first.len_utf8());
+    // Short names may be followed by modifiers, then a space or underscore
+    let end = rest.find([' ', '_']).unwrap_or(rest.len());
+    let modifiers = rest[..end].strip_prefix(',').unwrap_or(&rest[..end]);
+    (name, modifiers, rest.get(end + 1..).unwrap_or(""))
+}
+
+/// Parse a filter rule such as `- *.tmp`, `+ */`, `exclude,/ foo`,
+/// `-! keep/` or `:- .gitignore`. `defaults` are the modifiers of the merge
+/// rule the line was read through.
+fn parse_line(line: &str, syntax: Syntax, defaults: Modifiers) -> Option<Line> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+        return None;
+    }
+    if line == "!" || (syntax == Syntax::Rules && line == "clear") {
+        return Some(Line::Clear);
+    }
+
+    let plain = |include: bool, pattern: &str| {
+        let pattern = pattern.to_string();
+        Some(match include {
+            true => Line::Include(pattern, defaults),
+            false => Line::Exclude(pattern, defaults),
+        })
+    };
+    match syntax {
+        Syntax::Patterns(include) => return plain(include, line),
+        Syntax::Prefixed(include) => {
+            return match (line.strip_prefix("- "), line.strip_prefix("+ ")) {
+                (Some(pattern), _) => plain(false, pattern),
+                (_, Some(pattern)) => plain(true, pattern),
+                _ => plain(include, line),
+            };
+        }
+        Syntax::Rules => {}
+    }
+
+    let (name, modifiers, pattern) = split_rule(line);
+    let untestable = |reason: &str| Some(Line::Untestable(format!("{} ({})", line, reason)));
+    match name {
+        "-" | "exclude" | "H" | "hide" | "+" | "include" | "S" | "show" => {
+            let include = matches!(name, "+" | "include" | "S" | "show");
+            let mut parsed = defaults;
+            for modifier in modifiers.chars() {
+                match modifier {
+                    '!' => parsed.negate = true,
+                    '/' => parsed.absolute = true,
+                    // Sender-side and perishable rules still decide what is sent
+                    's' | 'p' => {}
+                    // Receiver-side and xattr rules don't
+                    'r' | 'x' => return Some(Line::Ignored),
+                    'C' => return untestable("CVS default patterns are not emulated"),
+                    _ => return untestable(&format!("unknown modifier '{}'", modifier)),
+                }
+            }
+            Some(match include {
+                true => Line::Include(pattern.to_string(), parsed),
+                false => Line::Exclude(pattern.to_string(), parsed),
+            })
+        }
+        "." | "merge" | ":" | "dir-merge" => {
+            let mut file = MergeFile::new(pattern);
+            file.modifiers = defaults;
+            for modifier in modifiers.chars() {
+                match modifier {
+                    '-' => file.syntax = Syntax::Patterns(false),
+                    '+' => file.syntax = Syntax::Patterns(true),
+                    'e' => file.exclude_self = true,
+                    '/' => file.modifiers.absolute = true,
+                    's' | 'p' => {}
+                    'r' | 'x' => return Some(Line::Ignored),
+                    'n' => return untestable("non-inherited merge files are not emulated"),
+                    'w' => return untestable("word-split merge files are not emulated"),
+                    'C' => return untestable("CVS merge files are not emulated"),
+                    _ => return untestable(&format!("unknown modifier '{}'", modifier)),
+                }
+            }
+            Some(match name {
+                "." | "merge" => Line::Merge(file),
+                _ => Line::DirMerge(file),
+            })
+        }
+        "P" | "protect" | "R" | "risk" => Some(Line::Ignored),
+        "C" => untestable("CVS default patterns are not emulated"),
+        _ => untestable("not a filter rule"),
+    }
+}
+
+/// Rules and dir-merge points compiled from rule lines, with the rules
+/// that could not be emulated
+#[derive(Default)]
+struct Compiler {
+    items: Vec<Item>,
+    untestable: Vec<String>,
+    /// Merge files being read, to stop a file that merges itself
+    merging: Vec<std::path::PathBuf>,
+}
+
+impl Compiler {
+    /// Add parsed lines to the items, reading merge files as they appear
+    fn push_lines(&mut self, lines: Vec<Line>, origin: &str, base: &str) {
+        for line in lines {
+            match line {
+                Line::Include(pattern, _) | Line::Exclude(pattern, _) if pattern.is_empty() => {}
+                Line::Include(pattern, modifiers) => {
+                    self.push_rule(true, pattern, modifiers, origin, base)
+                }
+                Line::Exclude(pattern, modifiers) => {
+                    self.push_rule(false, pattern, modifiers, origin, base)
+                }
+                Line::Clear => self.items.clear(),
+                Line::Merge(file) => self.merge(&file, origin, base),
+                Line::DirMerge(file) => self.items.push(Item::DirMerge(file)),
+                Line::Ignored => {}
+                Line::Untestable(reason) => {
+                    self.untestable.push(format!("{}: {}", origin, reason));
+                }
+            }
+        }
+    }
+
+    fn push_rule(
+        &mut self,
+        include: bool,
+        pattern: String,
+        modifiers: Modifiers,
+        origin: &str,
+        base: &str,
+    ) {
+        self.items.push(Item::Rule(Compiled {
+            include,
+            pattern,
+            modifiers,
+            base: base.to_string(),
+            origin: origin.to_string(),
+        }));
+    }
+
+    /// Read the rules of a merge file in place of the merge rule
+    fn merge(&mut self, file: &MergeFile, origin: &str, base: &str) {
+        let path = Path::new(&file.name);
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if self.merging.contains(&key) {
+            self.untestable
+                .push(format!("{}: {} merges itself", origin, file.name));
+            return;
+        }
+        if file.exclude_self {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string());
+            if let Some(name) = name {
+                self.push_rule(false, name, Modifiers::default(), origin, base);
+            }
+        }
+        match read_rule_file(path, file.syntax, file.modifiers) {
+            Ok(lines) => {
+                self.merging.push(key);
+                self.push_lines(lines, origin, base);
+                self.merging.pop();
+            }
+            Err(e) => self.untestable.push(format!("{}: {:#}", origin, e)),
+        }
+    }
+}
+
+fn read_rule_file(path: &Path, syntax: Syntax, defaults: Modifiers) -> anyhow::Result<Vec<Line>> {
+    let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    Ok(text
+        .lines()
+        .filter_map(|l| parse_line(l, syntax, defaults))
+        .collect())
+}
+
+/// Turn the rule list into include/exclude items, reading rule files
+fn compile(rules: &[FilterRule]) -> anyhow::Result<Compiler> {
+    let mut compiler = Compiler::default();
+    let plain = Modifiers::default();
+    for (i, rule) in rules.iter().enumerate() {
+        let origin = format!("#{} {} {}", i + 1, rule.kind.label(), rule.pattern);
+        let lines = match rule.kind {
+            RuleKind::Exclude => parse_line(&rule.pattern, Syntax::Prefixed(false), plain)
+                .into_iter()
+                .collect(),
+            RuleKind::Include => parse_line(&rule.pattern, Syntax::Prefixed(true), plain)
+                .into_iter()
+                .collect(),
+            RuleKind::Filter => parse_line(&rule.pattern, Syntax::Rules, plain)
+                .into_iter()
+                .collect(),
+            RuleKind::ExcludeFrom => {
+                read_rule_file(Path::new(&rule.pattern), Syntax::Prefixed(false), plain)?
+            }
+            RuleKind::IncludeFrom => {
+                read_rule_file(Path::new(&rule.pattern), Syntax::Prefixed(true), plain)?
+            }
+            RuleKind::DirMerge => vec![Line::DirMerge(MergeFile::new(&rule.pattern))],
+        };
+        compiler.push_lines(lines, &origin, "");
+    }
+    Ok(compiler)
+}
+
+/// Result of testing one path against the rules
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestedPath {
+    /// Path relative to the transfer root
+    pub path: String,
+    pub is_dir: bool,
+    pub included: bool,
+    /// Rule that decided, or None if no rule matched (included by default)
+    pub rule: Option<String>,
+}
+
+/// What the rules do to the files in a source directory
+#[derive(Debug, Default)]
+pub struct FilterReport {
+    pub entries: Vec<TestedPath>,
+    /// Whether the walk stopped at MAX_ENTRIES
+    pub truncated: bool,
+    /// Rules the tester skipped because it can't emulate them, so the
+    /// result may differ from rsync's
+    pub untestable: Vec<String>,
+}
+
+/// Rules read from per-directory merge files while walking
+struct Merged {
+    /// Index of the DirMerge item the rules belong to
+    item: usize,
+    rules: Vec<Compiled>,
+}
+
+struct Walker {
+    items: Vec<Item>,
+    /// Absolute path of the transfer root without its leading slash
+    root: String,
+    /// Merge-file rules for the directories currently being walked,
+    /// outermost first
+    merged: Vec<(usize, Merged)>,
+    report: FilterReport,
+}
+
+impl Walker {
+    /// First matching rule for `path`: (include, origin)
+    fn decide(&self, path: &str, is_dir: bool) -> Option<(bool, String)> {
+        for (index, item) in self.items.iter().enumerate() {
+            let found = match item {
+                Item::Rule(rule) => rule.matches(path, is_dir, &self.root).then_some(rule),
+                // Rules from deeper merge files take precedence
+                Item::DirMerge(_) => self
+                    .merged
+                    .iter()
+                    .rev()
+                    .filter(|(_, merged)| merged.item == index)
+                    .flat_map(|(_, merged)| merged.rules.iter())
+                    .find(|rule| rule.matches(path, is_dir, &self.root)),
+            };
+            if let Some(rule) = found {
+                return Some((rule.include, rule.origin.clone()));
+            }
+        }
+        None
+    }
+
+    /// Read the per-directory merge files found in `dir`
+    fn enter(&mut self, dir: &Path, relative: &str, depth: usize) {
+        for (index, item) in self.items.iter().enumerate() {
+            let Item::DirMerge(merge) = item else {
+                continue;
+            };
+            let file = dir.join(&merge.name);
+            if !file.is_file() {
+                continue;
+            }
+            let origin = if relative.is_empty() {
+                merge.name.clone()
+            } else {
+                format!("{}/{}", relative, merge.name)
+            };
+            let mut compiler = Compiler::default();
+            let located = MergeFile {
+                name: file.display().to_string(),
+                ..merge.clone()
+            };
+            compiler.merge(&located, &origin, relative);
+            let mut rules = Vec::new();
+            for item in compiler.items {
+                match item {
+                    Item::Rule(rule) => rules.push(rule),
+                    Item::DirMerge(nested) => compiler.untestable.push(format!(
+                        "{}: dir-merge {} (nested dir-merge rules are not emulated)",
+                        origin, nested.name
+                    )),
+                }
+            }
+            self.report.untestable.extend(compiler.untestable);
+            self.merged.push((depth, Merged { item: index, rules }));
+        }
+    }
+
+    fn walk(&mut self, dir: &Path, relative: &str, depth: usize) -> anyhow::Result<()> {
+        self.enter(dir, relative, depth);
+
+        let mut entries: Vec<_> = fs::read_dir(dir)
+            .with_context(|| format!("reading {}", dir.display()))?
+            .filter_map(|e| e.ok())
+            .collect();
+        entries.sort_by_key(|e| e.file_name());
+
+        for entry in entries {
+            if self.report.entries.len() >= MAX_ENTRIES {
+                self.report.truncated = true;
+                break;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = if relative.is_empty() {
+                name
+            } else {
+                format!("{}/{}", relative, name)
+            };
+            // Symlinks are not followed, as with rsync -a
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+
+            let (included, rule) = match self.decide(&path, is_dir) {
+                Some((included, origin)) => (included, Some(origin)),
+                None => (true, None),
+            };
+            self.report.entries.push(TestedPath {
+                path: path.clone(),
+                is_dir,
+                included,
+                rule,
+            });
+
+            // rsync never looks inside an excluded directory
+            if is_dir && included {
+                self.walk(&entry.path(), &path, depth + 1)?;
+            }
+        }
+
+        self.merged.retain(|(d, _)| *d < depth);
+        Ok(())
+    }
+}
+
+/// Walk a local source directory and decide every entry with `rules`.
+/// Like rsync, a source without a trailing slash transfers the directory
+/// itself, so its name becomes the first path component.
+pub fn test_filters(source: &str, rules: &[FilterRule]) -> anyhow::Result<FilterReport> {
+    let root = Path::new(source);
+    if !root.is_dir() {
+        anyhow::bail!("{} is not a local directory", source);
+    }
+
+    let compiler = compile(rules)?;
+    // `/` rules match the absolute path, from the directory holding the
+    // transfer root's first component
+    let absolute = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let parent = match source.ends_with('/') {
+        true => Some(absolute.as_path()),
+        false => absolute.parent(),
+    };
+    let mut walker = Walker {
+        items: compiler.items,
+        root: parent
+            .map(|p| p.to_string_lossy().trim_start_matches('/').to_string())
+            .unwrap_or_default(),
+        merged: Vec::new(),
+        report: FilterReport {
+            untestable: compiler.untestable,
+            ..FilterReport::default()
+        },
+    };
+
+    if source.ends_with('/') {
+        walker.walk(root, "", 0)?;
+    } else {
+        let name = root
+            .canonicalize()
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_default();
+        let (included, rule) = match walker.decide(&name, true) {
+            Some((included, origin)) => (included, Some(origin)),
+            None => (true, None),
+        };
+        walker.report.entries.push(TestedPath {
+            path: name.clone(),
+            is_dir: true,
+            included,
+            rule,
+        });
+        if included {
+            walker.walk(root, &name, 0)?;
+        }
+    }
+    Ok(walker.report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildmatch() {
+        assert!(wildmatch("*.tmp", "a.tmp"));
+        assert!(!wildmatch("*.tmp", "dir/a.tmp"));
+        assert!(wildmatch("**.tmp", "dir/a.tmp"));
+        assert!(wildmatch("a?c", "abc"));
+        assert!(!wildmatch("a?c", "a/c"));
+        assert!(wildmatch("file[0-9].txt", "file7.txt"));
+        assert!(!wildmatch("file[!0-9].txt", "file7.txt"));
+        assert!(wildmatch("[]x]", "]"));
+        assert!(wildmatch("\\*", "*"));
+        assert!(!wildmatch("\\*", "a"));
+        assert!(wildmatch("photos/**", "photos/2024/a.jpg"));
+        assert!(!wildmatch("photos/*", "photos/2024/a.jpg"));
+    }
+
+    #[test]
+    fn test_pattern_final_component() {
+        assert!(pattern_matches("*.log", "a/b/c.log", false));
+        assert!(pattern_matches("cache", "home/cache", true));
+        assert!(!pattern_matches("cache", "home/cache/x", false));
+    }
+
+    #[test]
+    fn test_pattern_anchoring() {
+        assert!(pattern_matches("/tmp", "tmp", true));
+        assert!(!pattern_matches("/tmp", "a/tmp", true));
+        // Unanchored patterns with a slash match at any component boundary
+        assert!(pattern_matches("foo/bar", "x/foo/bar", false));
+        assert!(!pattern_matches("foo/bar", "x/xfoo/bar", false));
+    }
+
+    #[test]
+    fn test_pattern_trailing_slash_and_triple_star() {
+        assert!(pattern_matches("build/", "build", true));
+        assert!(!pattern_matches("build/", "build", false));
+        assert!(pattern_matches("/src/***", "src", true));
+        assert!(pattern_matches("/src/***", "src/a/b.rs", false));
+        assert!(!pattern_matches("/src/***", "srcx", true));
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let rules = |line| parse_line(line, Syntax::Rules, Modifiers::default());
+        let plain = Modifiers::default();
+        assert_eq!(rules("- *.tmp"), Some(Line::Exclude("*.tmp".into(), plain)));
+        assert_eq!(rules("+ */"), Some(Line::Include("*/".into(), plain)));
+        assert_eq!(
+            rules("exclude,s foo"),
+            Some(Line::Exclude("foo".into(), plain))
+        );
+        assert_eq!(
+            rules(": .rsync-filter"),
+            Some(Line::DirMerge(MergeFile::new(".rsync-filter")))
+        );
+        assert_eq!(rules("!"), Some(Line::Clear));
+        assert_eq!(rules("# comment"), None);
+        assert_eq!(rules("P /keep"), Some(Line::Ignored));
+        assert_eq!(rules("-r *.bak"), Some(Line::Ignored));
+        // Plain lines in --exclude-from files
+        assert_eq!(
+            parse_line("*.bak", Syntax::Prefixed(false), plain),
+            Some(Line::Exclude("*.bak".into(), plain))
+        );
+        assert_eq!(
+            parse_line("+ *.bak", Syntax::Prefixed(false), plain),
+            Some(Line::Include("*.bak".into(), plain))
+        );
+        assert!(matches!(rules("*.bak"), Some(Line::Untestable(_))));
+    }
+
+    #[test]
+    fn test_parse_line_modifiers() {
+        let rules = |line| parse_line(line, Syntax::Rules, Modifiers::default());
+        let negated = Modifiers {
+            negate: true,
+            ..Default::default()
+        };
+        let absolute = Modifiers {
+            absolute: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            rules("-! keep/"),
+            Some(Line::Exclude("keep/".into(), negated))
+        );
+        assert_eq!(
+            rules("-!_keep/"),
+            Some(Line::Exclude("keep/".into(), negated))
+        );
+        assert_eq!(
+            rules("+/ /srv/a"),
+            Some(Line::Include("/srv/a".into(), absolute))
+        );
+        assert_eq!(
+            rules("exclude,/ foo"),
+            Some(Line::Exclude("foo".into(), absolute))
+        );
+
+        let gitignore = MergeFile {
+            syntax: Syntax::Patterns(false),
+            ..MergeFile::new(".gitignore")
+        };
+        assert_eq!(
+            rules(":- .gitignore"),
+            Some(Line::DirMerge(gitignore.clone()))
+        );
+        assert_eq!(
+            rules("dir-merge,- .gitignore"),
+            Some(Line::DirMerge(gitignore))
+        );
+        assert_eq!(
+            rules(".e+ list"),
+            Some(Line::Merge(MergeFile {
+                syntax: Syntax::Patterns(true),
+                exclude_self: true,
+                ..MergeFile::new("list")
+            }))
+        );
+        // Lines of a pattern-only file are never rules
+        assert_eq!(
+            parse_line("- foo", Syntax::Patterns(false), absolute),
+            Some(Line::Exclude("- foo".into(), absolute))
+        );
+
+        for line in [
+            "C",
+            "-C foo",
+            ":n .rsync-filter",
+            "dir-merge,w list",
+            "-q foo",
+        ] {
+            assert!(
+                matches!(rules(line), Some(Line::Untestable(_))),
+                "{} is untestable",
+                line
+            );
+        }
+    }
+
+    fn temp_tree(files: &[&str]) -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        for file in files {
+            let path = root.path().join(file);
+            if file.ends_with('/') {
+                fs::create_dir_all(&path).unwrap();
+            } else {
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(&path, "").unwrap();
+            }
+        }
+        root
+    }
+
+    fn verdicts(report: &FilterReport) -> Vec<(String, bool)> {
+        report
+            .entries
+            .iter()
+            .map(|e| (e.path.clone(), e.included))
+            .collect()
+    }
+
+    #[test]
+    fn test_first_match_wins_and_excluded_dirs_not_entered() {
+        let tmp = temp_tree(&[
+            "keep.txt",
+            "photos/a.jpg",
+            "photos/raw/b.cr2",
+            "tmp/x.tmp",
+            "z.tmp",
+        ]);
+        let root = tmp.path();
+        let rules = vec![
+            FilterRule::new(RuleKind::Include, "keep.txt"),
+            FilterRule::new(RuleKind::Exclude, "raw/"),
+            FilterRule::new(RuleKind::Exclude, "tmp/"),
+            FilterRule::new(RuleKind::Exclude, "*.tmp"),
+        ];
+        let source = format!("{}/", root.display());
+        let report = test_filters(&source, &rules).unwrap();
+
+        assert_eq!(
+            verdicts(&report),
+            vec![
+                ("keep.txt".to_string(), true),
+                ("photos".to_string(), true),
+                ("photos/a.jpg".to_string(), true),
+                ("photos/raw".to_string(), false),
+                ("tmp".to_string(), false),
+                ("z.tmp".to_string(), false),
+            ]
+        );
+        assert_eq!(
+            report.entries[0].rule.as_deref(),
+            Some("#1 include keep.txt")
+        );
+        assert_eq!(report.entries[1].rule, None);
+        assert!(!report.truncated);
+    }
+
+    #[test]
+    fn test_source_without_slash_includes_dir_name() {
+        let tmp = temp_tree(&["data/a.txt", "data/b.log"]);
+        let root = tmp.path();
+        let rules = vec![FilterRule::new(RuleKind::Exclude, "/data/b.log")];
+        let source = root.join("data").display().to_string();
+        let report = test_filters(&source, &rules).unwrap();
+
+        assert_eq!(
+            verdicts(&report),
+            vec![
+                ("data".to_string(), true),
+                ("data/a.txt".to_string(), true),
+                ("data/b.log".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dir_merge_files() {
+        let tmp = temp_tree(&["a.log", "sub/b.log", "sub/c.txt", "sub/keep.log"]);
+        let root = tmp.path();
+        fs::write(
+            root.join("sub/.rsync-filter"),
+            "+ keep.log\n- /c.txt\n- *.log\n",
+        )
+        .unwrap();
+        let rules = vec![FilterRule::new(RuleKind::DirMerge, ".rsync-filter")];
+        let source = format!("{}/", root.display());
+        let report = test_filters(&source, &rules).unwrap();
+
+        assert_eq!(
+            verdicts(&report),
+            vec![
+                ("a.log".to_string(), true),
+                ("sub".to_string(), true),
+                ("sub/.rsync-filter".to_string(), true),
+                ("sub/b.log".to_string(), false),
+                ("sub/c.txt".to_string(), false),
+                ("sub/keep.log".to_string(), true),
+            ]
+        );
+        assert_eq!(report.entries[3].rule.as_deref(), Some("sub/.rsync-filter"));
+    }
+
+    #[test]
+    fn test_negated_and_absolute_rules() {
+        let tmp = temp_tree(&["keep/a.txt", "other/b.txt", "c.txt"]);
+        let root = tmp.path();
+        let absolute = root.canonicalize().unwrap().join("c.txt");
+        let rules = vec![
+            FilterRule::new(RuleKind::Filter, &format!("-/ {}", absolute.display())),
+            FilterRule::new(RuleKind::Filter, "+ */"),
+            FilterRule::new(RuleKind::Filter, "-! /keep/**"),
+        ];
+        let report = test_filters(&format!("{}/", root.display()), &rules).unwrap();
+
+        assert_eq!(
+            verdicts(&report),
+            vec![
+                ("c.txt".to_string(), false),
+                ("keep".to_string(), true),
+                ("keep/a.txt".to_string(), true),
+                ("other".to_string(), true),
+                ("other/b.txt".to_string(), false),
+            ]
+        );
+        assert!(report.untestable.is_empty());
+    }
+
+    #[test]
+    fn test_plain_exclude_merge_files() {
+        let tmp = temp_tree(&["a.log", "b.txt", "sub/c.tmp", "sub/d.txt"]);
+        let root = tmp.path();
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(root.join("sub/.gitignore"), "# temp files\n*.tmp\n").unwrap();
+        let source = format!("{}/", root.display());
+
+        for rule in [":- .gitignore", "dir-merge,-e .gitignore"] {
+            let rules = vec![FilterRule::new(RuleKind::Filter, rule)];
+            let report = test_filters(&source, &rules).unwrap();
+            let excluded: Vec<String> = report
+                .entries
+                .iter()
+                .filter(|e| !e.included)
+                .map(|e| e.path.clone())
+                .collect();
+            if rule.contains("-e") {
+                assert_eq!(
+                    excluded,
+                    [".gitignore", "a.log", "sub/.gitignore", "sub/c.tmp"],
+                    "{}",
+                    rule
+                );
+            } else {
+                assert_eq!(excluded, ["a.log", "sub/c.tmp"], "{}", rule);
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_file_including_itself() {
+        let tmp = temp_tree(&["a.tmp", "b.txt"]);
+        let root = tmp.path();
+        let rules_file = root.with_extension("rules");
+        fs::write(
+            &rules_file,
+            format!("- *.tmp\nmerge {}\n", rules_file.display()),
+        )
+        .unwrap();
+        let rules = vec![FilterRule::new(
+            RuleKind::Filter,
+            &format!("merge {}", rules_file.display()),
+        )];
+        let report = test_filters(&format!("{}/", root.display()), &rules).unwrap();
+
+        assert_eq!(
+            verdicts(&report),
+            vec![("a.tmp".to_string(), false), ("b.txt".to_string(), true)]
+        );
+        assert_eq!(report.untestable.len(), 1);
+        assert!(report.untestable[0].contains("merges itself"));
+        fs::remove_file(&rules_file).unwrap();
+    }
+
+    #[test]
+    fn test_untestable_rules_are_reported() {
+        let tmp = temp_tree(&["a.o"]);
+        let root = tmp.path();
+        let rules = vec![
+            FilterRule::new(RuleKind::Filter, "-C"),
+            FilterRule::new(RuleKind::Exclude, "*.o"),
+        ];
+        let report = test_filters(&format!("{}/", root.display()), &rules).unwrap();
+
+        assert_eq!(verdicts(&report), vec![("a.o".to_string(), false)]);
+        assert_eq!(
+            report.untestable,
+            ["#1 filter -C: -C (CVS default patterns are not emulated)"]
+        );
+    }
+
+    #[test]
+    fn test_exclude_from_file() {
+        let tmp = temp_tree(&["a.bak", "b.txt"]);
+        let root = tmp.path();
+        let list = root.with_extension("excl");
+        fs::write(&list, "# backups\n*.bak\n").unwrap();
+        let rules = vec![FilterRule::new(
+            RuleKind::ExcludeFrom,
+            &list.display().to_string(),
+        )];
+        let report = test_filters(&format!("{}/", root.display()), &rules).unwrap();
+
+        assert_eq!(
+            verdicts(&report),
+            vec![("a.bak".to_string(), false), ("b.txt".to_string(), true)]
+        );
+        fs::remove_file(&list).unwrap();
+    }
+
+    #[test]
+    fn test_missing_source() {
+        assert!(test_filters("/nonexistent/source/", &[]).is_err());
+    }
+}
@@ -1,2 +1,15 @@
+pub mod binary;
+pub mod catalog;
 pub mod command;
+pub mod daemon;
+pub mod exit;
+pub mod filter;
+pub mod ignore;
+pub mod itemize;
+pub mod matcher;
 pub mod options;
+pub mod parse;
+pub mod plan;
+pub mod progress;
+pub mod runner;
+pub mod stats;
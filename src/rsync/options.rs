@@ -1,47 +1,204 @@
-/// Rsync command options
-#[derive(Debug, Clone)]
-pub struct RsyncOptions {
-    pub archive: bool,        // -a
-    pub verbose: bool,        // -v
-    pub compress: bool,       // -z
-    pub dry_run: bool,        // -n
-    pub progress: bool,       // --progress
-    pub delete: bool,         // --delete
-    pub human_readable: bool, // -h
-    pub use_ssh: bool,        // -e ssh
-    pub exclude: Vec<String>,
+use serde::de::Deserializer;
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::catalog::{self, ValueType, CATALOG};
+use super::filter::{FilterRule, RuleKind};
+
+/// Value of a single option
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OptionValue {
+    Bool(bool),
+    Int(i64),
+    Text(String),
+    List(Vec<String>),
+}
+
+impl OptionValue {
+    /// Whether the value has the right shape for `value_type`
+    fn fits(&self, value_type: ValueType) -> bool {
+        match (self, value_type) {
+            (OptionValue::Bool(_), ValueType::Bool)
+            | (OptionValue::Int(_), ValueType::Int)
+            | (OptionValue::Text(_), ValueType::Text)
+            | (OptionValue::List(_), ValueType::List) => true,
+            (OptionValue::Text(choice), ValueType::Enum(choices)) => {
+                choices.contains(&choice.as_str())
+            }
+            _ => false,
+        }
+    }
 }
 
-impl Default for RsyncOptions {
-    fn default() -> Self {
-        Self {
-            archive: true,
-            verbose: true,
-            compress: false,
-            dry_run: false,
-            progress: true,
-            delete: false,
-            human_readable: true,
-            use_ssh: false,
-            exclude: Vec::new(),
+impl fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionValue::Bool(on) => write!(f, "{}", on),
+            OptionValue::Int(n) => write!(f, "{}", n),
+            OptionValue::Text(text) => write!(f, "{}", text),
+            OptionValue::List(items) => write!(f, "{}", items.join(", ")),
         }
     }
 }
 
+/// Rsync command options, described by the option catalog, plus the
+/// ordered filter rules.
+/// Only values that differ from the catalog defaults are stored; saved
+/// profiles list every option, and missing ones take their defaults.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RsyncOptions {
+    values: BTreeMap<&'static str, OptionValue>,
+    pub filters: Vec<FilterRule>,
+    /// Arguments the catalog doesn't know, passed to rsync unchanged
+    pub extra_args: Vec<String>,
+}
+
 impl RsyncOptions {
-    /// Toggle an option by index (0-7)
-    pub fn toggle(&mut self, index: usize) {
-        match index {
-            0 => self.archive = !self.archive,
-            1 => self.verbose = !self.verbose,
-            2 => self.compress = !self.compress,
-            3 => self.dry_run = !self.dry_run,
-            4 => self.progress = !self.progress,
-            5 => self.delete = !self.delete,
-            6 => self.human_readable = !self.human_readable,
-            7 => self.use_ssh = !self.use_ssh,
-            _ => {}
+    /// Whether a Bool option is on
+    pub fn is_enabled(&self, id: &str) -> bool {
+        let spec = catalog::find(id);
+        debug_assert!(spec.is_some(), "unknown option {}", id);
+        match self.values.get(id) {
+            Some(OptionValue::Bool(on)) => *on,
+            _ => spec.is_some_and(|spec| spec.default),
+        }
+    }
+
+    /// Switch a Bool option on or off. Switching it on turns off any options
+    /// it conflicts with, whose ids are returned.
+    pub fn set_enabled(&mut self, id: &str, on: bool) -> Vec<&'static str> {
+        let _ = self.set(id, Some(OptionValue::Bool(on)));
+        if on {
+            self.clear_conflicts(id)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Flip a Bool option, returning the ids of options turned off by conflicts
+    pub fn toggle(&mut self, id: &str) -> Vec<&'static str> {
+        self.set_enabled(id, !self.is_enabled(id))
+    }
+
+    /// Value of an Int, Text or Enum option (None if unset)
+    pub fn value(&self, id: &str) -> Option<&OptionValue> {
+        self.values.get(id)
+    }
+
+    /// Entries of a List option
+    pub fn list(&self, id: &str) -> &[String] {
+        match self.values.get(id) {
+            Some(OptionValue::List(items)) => items,
+            _ => &[],
+        }
+    }
+
+    /// Set (or with None, reset) an option's value
+    pub fn set(&mut self, id: &str, value: Option<OptionValue>) -> anyhow::Result<()> {
+        let Some(spec) = catalog::find(id) else {
+            anyhow::bail!("unknown option '{}'", id);
+        };
+        match value {
+            Some(value) if !value.fits(spec.value) => {
+                anyhow::bail!("invalid value {:?} for {}", value, spec.flag)
+            }
+            // Defaults are not stored so equal options compare equal
+            Some(OptionValue::Bool(on)) if on == spec.default => {
+                self.values.remove(spec.id);
+            }
+            Some(OptionValue::List(items)) if items.is_empty() => {
+                self.values.remove(spec.id);
+            }
+            Some(value) => {
+                self.values.insert(spec.id, value);
+            }
+            None => {
+                self.values.remove(spec.id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Turn off or reset the options that conflict with `id`
+    fn clear_conflicts(&mut self, id: &str) -> Vec<&'static str> {
+        let Some(spec) = catalog::find(id) else {
+            return Vec::new();
+        };
+        let mut cleared = Vec::new();
+        for other in spec.conflicts.iter().filter_map(|id| catalog::find(id)) {
+            let is_bool = other.value == ValueType::Bool;
+            let active = if is_bool {
+                self.is_enabled(other.id)
+            } else {
+                self.values.contains_key(other.id)
+            };
+            if active {
+                let _ = self.set(other.id, is_bool.then_some(OptionValue::Bool(false)));
+                cleared.push(other.id);
+            }
+        }
+        cleared
+    }
+}
+
+impl Serialize for RsyncOptions {
+    /// Every option with its current value, so profiles don't depend on
+    /// defaults changing later
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for spec in CATALOG {
+            match spec.value {
+                ValueType::Bool => map.serialize_entry(spec.id, &self.is_enabled(spec.id))?,
+                ValueType::List => map.serialize_entry(spec.id, self.list(spec.id))?,
+                _ => {
+                    if let Some(value) = self.value(spec.id) {
+                        map.serialize_entry(spec.id, value)?;
+                    }
+                }
+            }
         }
+        map.serialize_entry("filters", &self.filters)?;
+        if !self.extra_args.is_empty() {
+            map.serialize_entry("extra_args", &self.extra_args)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for RsyncOptions {
+    /// Unknown options and values of the wrong type are ignored
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = BTreeMap::<String, serde_json::Value>::deserialize(deserializer)?;
+        let mut options = RsyncOptions::default();
+        let mut legacy_excludes: Vec<String> = Vec::new();
+        for (id, value) in raw {
+            match id.as_str() {
+                "filters" => {
+                    options.filters = serde_json::from_value(value).unwrap_or_default();
+                }
+                "extra_args" => {
+                    options.extra_args = serde_json::from_value(value).unwrap_or_default();
+                }
+                // Profiles written before filter rules kept a plain exclude list
+                "exclude" => {
+                    legacy_excludes = serde_json::from_value(value).unwrap_or_default();
+                }
+                _ => {
+                    if let Ok(value) = serde_json::from_value::<Option<OptionValue>>(value) {
+                        let _ = options.set(&id, value);
+                    }
+                }
+            }
+        }
+        options.filters.extend(
+            legacy_excludes
+                .iter()
+                .map(|pattern| FilterRule::new(RuleKind::Exclude, pattern)),
+        );
+        Ok(options)
     }
 }
 
@@ -53,93 +210,129 @@ mod tests {
     fn test_default_values() {
         let opts = RsyncOptions::default();
 
-        assert!(opts.archive);
-        assert!(opts.verbose);
-        assert!(!opts.compress);
-        assert!(!opts.dry_run);
-        assert!(opts.progress);
-        assert!(!opts.delete);
-        assert!(opts.human_readable);
-        assert!(!opts.use_ssh);
-        assert!(opts.exclude.is_empty());
+        assert!(opts.is_enabled("archive"));
+        assert!(opts.is_enabled("verbose"));
+        assert!(!opts.is_enabled("compress"));
+        assert!(!opts.is_enabled("dry_run"));
+        assert!(opts.is_enabled("progress"));
+        assert!(!opts.is_enabled("delete"));
+        assert!(opts.is_enabled("human_readable"));
+        assert!(!opts.is_enabled("use_ssh"));
+        assert!(!opts.is_enabled("progress_total"));
+        assert!(!opts.is_enabled("itemize_changes"));
+        assert!(opts.list("link_dest").is_empty());
+        assert!(opts.filters.is_empty());
+        assert!(opts.value("max_delete").is_none());
     }
 
     #[test]
     fn test_toggle_archive() {
         let mut opts = RsyncOptions::default();
-        assert!(opts.archive);
-        opts.toggle(0);
-        assert!(!opts.archive);
-        opts.toggle(0);
-        assert!(opts.archive);
+        assert!(opts.is_enabled("archive"));
+        opts.toggle("archive");
+        assert!(!opts.is_enabled("archive"));
+        opts.toggle("archive");
+        assert!(opts.is_enabled("archive"));
     }
 
     #[test]
-    fn test_toggle_verbose() {
-        let mut opts = RsyncOptions::default();
-        assert!(opts.verbose);
-        opts.toggle(1);
-        assert!(!opts.verbose);
+    fn test_toggle_every_bool_option() {
+        for spec in CATALOG.iter().filter(|s| s.value == ValueType::Bool) {
+            let mut opts = RsyncOptions::default();
+            assert_eq!(opts.is_enabled(spec.id), spec.default, "{}", spec.id);
+            opts.toggle(spec.id);
+            assert_eq!(opts.is_enabled(spec.id), !spec.default, "{}", spec.id);
+        }
     }
 
     #[test]
-    fn test_toggle_compress() {
+    fn test_toggled_back_equals_default() {
         let mut opts = RsyncOptions::default();
-        assert!(!opts.compress);
-        opts.toggle(2);
-        assert!(opts.compress);
+        opts.toggle("compress");
+        assert_ne!(opts, RsyncOptions::default());
+        opts.toggle("compress");
+        assert_eq!(opts, RsyncOptions::default());
     }
 
     #[test]
-    fn test_toggle_dry_run() {
+    fn test_enabling_clears_conflicts() {
         let mut opts = RsyncOptions::default();
-        assert!(!opts.dry_run);
-        opts.toggle(3);
-        assert!(opts.dry_run);
+        assert!(opts.toggle("sparse").is_empty());
+        assert_eq!(opts.toggle("inplace"), vec!["sparse"]);
+        assert!(opts.is_enabled("inplace"));
+        assert!(!opts.is_enabled("sparse"));
     }
 
     #[test]
-    fn test_toggle_progress() {
+    fn test_set_value_checks_type() {
         let mut opts = RsyncOptions::default();
-        assert!(opts.progress);
-        opts.toggle(4);
-        assert!(!opts.progress);
-    }
+        opts.set("max_delete", Some(OptionValue::Int(100))).unwrap();
+        assert_eq!(opts.value("max_delete"), Some(&OptionValue::Int(100)));
 
-    #[test]
-    fn test_toggle_delete() {
-        let mut opts = RsyncOptions::default();
-        assert!(!opts.delete);
-        opts.toggle(5);
-        assert!(opts.delete);
+        assert!(opts
+            .set("max_delete", Some(OptionValue::Text("x".into())))
+            .is_err());
+        assert!(opts.set("unknown", Some(OptionValue::Bool(true))).is_err());
+
+        let choice = |c: &str| Some(OptionValue::Text(c.to_string()));
+        opts.set("checksum_choice", choice("xxh3")).unwrap();
+        assert!(opts.set("checksum_choice", choice("sha1")).is_err());
+
+        opts.set("max_delete", None).unwrap();
+        assert!(opts.value("max_delete").is_none());
     }
 
     #[test]
-    fn test_toggle_human_readable() {
-        let mut opts = RsyncOptions::default();
-        assert!(opts.human_readable);
-        opts.toggle(6);
-        assert!(!opts.human_readable);
+    fn test_deserialize_missing_fields_use_defaults() {
+        let opts: RsyncOptions = serde_json::from_str(r#"{"compress": true}"#).unwrap();
+
+        assert!(opts.is_enabled("compress"));
+        assert!(opts.is_enabled("archive"));
+        assert!(opts.is_enabled("progress"));
+        assert!(opts.filters.is_empty());
     }
 
     #[test]
-    fn test_toggle_use_ssh() {
-        let mut opts = RsyncOptions::default();
-        assert!(!opts.use_ssh);
-        opts.toggle(7);
-        assert!(opts.use_ssh);
+    fn test_deserialize_ignores_unknown_and_invalid() {
+        let opts: RsyncOptions = serde_json::from_str(
+            r#"{"colour": "blue", "delete": "yes", "timeout": 30, "exclude": ["*.tmp"]}"#,
+        )
+        .unwrap();
+
+        assert!(!opts.is_enabled("delete"));
+        assert_eq!(opts.value("timeout"), Some(&OptionValue::Int(30)));
+        // A plain exclude list from older profiles becomes filter rules
+        assert_eq!(
+            opts.filters,
+            vec![FilterRule::new(RuleKind::Exclude, "*.tmp")]
+        );
     }
 
     #[test]
-    fn test_toggle_invalid_index() {
+    fn test_serialize_roundtrip() {
         let mut opts = RsyncOptions::default();
-        let original = opts.clone();
-        opts.toggle(8); // Invalid index
-        opts.toggle(100); // Invalid index
-
-        // All values should remain unchanged
-        assert_eq!(opts.archive, original.archive);
-        assert_eq!(opts.verbose, original.verbose);
-        assert_eq!(opts.compress, original.compress);
+        opts.toggle("checksum");
+        opts.toggle("archive");
+        opts.set("bwlimit", Some(OptionValue::Text("1.5m".into())))
+            .unwrap();
+        opts.set(
+            "link_dest",
+            Some(OptionValue::List(vec!["/backup/prev".to_string()])),
+        )
+        .unwrap();
+        opts.filters = vec![
+            FilterRule::new(RuleKind::Include, "*/"),
+            FilterRule::new(RuleKind::Exclude, "*.log"),
+        ];
+        opts.extra_args = vec!["--chmod=D755".to_string()];
+
+        let json = serde_json::to_value(&opts).unwrap();
+        // Defaults are written out too
+        assert_eq!(json["verbose"], true);
+        assert_eq!(json["archive"], false);
+        assert!(json.get("max_delete").is_none());
+
+        let loaded: RsyncOptions = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded, opts);
     }
 }
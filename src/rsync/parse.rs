@@ -0,0 +1,704 @@
+use std::path::Path;
+
+use super::catalog::{OptionSpec, ValueType, CATALOG};
+use super::filter::{FilterRule, RuleKind};
+use super::options::{OptionValue, RsyncOptions};
+
+/// An rsync command line turned back into TUI state
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedCommand {
+    pub source: String,
+    pub destination: String,
+    /// Recognised options; anything else is kept in `extra_args`
+    pub options: RsyncOptions,
+}
+
+/// Long forms of short flags the catalog lists by their short form
+const LONG_ALIASES: &[(&str, &str)] = &[
+    ("--archive", "-a"),
+    ("--verbose", "-v"),
+    ("--compress", "-z"),
+    ("--dry-run", "-n"),
+    ("--human-readable", "-h"),
+    ("--itemize-changes", "-i"),
+];
+
+/// Short forms of flags the catalog lists by their long form
+const SHORT_ALIASES: &[(char, &str)] = &[
+    ('c', "--checksum"),
+    ('H', "--hard-links"),
+    ('A', "--acls"),
+    ('X', "--xattrs"),
+    ('S', "--sparse"),
+    ('W', "--whole-file"),
+    ('u', "--update"),
+    ('x', "--one-file-system"),
+];
+
+/// Short options that take a value
+const SHORT_WITH_VALUE: &[char] = &['e', 'f', 'B', 'T', 'M', '@'];
+
+/// Long options outside the catalog that take a separate value, so the
+/// value isn't mistaken for a path
+const LONG_WITH_VALUE: &[&str] = &[
+    "--address",
+    "--block-size",
+    "--checksum-seed",
+    "--chmod",
+    "--chown",
+    "--compare-dest",
+    "--compress-choice",
+    "--compress-level",
+    "--contimeout",
+    "--copy-dest",
+    "--debug",
+    "--files-from",
+    "--groupmap",
+    "--iconv",
+    "--info",
+    "--log-file",
+    "--log-file-format",
+    "--max-alloc",
+    "--max-size",
+    "--min-size",
+    "--modify-window",
+    "--out-format",
+    "--partial-dir",
+    "--protocol",
+    "--remote-option",
+    "--rsync-path",
+    "--skip-compress",
+    "--sockopts",
+    "--stop-after",
+    "--stop-at",
+    "--suffix",
+    "--temp-dir",
+    "--usermap",
+    "--write-batch",
+    "--only-write-batch",
+    "--read-batch",
+];
+
+/// Split a command line into words the way a POSIX shell would: quotes,
+/// backslash escapes and line continuations. Variables and other expansions
+/// are kept literally; operators such as `|` or `&&` are refused.
+pub fn tokenize(line: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    // Whether a word has started, so "" gives an empty argument
+    let mut in_word = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' | '\r' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '#' if !in_word => break,
+            '|' | ';' | '&' | '<' | '>' | '(' | ')' | '`' => {
+                anyhow::bail!("unsupported shell syntax '{}'", c)
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => anyhow::bail!("unterminated single quote"),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ ('$' | '`' | '"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => anyhow::bail!("unterminated double quote"),
+                        },
+                        Some(c) => word.push(c),
+                        None => anyhow::bail!("unterminated double quote"),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // Line continuation
+                Some('\n') => {}
+                Some(c) => {
+                    in_word = true;
+                    word.push(c);
+                }
+                None => anyhow::bail!("trailing backslash"),
+            },
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Parse a shell command line such as `rsync -avz --delete src/ host:dst/`
+pub fn parse_command(line: &str) -> anyhow::Result<ParsedCommand> {
+    let words = tokenize(line)?;
+    if words.is_empty() {
+        anyhow::bail!("empty command");
+    }
+    parse_args(&words)
+}
+
+/// Whether the long option `flag`, given without `=VALUE`, takes the next
+/// argument as its value
+pub fn long_takes_value(flag: &str) -> bool {
+    flag == "--rsh"
+        || valued_option(flag).is_some()
+        || filter_kind(flag).is_some()
+        || LONG_WITH_VALUE.contains(&flag)
+}
+
+/// Whether the short option `c` takes a value, from the rest of its
+/// cluster or the next argument
+pub fn short_takes_value(c: char) -> bool {
+    SHORT_WITH_VALUE.contains(&c)
+}
+
+/// Bool option whose flag is exactly `flag`
+fn bool_option(flag: &str) -> Option<&'static OptionSpec> {
+    CATALOG
+        .iter()
+        .find(|spec| spec.value == ValueType::Bool && spec.flag == flag)
+}
+
+/// Valued option whose flag is `flag`
+fn valued_option(flag: &str) -> Option<&'static OptionSpec> {
+    CATALOG
+        .iter()
+        .find(|spec| spec.value != ValueType::Bool && spec.flag == flag)
+}
+
+/// Bool option named after `--no-`, in its short or long form
+fn negated_option(name: &str) -> Option<&'static OptionSpec> {
+    let mut chars = name.chars();
+    let flag = match (chars.next(), chars.next()) {
+        (Some(c), None) => SHORT_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == c)
+            .map_or(format!("-{}", c), |(_, long)| long.to_string()),
+        _ => {
+            let long = format!("--{}", name);
+            LONG_ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == long)
+                .map_or(long, |(_, short)| short.to_string())
+        }
+    };
+    bool_option(&flag)
+}
+
+fn filter_kind(flag: &str) -> Option<RuleKind> {
+    match flag {
+        "--exclude" => Some(RuleKind::Exclude),
+        "--include" => Some(RuleKind::Include),
+        "--filter" => Some(RuleKind::Filter),
+        "--exclude-from" => Some(RuleKind::ExcludeFrom),
+        "--include-from" => Some(RuleKind::IncludeFrom),
+        _ => None,
+    }
+}
+
+/// Filter rule for `--filter VALUE`, recognising the dir-merge rules
+/// `build_command` writes
+fn filter_rule(kind: RuleKind, value: &str) -> FilterRule {
+    if kind == RuleKind::Filter {
+        for prefix in ["dir-merge ", ": "] {
+            if let Some(file) = value.strip_prefix(prefix) {
+                return FilterRule::new(RuleKind::DirMerge, file);
+            }
+        }
+    }
+    FilterRule::new(kind, value)
+}
+
+/// Store a valued option given on the command line
+fn set_value(options: &mut RsyncOptions, spec: &OptionSpec, value: &str) -> anyhow::Result<()> {
+    match spec.value {
+        ValueType::Int => {
+            let n = value.parse().map_err(|_| {
+                anyhow::anyhow!("{} needs a whole number, got '{}'", spec.flag, value)
+            })?;
+            options.set(spec.id, Some(OptionValue::Int(n)))
+        }
+        ValueType::List => {
+            let mut items = options.list(spec.id).to_vec();
+            items.push(value.to_string());
+            options.set(spec.id, Some(OptionValue::List(items)))
+        }
+        _ => options.set(spec.id, Some(OptionValue::Text(value.to_string()))),
+    }
+}
+
+/// Parse rsync arguments, with or without the program name. The last path
+/// is the destination and the first the source; further sources and
+/// options the catalog doesn't know are kept as extra arguments.
+pub fn parse_args(args: &[String]) -> anyhow::Result<ParsedCommand> {
+    let mut args = args.iter().peekable();
+    if let Some(first) = args.peek() {
+        if Path::new(first.as_str())
+            .file_name()
+            .is_some_and(|n| n == "rsync")
+        {
+            args.next();
+        }
+    }
+
+    // Options not on the command line are off, whatever their defaults
+    let mut options = RsyncOptions::default();
+    for spec in CATALOG.iter().filter(|s| s.value == ValueType::Bool) {
+        options.set_enabled(spec.id, false);
+    }
+    let mut paths = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            paths.extend(args.by_ref().cloned());
+            break;
+        }
+
+        if let Some(long) = arg.strip_prefix("--") {
+            let (flag, inline) = match long.split_once('=') {
+                Some((name, value)) => (format!("--{}", name), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+            let flag = LONG_ALIASES
+                .iter()
+                .find(|(long, _)| *long == flag)
+                .map_or(flag.as_str(), |(_, short)| short);
+
+            // Flags with a fixed value such as --info=progress2 match whole
+            if let Some(spec) = bool_option(arg).or_else(|| bool_option(flag)) {
+                if inline.is_none() || spec.flag == arg {
+                    options.set_enabled(spec.id, true);
+                    continue;
+                }
+            }
+            // --no-OPTION turns an option off, e.g. --no-v or --no-compress
+            if let Some(spec) = flag.strip_prefix("--no-").and_then(negated_option) {
+                options.set_enabled(spec.id, false);
+                continue;
+            }
+
+            let mut value = || -> anyhow::Result<String> {
+                inline
+                    .clone()
+                    .or_else(|| args.next().cloned())
+                    .ok_or_else(|| anyhow::anyhow!("{} requires a value", flag))
+            };
+            if flag == "--rsh" {
+                set_shell(&mut options, &value()?);
+            } else if let Some(spec) = valued_option(flag) {
+                set_value(&mut options, spec, &value()?)?;
+            } else if let Some(kind) = filter_kind(flag) {
+                options.filters.push(filter_rule(kind, &value()?));
+            } else if inline.is_none() && LONG_WITH_VALUE.contains(&flag) {
+                let value = value()?;
+                options.extra_args.push(arg.clone());
+                options.extra_args.push(value);
+            } else {
+                options.extra_args.push(arg.clone());
+            }
+        } else if let Some(cluster) = arg.strip_prefix('-').filter(|c| !c.is_empty()) {
+            parse_short(&mut options, cluster, &mut args)?;
+        } else {
+            paths.push(arg.clone());
+        }
+    }
+
+    let destination = if paths.len() > 1 {
+        paths.pop().unwrap_or_default()
+    } else {
+        String::new()
+    };
+    let mut paths = paths.into_iter();
+    let source = paths.next().unwrap_or_default();
+    // rsync treats every path before the destination as a source
+    options.extra_args.extend(paths);
+
+    Ok(ParsedCommand {
+        source,
+        destination,
+        options,
+    })
+}
+
+/// A cluster of short flags such as `avzP` or `e'ssh -p 22'`
+fn parse_short<'a>(
+    options: &mut RsyncOptions,
+    cluster: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> anyhow::Result<()> {
+    for (i, c) in cluster.char_indices() {
+        if short_takes_value(c) {
+            // The rest of the cluster, or the next argument, is the value
+            let rest = &cluster[i + c.len_utf8()..];
+            let value = if rest.is_empty() {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("-{} requires a value", c))?
+            } else {
+                rest.to_string()
+            };
+            match c {
+                'e' => set_shell(options, &value),
+                'f' => options.filters.push(filter_rule(RuleKind::Filter, &value)),
+                _ => {
+                    options.extra_args.push(format!("-{}", c));
+                    options.extra_args.push(value);
+                }
+            }
+            return Ok(());
+        }
+
+        // -P is --partial --progress
+        if c == 'P' {
+            options.set_enabled("partial", true);
+            options.set_enabled("progress", true);
+            continue;
+        }
+
+        let short = format!("-{}", c);
+        let flag = SHORT_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == c)
+            .map_or(short.as_str(), |(_, long)| long);
+        match bool_option(flag) {
+            Some(spec) => {
+                options.set_enabled(spec.id, true);
+            }
+            None => options.extra_args.push(short),
+        }
+    }
+    Ok(())
+}
+
+/// Split an `-e` command the way rsync does: on spaces, keeping single- or
+/// double-quoted text together, where a doubled quote stands for one
+pub fn split_remote_shell(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, ' ') => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (Some(q), c) if c == q => {
+                if chars.peek() == Some(&q) {
+                    chars.next();
+                    word.push(q);
+                } else {
+                    quote = None;
+                }
+            }
+            (_, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// SSH settings for an `-e` command, or None if it uses anything the
+/// settings can't express
+fn ssh_settings(command: &str) -> Option<Vec<(&'static str, OptionValue)>> {
+    let words = split_remote_shell(command);
+    let (program, rest) = words.split_first()?;
+    let mut settings = Vec::new();
+    if program != "ssh" {
+        settings.push(("ssh_program", OptionValue::Text(program.clone())));
+    }
+
+    let mut options = Vec::new();
+    let mut rest = rest.iter();
+    while let Some(word) = rest.next() {
+        // Values may follow the flag directly, as in -p2222
+        let flag = word.get(..2)?;
+        let value = match &word[2..] {
+            "" => rest.next()?.clone(),
+            attached => attached.to_string(),
+        };
+        let spec = CATALOG.iter().find(|s| {
+            s.ssh && s.value != ValueType::Bool && s.flag == flag && s.id != "ssh_program"
+        })?;
+        let value = match spec.value {
+            ValueType::Int => OptionValue::Int(value.parse().ok()?),
+            ValueType::List => {
+                options.push(value);
+                continue;
+            }
+            _ => OptionValue::Text(value),
+        };
+        // A repeated flag can't be expressed by a single setting
+        if settings.iter().any(|(id, _)| *id == spec.id) {
+            return None;
+        }
+        settings.push((spec.id, value));
+    }
+    if !options.is_empty() {
+        settings.push(("ssh_options", OptionValue::List(options)));
+    }
+    Some(settings)
+}
+
+/// An ssh `-e` command turns SSH on with matching settings; any other remote
+/// shell is passed through
+fn set_shell(options: &mut RsyncOptions, shell: &str) {
+    let Some(settings) = ssh_settings(shell) else {
+        options.extra_args.push("-e".to_string());
+        options.extra_args.push(shell.to_string());
+        return;
+    };
+    options.set_enabled("use_ssh", true);
+    for (id, value) in settings {
+        let _ = options.set(id, Some(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rsync::command::build_command;
+
+    fn words(line: &str) -> Vec<String> {
+        tokenize(line).unwrap()
+    }
+
+    #[test]
+    fn test_tokenize_quotes() {
+        assert_eq!(
+            words("rsync -a 'my dir/' \"b c\""),
+            ["rsync", "-a", "my dir/", "b c"]
+        );
+        assert_eq!(words(r#"a\ b "x\"y" 'it'\''s'"#), ["a b", "x\"y", "it's"]);
+        assert_eq!(
+            words("rsync -a \\\n  src dst"),
+            ["rsync", "-a", "src", "dst"]
+        );
+        assert_eq!(words("a \"\" b # comment"), ["a", "", "b"]);
+        assert_eq!(words(r#""$HOME\n""#), ["$HOME\\n"]);
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        assert!(tokenize("rsync 'open").is_err());
+        assert!(tokenize("rsync \"open").is_err());
+        assert!(tokenize("rsync a b && echo done").is_err());
+        assert!(tokenize("rsync a b | tee log").is_err());
+    }
+
+    #[test]
+    fn test_parse_short_and_long_flags() {
+        let parsed =
+            parse_command("rsync -avzP --delete --human-readable --max-delete=10 src/ host:/dst/")
+                .unwrap();
+        let opts = &parsed.options;
+        for id in [
+            "archive",
+            "verbose",
+            "compress",
+            "partial",
+            "progress",
+            "delete",
+            "human_readable",
+        ] {
+            assert!(opts.is_enabled(id), "{}", id);
+        }
+        assert!(!opts.is_enabled("dry_run"));
+        assert_eq!(opts.value("max_delete"), Some(&OptionValue::Int(10)));
+        assert_eq!(parsed.source, "src/");
+        assert_eq!(parsed.destination, "host:/dst/");
+        assert!(opts.extra_args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_filters_and_values() {
+        let parsed = parse_command(
+            "rsync -a --exclude='*.tmp' --include '*/' -f '- .git/' \
+             --filter 'dir-merge .rsync-filter' --link-dest /a --link-dest=/b \
+             --checksum-choice xxh3 --port 8730 --password-file=/etc/nas.pw src dst",
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.options.filters,
+            vec![
+                FilterRule::new(RuleKind::Exclude, "*.tmp"),
+                FilterRule::new(RuleKind::Include, "*/"),
+                FilterRule::new(RuleKind::Filter, "- .git/"),
+                FilterRule::new(RuleKind::DirMerge, ".rsync-filter"),
+            ]
+        );
+        assert_eq!(parsed.options.list("link_dest"), ["/a", "/b"]);
+        assert_eq!(
+            parsed.options.value("checksum_choice"),
+            Some(&OptionValue::Text("xxh3".into()))
+        );
+        assert_eq!(parsed.options.value("port"), Some(&OptionValue::Int(8730)));
+        assert_eq!(
+            parsed.options.value("password_file"),
+            Some(&OptionValue::Text("/etc/nas.pw".into()))
+        );
+    }
+
+    #[test]
+    fn test_unrecognised_arguments_pass_through() {
+        let parsed = parse_command(
+            "/usr/bin/rsync -avr --chmod D755 --fuzzy -e 'ssh -v' --no-v --no-D a b host:c",
+        )
+        .unwrap();
+        let opts = &parsed.options;
+        assert!(opts.is_enabled("archive"));
+        assert!(!opts.is_enabled("use_ssh"));
+        assert_eq!(
+            opts.extra_args,
+            [
+                "-r",
+                "--chmod",
+                "D755",
+                "--fuzzy",
+                "-e",
+                "ssh -v",
+                "--no-D",
+                "b"
+            ]
+        );
+        assert!(!opts.is_enabled("verbose"));
+        assert_eq!(parsed.source, "a");
+        assert_eq!(parsed.destination, "host:c");
+    }
+
+    #[test]
+    fn test_ssh_command() {
+        let parsed = parse_command(
+            "rsync -a -e \"ssh -p2222 -l backup -i '/keys/my key' -J bastion \
+             -o StrictHostKeyChecking=no -oControlMaster=auto\" a host:b",
+        )
+        .unwrap();
+        let opts = &parsed.options;
+        assert!(opts.is_enabled("use_ssh"));
+        assert_eq!(opts.value("ssh_port"), Some(&OptionValue::Int(2222)));
+        assert_eq!(
+            opts.value("ssh_identity"),
+            Some(&OptionValue::Text("/keys/my key".into()))
+        );
+        assert_eq!(
+            opts.list("ssh_options"),
+            ["StrictHostKeyChecking=no", "ControlMaster=auto"]
+        );
+        assert!(opts.extra_args.is_empty());
+
+        // A remote shell the settings can't express is kept as it is
+        let parsed = parse_command("rsync -e 'ssh -p 22 -p 23' a host:b").unwrap();
+        assert!(!parsed.options.is_enabled("use_ssh"));
+        assert_eq!(parsed.options.extra_args, ["-e", "ssh -p 22 -p 23"]);
+    }
+
+    #[test]
+    fn test_split_remote_shell() {
+        assert_eq!(
+            split_remote_shell("ssh -i 'a b' -o \"x=''y\" 'it''s'"),
+            ["ssh", "-i", "a b", "-o", "x=''y", "it's"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        assert!(parse_command("rsync --max-delete=lots a b").is_err());
+        assert!(parse_command("rsync --checksum-choice=crc32 a b").is_err());
+        assert!(parse_command("rsync a b --exclude").is_err());
+    }
+
+    #[test]
+    fn test_roundtrip_with_build_command() {
+        let mut options = RsyncOptions::default();
+        options.set_enabled("verbose", false);
+        for id in [
+            "compress",
+            "delete",
+            "progress_total",
+            "checksum",
+            "sparse",
+            "use_ssh",
+            "numeric_ids",
+        ] {
+            options.set_enabled(id, true);
+        }
+        options.set("timeout", Some(OptionValue::Int(30))).unwrap();
+        options
+            .set("bwlimit", Some(OptionValue::Text("1.5m".into())))
+            .unwrap();
+        options
+            .set(
+                "link_dest",
+                Some(OptionValue::List(vec!["/snap/1".into(), "/snap/2".into()])),
+            )
+            .unwrap();
+        options
+            .set("ssh_port", Some(OptionValue::Int(2222)))
+            .unwrap();
+        options
+            .set(
+                "ssh_identity",
+                Some(OptionValue::Text("/keys/it's mine".into())),
+            )
+            .unwrap();
+        options
+            .set(
+                "ssh_options",
+                Some(OptionValue::List(vec!["ControlMaster=auto".into()])),
+            )
+            .unwrap();
+        options.filters = vec![
+            FilterRule::new(RuleKind::Include, "photos/**"),
+            FilterRule::new(RuleKind::DirMerge, ".rsync-filter"),
+            FilterRule::new(RuleKind::ExcludeFrom, "/etc/backup.excl"),
+            FilterRule::new(RuleKind::Exclude, "*"),
+        ];
+        options.extra_args = vec!["--chmod".into(), "D755".into(), "--fuzzy".into()];
+
+        let args = build_command("/home/me/", "backup:/srv/me/", &options);
+        let parsed = parse_args(&args).unwrap();
+        assert_eq!(parsed.source, "/home/me/");
+        assert_eq!(parsed.destination, "backup:/srv/me/");
+        assert_eq!(parsed.options, options);
+
+        // Default options survive too
+        let args = build_command("a", "b", &RsyncOptions::default());
+        assert_eq!(parse_args(&args).unwrap().options, RsyncOptions::default());
+    }
+}
@@ -0,0 +1,213 @@
+use super::itemize::{ChangeKind, ItemizedChange};
+
+/// Output format used for dry runs so each line carries the file size
+pub const PLAN_OUT_FORMAT: &str = "--out-format=%i %l %n";
+
+/// Created/updated/deleted counts and bytes to transfer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlanTotals {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub bytes: u64,
+}
+
+impl PlanTotals {
+    fn add(&mut self, kind: ChangeKind, size: u64) {
+        match kind {
+            ChangeKind::Created => self.created += 1,
+            ChangeKind::Updated | ChangeKind::Attributes => self.updated += 1,
+            ChangeKind::Deleted => self.deleted += 1,
+            ChangeKind::Unchanged => return,
+        }
+        if kind != ChangeKind::Deleted {
+            self.bytes += size;
+        }
+    }
+}
+
+/// A file or directory in the dry-run tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanNode {
+    pub name: String,
+    pub is_dir: bool,
+    /// Change to this item itself (None for directories only implied by paths)
+    pub kind: Option<ChangeKind>,
+    pub size: u64,
+    /// Totals for this item and everything below it
+    pub totals: PlanTotals,
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    fn dir(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            is_dir: true,
+            kind: None,
+            size: 0,
+            totals: PlanTotals::default(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Insert a change at `parts` (path components) below this node
+    fn insert(&mut self, parts: &[&str], is_dir: bool, kind: ChangeKind, size: u64) {
+        self.totals.add(kind, size);
+        let Some((first, rest)) = parts.split_first() else {
+            return;
+        };
+
+        let index = match self.children.iter().position(|c| c.name == *first) {
+            Some(index) => index,
+            None => {
+                self.children.push(PlanNode::dir(first));
+                self.children.len() - 1
+            }
+        };
+        let child = &mut self.children[index];
+        if rest.is_empty() {
+            child.is_dir = is_dir;
+            child.kind = Some(kind);
+            child.size = size;
+            child.totals.add(kind, size);
+        } else {
+            child.insert(rest, is_dir, kind, size);
+        }
+    }
+
+    fn sort(&mut self) {
+        // Directories first, then by name
+        self.children
+            .sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then(a.name.cmp(&b.name)));
+        for child in &mut self.children {
+            child.sort();
+        }
+    }
+}
+
+/// Result of a dry run: the command to execute for real and the tree of
+/// changes it predicted
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub command: Vec<String>,
+    pub root: PlanNode,
+}
+
+impl Plan {
+    pub fn new(command: Vec<String>, changes: &[(ItemizedChange, u64)]) -> Self {
+        let mut root = PlanNode::dir("");
+        for (change, size) in changes {
+            let parts: Vec<&str> = change
+                .path
+                .split('/')
+                .filter(|p| !p.is_empty() && *p != ".")
+                .collect();
+            // Skip the transfer root itself ("./")
+            if parts.is_empty() {
+                continue;
+            }
+            // Directory sizes are metadata, not data to transfer
+            let is_dir = change.path.ends_with('/');
+            let size = if is_dir { 0 } else { *size };
+            root.insert(&parts, is_dir, change.kind, size);
+        }
+        root.sort();
+        Self { command, root }
+    }
+
+    pub fn totals(&self) -> PlanTotals {
+        self.root.totals
+    }
+
+    /// Node at the given directory path (empty path is the root)
+    pub fn node(&self, path: &[String]) -> Option<&PlanNode> {
+        let mut node = &self.root;
+        for name in path {
+            node = node.children.iter().find(|c| &c.name == name)?;
+        }
+        Some(node)
+    }
+}
+
+/// Format a byte count as e.g. "1.5 MB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rsync::itemize::parse_itemize_sized;
+
+    fn plan(lines: &[&str]) -> Plan {
+        let changes: Vec<_> = lines
+            .iter()
+            .filter_map(|l| parse_itemize_sized(l))
+            .collect();
+        Plan::new(vec!["rsync".to_string()], &changes)
+    }
+
+    #[test]
+    fn test_plan_totals() {
+        let plan = plan(&[
+            ".d..t...... 4,096 ./",
+            "cd+++++++++ 4,096 photos/",
+            ">f+++++++++ 1,000 photos/a.jpg",
+            ">f.st...... 500 notes.txt",
+            "*deleting   0 old.txt",
+        ]);
+
+        let totals = plan.totals();
+        assert_eq!(totals.created, 2);
+        assert_eq!(totals.updated, 1);
+        assert_eq!(totals.deleted, 1);
+        assert_eq!(totals.bytes, 1_500);
+    }
+
+    #[test]
+    fn test_plan_tree_and_navigation() {
+        let plan = plan(&[
+            ">f+++++++++ 10 z.txt",
+            ">f+++++++++ 1,000 photos/2024/a.jpg",
+            "*deleting   0 photos/old.jpg",
+        ]);
+
+        // Directories sort before files
+        let names: Vec<&str> = plan.root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["photos", "z.txt"]);
+
+        let photos = plan.node(&["photos".to_string()]).unwrap();
+        assert!(photos.is_dir);
+        assert_eq!(photos.kind, None);
+        assert_eq!(photos.totals.created, 1);
+        assert_eq!(photos.totals.deleted, 1);
+        assert_eq!(photos.totals.bytes, 1_000);
+
+        let year = plan
+            .node(&["photos".to_string(), "2024".to_string()])
+            .unwrap();
+        assert_eq!(year.children[0].name, "a.jpg");
+        assert_eq!(year.children[0].kind, Some(ChangeKind::Created));
+
+        assert!(plan.node(&["missing".to_string()]).is_none());
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1_500), "1.5 kB");
+        assert_eq!(format_bytes(2_340_000_000), "2.3 GB");
+    }
+}
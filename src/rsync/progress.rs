@@ -0,0 +1,298 @@
+/// A parsed rsync progress line, either per-file (`--progress`) or
+/// whole-transfer (`--info=progress2`). Both share the same layout:
+/// "     1,234,567  45%   12.34MB/s    0:01:23 (xfr#12, to-chk=100/200)"
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressLine {
+    pub bytes: u64,
+    pub percent: f64,
+    pub rate: String,
+    pub eta: String,
+    /// Number of files transferred so far (`xfr#N`)
+    pub transferred: Option<u64>,
+    /// Files still to check and total files (`to-chk=R/T` or `ir-chk=R/T`)
+    pub to_check: Option<(u64, u64)>,
+}
+
+/// Parse rsync progress output line
+/// Example: "     1,234,567  45%   12.34MB/s    0:01:23"
+pub fn parse_progress(line: &str) -> Option<ProgressLine> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+
+    // Layout is "<bytes> <percent>% <rate> <eta> [(xfr#N, to-chk=R/T)]"
+    let bytes = parse_size(parts.first()?)?;
+    let percent = parts.get(1)?.strip_suffix('%')?.parse::<f64>().ok()?;
+    let rate = parts.get(2).map(|s| s.to_string()).unwrap_or_default();
+    let eta = parts.get(3).map(|s| s.to_string()).unwrap_or_default();
+
+    let mut transferred = None;
+    let mut to_check = None;
+    for part in parts.iter().skip(4) {
+        let part = part.trim_matches(|c| c == '(' || c == ')' || c == ',');
+        if let Some(n) = part.strip_prefix("xfr#") {
+            transferred = n.parse().ok();
+        } else if let Some((_, counts)) = part.split_once("chk=").or(part.split_once("check=")) {
+            if let Some((remaining, total)) = counts.split_once('/') {
+                if let (Ok(r), Ok(t)) = (remaining.parse(), total.parse()) {
+                    to_check = Some((r, t));
+                }
+            }
+        }
+    }
+
+    Some(ProgressLine {
+        bytes,
+        percent: percent.min(100.0),
+        rate,
+        eta,
+        transferred,
+        to_check,
+    })
+}
+
+/// Parse a byte count such as "1,234,567" or a human-readable "1.23M"
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.replace(',', "");
+    let (number, multiplier) = match s.chars().last()? {
+        'K' | 'k' => (&s[..s.len() - 1], 1e3),
+        'M' => (&s[..s.len() - 1], 1e6),
+        'G' => (&s[..s.len() - 1], 1e9),
+        'T' => (&s[..s.len() - 1], 1e12),
+        'P' => (&s[..s.len() - 1], 1e15),
+        _ => (s.as_str(), 1.0),
+    };
+    let value = number.parse::<f64>().ok()?;
+    Some((value * multiplier) as u64)
+}
+
+/// How progress lines relate to the whole transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// `--progress`: percentages are for the current file
+    PerFile,
+    /// `--info=progress2`: percentages are for the whole transfer
+    Total,
+}
+
+impl ProgressMode {
+    /// Detect the progress mode from the rsync command line
+    pub fn from_args(args: &[String]) -> Self {
+        if args.iter().any(|a| a == "--info=progress2") {
+            ProgressMode::Total
+        } else {
+            ProgressMode::PerFile
+        }
+    }
+}
+
+/// Turns progress lines into an overall transfer percentage
+#[derive(Debug, Clone)]
+pub struct ProgressTracker {
+    mode: ProgressMode,
+    files_done: u64,
+    files_total: u64,
+}
+
+impl ProgressTracker {
+    pub fn new(mode: ProgressMode) -> Self {
+        Self {
+            mode,
+            files_done: 0,
+            files_total: 0,
+        }
+    }
+
+    /// Overall percentage of the transfer after this progress line
+    pub fn update(&mut self, line: &ProgressLine) -> f64 {
+        if self.mode == ProgressMode::Total {
+            return line.percent;
+        }
+
+        // Per-file mode: the to-chk counts appear when a file completes
+        if let Some((remaining, total)) = line.to_check {
+            self.files_total = total;
+            self.files_done = total.saturating_sub(remaining);
+        } else if self.files_total > 0 {
+            // Credit the partially transferred current file
+            let done = self.files_done as f64 + line.percent / 100.0;
+            return (done / self.files_total as f64 * 100.0).min(100.0);
+        }
+
+        if self.files_total == 0 {
+            // Total not known yet (first file still in flight)
+            return line.percent;
+        }
+        self.files_done as f64 / self.files_total as f64 * 100.0
+    }
+
+    /// Speed, time and file counts for the gauge label
+    pub fn info(&self, line: &ProgressLine) -> String {
+        let mut info = format!("{} {}", line.rate, line.eta).trim().to_string();
+        if self.files_total > 0 {
+            info.push_str(&format!(
+                " ({}/{} files)",
+                self.files_done, self.files_total
+            ));
+        }
+        info
+    }
+}
+
+/// A piece of output terminated by `\n` or `\r`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub text: String,
+    /// Ended with `\r`: rsync will overwrite it with the next update
+    pub rewrite: bool,
+}
+
+/// Splits raw output on both `\r` and `\n` so in-place progress
+/// updates are seen as they happen rather than at the next newline
+#[derive(Debug, Default)]
+pub struct Tokenizer {
+    pending: Vec<u8>,
+}
+
+impl Tokenizer {
+    /// Feed a chunk of output, returning every completed segment
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        for &byte in bytes {
+            match byte {
+                b'\n' | b'\r' => {
+                    if let Some(segment) = self.take(byte == b'\r') {
+                        segments.push(segment);
+                    }
+                }
+                _ => self.pending.push(byte),
+            }
+        }
+        segments
+    }
+
+    /// Flush any trailing output without a terminator
+    pub fn finish(&mut self) -> Option<Segment> {
+        self.take(false)
+    }
+
+    fn take(&mut self, rewrite: bool) -> Option<Segment> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        Some(Segment { text, rewrite })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress_line() {
+        let result = parse_progress("     1,234,567  45%   12.34MB/s    0:01:23").unwrap();
+        assert_eq!(result.bytes, 1_234_567);
+        assert_eq!(result.percent, 45.0);
+        assert_eq!(result.rate, "12.34MB/s");
+        assert_eq!(result.eta, "0:01:23");
+        assert_eq!(result.transferred, None);
+        assert_eq!(result.to_check, None);
+    }
+
+    #[test]
+    fn test_parse_progress_file_complete() {
+        let result =
+            parse_progress("      1,048,576 100%   10.00MB/s    0:00:00 (xfr#3, to-chk=5/8)")
+                .unwrap();
+        assert_eq!(result.percent, 100.0);
+        assert_eq!(result.transferred, Some(3));
+        assert_eq!(result.to_check, Some((5, 8)));
+    }
+
+    #[test]
+    fn test_parse_progress2_incremental() {
+        let result = parse_progress(
+            "          2.15G  37%  104.56MB/s    0:00:19 (xfr#610, ir-chk=1032/2143)",
+        )
+        .unwrap();
+        assert_eq!(result.bytes, 2_150_000_000);
+        assert_eq!(result.percent, 37.0);
+        assert_eq!(result.to_check, Some((1032, 2143)));
+    }
+
+    #[test]
+    fn test_parse_progress_no_percentage() {
+        assert_eq!(parse_progress("sending incremental file list"), None);
+        assert_eq!(parse_progress("file 50% done.txt"), None);
+    }
+
+    #[test]
+    fn test_parse_progress_clamps_to_100() {
+        let result = parse_progress("  100  150%  1.00kB/s  0:00:00");
+        assert_eq!(result.map(|p| p.percent), Some(100.0));
+    }
+
+    #[test]
+    fn test_mode_from_args() {
+        let args = vec!["rsync".to_string(), "--info=progress2".to_string()];
+        assert_eq!(ProgressMode::from_args(&args), ProgressMode::Total);
+        let args = vec!["rsync".to_string(), "--progress".to_string()];
+        assert_eq!(ProgressMode::from_args(&args), ProgressMode::PerFile);
+    }
+
+    #[test]
+    fn test_tracker_total_mode_uses_percent() {
+        let mut tracker = ProgressTracker::new(ProgressMode::Total);
+        let line = parse_progress("  1,000  37%  1.00MB/s  0:00:19 (xfr#6, to-chk=10/20)").unwrap();
+        assert_eq!(tracker.update(&line), 37.0);
+    }
+
+    #[test]
+    fn test_tracker_per_file_uses_file_counts() {
+        let mut tracker = ProgressTracker::new(ProgressMode::PerFile);
+
+        // First file in flight: total unknown, fall back to file percent
+        let line = parse_progress("  500  50%  1.00MB/s  0:00:01").unwrap();
+        assert_eq!(tracker.update(&line), 50.0);
+
+        // First file completes: 1 of 4 done
+        let line = parse_progress("  1,000  100%  1.00MB/s  0:00:00 (xfr#1, to-chk=3/4)").unwrap();
+        assert_eq!(tracker.update(&line), 25.0);
+
+        // Second file half way: gauge does not drop back to 50%
+        let line = parse_progress("  500  50%  1.00MB/s  0:00:01").unwrap();
+        assert_eq!(tracker.update(&line), 37.5);
+        assert_eq!(tracker.info(&line), "1.00MB/s 0:00:01 (1/4 files)");
+    }
+
+    #[test]
+    fn test_tokenizer_splits_on_carriage_return() {
+        let mut tokenizer = Tokenizer::default();
+        let segments = tokenizer.push(b"file.txt\n  100  10%\r  500  50%\r  1000 100%\n");
+
+        let texts: Vec<(&str, bool)> = segments
+            .iter()
+            .map(|s| (s.text.as_str(), s.rewrite))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("file.txt", false),
+                ("  100  10%", true),
+                ("  500  50%", true),
+                ("  1000 100%", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_keeps_partial_segments() {
+        let mut tokenizer = Tokenizer::default();
+        assert!(tokenizer.push(b"par").is_empty());
+        let segments = tokenizer.push(b"tial\r\nnext");
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].text, "partial");
+        assert_eq!(tokenizer.finish().map(|s| s.text), Some("next".to_string()));
+        assert_eq!(tokenizer.finish(), None);
+    }
+}
@@ -0,0 +1,302 @@
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use super::progress::{parse_progress, ProgressMode, ProgressTracker, Segment, Tokenizer};
+
+/// Output stream a line was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// A line of rsync output tagged with its stream and arrival time
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputLine {
+    pub stream: Stream,
+    /// Time since the transfer started
+    pub elapsed: Duration,
+    pub text: String,
+}
+
+impl OutputLine {
+    /// Format as "[mm:ss.mmm] text", with "[ERR] " before stderr text
+    pub fn display(&self) -> String {
+        let millis = self.elapsed.as_millis();
+        let stamp = format!(
+            "[{:02}:{:02}.{:03}]",
+            millis / 60_000,
+            (millis / 1000) % 60,
+            millis % 1000
+        );
+        match self.stream {
+            Stream::Stdout => format!("{} {}", stamp, self.text),
+            Stream::Stderr => format!("{} [ERR] {}", stamp, self.text),
+        }
+    }
+}
+
+/// Event streamed from a background rsync transfer to the UI
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferEvent {
+    /// A line from stdout or stderr, in arrival order
+    Line(OutputLine),
+    /// Overall progress parsed from the output (percentage, speed/time info)
+    Progress(f64, String),
+    /// rsync exited with the given code (None if killed by a signal)
+    Finished(Option<i32>),
+    /// Waiting for the rsync process failed
+    Failed(String),
+}
+
+/// Handle to a running background transfer
+pub struct Transfer {
+    /// Process id of the rsync child (None if it already exited)
+    pub pid: Option<u32>,
+    /// Events streamed from the transfer task
+    pub events: UnboundedReceiver<TransferEvent>,
+}
+
+/// Signals used to control a running transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Interrupt, // SIGINT - graceful cancel
+    Terminate, // SIGTERM - forced cancel
+    Stop,      // SIGSTOP - pause
+    Continue,  // SIGCONT - resume
+}
+
+/// Spawn rsync in the background and stream its output over a channel.
+/// `args` is the full command line as produced by `build_command` and `env`
+/// the variables from `command_env`.
+/// Must be called from within a tokio runtime.
+pub fn spawn(args: &[String], env: &[(String, String)]) -> std::io::Result<Transfer> {
+    let mut child = Command::new(&args[0])
+        .args(&args[1..])
+        .envs(env.iter().cloned())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let pid = child.id();
+    let started = Instant::now();
+    let tracker = ProgressTracker::new(ProgressMode::from_args(args));
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        // Read both pipes concurrently so neither can fill up and block rsync
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        tokio::join!(
+            read_stream(stdout, Stream::Stdout, started, Some(tracker), tx.clone()),
+            read_stream(stderr, Stream::Stderr, started, None, tx.clone()),
+        );
+
+        let event = match child.wait().await {
+            Ok(status) => TransferEvent::Finished(status.code()),
+            Err(e) => TransferEvent::Failed(e.to_string()),
+        };
+        let _ = tx.send(event);
+    });
+
+    Ok(Transfer { pid, events: rx })
+}
+
+/// Send a control signal to the rsync process
+pub fn send_signal(pid: u32, signal: Signal) -> std::io::Result<()> {
+    let sig = match signal {
+        Signal::Interrupt => libc::SIGINT,
+        Signal::Terminate => libc::SIGTERM,
+        Signal::Stop => libc::SIGSTOP,
+        Signal::Continue => libc::SIGCONT,
+    };
+    // SAFETY: kill() has no memory-safety preconditions
+    let result = unsafe { libc::kill(pid as libc::pid_t, sig) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Forward every line of a pipe as it arrives, splitting on `\r` as well
+/// as `\n` so in-place progress updates are seen immediately
+async fn read_stream<R: AsyncRead + Unpin>(
+    pipe: Option<R>,
+    stream: Stream,
+    started: Instant,
+    mut tracker: Option<ProgressTracker>,
+    tx: UnboundedSender<TransferEvent>,
+) {
+    let Some(mut pipe) = pipe else {
+        return;
+    };
+    let mut tokenizer = Tokenizer::default();
+    let mut buf = [0u8; 4096];
+    while let Ok(n) = pipe.read(&mut buf).await {
+        if n == 0 {
+            break;
+        }
+        for segment in tokenizer.push(&buf[..n]) {
+            send_segment(&tx, stream, started.elapsed(), tracker.as_mut(), segment);
+        }
+    }
+    if let Some(segment) = tokenizer.finish() {
+        send_segment(&tx, stream, started.elapsed(), tracker.as_mut(), segment);
+    }
+}
+
+/// Send a segment as a line, with a progress event first if it contains one.
+/// In-place progress updates (ending in `\r`) only update the gauge.
+fn send_segment(
+    tx: &UnboundedSender<TransferEvent>,
+    stream: Stream,
+    elapsed: Duration,
+    tracker: Option<&mut ProgressTracker>,
+    segment: Segment,
+) {
+    if let (Some(tracker), Some(progress)) = (tracker, parse_progress(&segment.text)) {
+        let percent = tracker.update(&progress);
+        let _ = tx.send(TransferEvent::Progress(percent, tracker.info(&progress)));
+        if segment.rewrite {
+            return;
+        }
+    }
+    let _ = tx.send(TransferEvent::Line(OutputLine {
+        stream,
+        elapsed,
+        text: segment.text,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn collect(mut rx: UnboundedReceiver<TransferEvent>) -> Vec<TransferEvent> {
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        events
+    }
+
+    fn sh(script: &str) -> Vec<String> {
+        vec!["sh".to_string(), "-c".to_string(), script.to_string()]
+    }
+
+    fn lines(events: &[TransferEvent]) -> Vec<(Stream, String)> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                TransferEvent::Line(line) => Some((line.stream, line.text.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_spawn_streams_output_and_exit_code() {
+        let args = sh("echo hello; echo '  10  50%  1.00MB/s  0:00:01'; echo oops >&2; exit 3");
+        let events = collect(spawn(&args, &[]).unwrap().events).await;
+
+        assert!(lines(&events).contains(&(Stream::Stdout, "hello".to_string())));
+        assert!(events.contains(&TransferEvent::Progress(
+            50.0,
+            "1.00MB/s 0:00:01".to_string()
+        )));
+        assert!(
+            lines(&events).contains(&(Stream::Stdout, "  10  50%  1.00MB/s  0:00:01".to_string()))
+        );
+        assert!(lines(&events).contains(&(Stream::Stderr, "oops".to_string())));
+        assert_eq!(events.last(), Some(&TransferEvent::Finished(Some(3))));
+    }
+
+    #[tokio::test]
+    async fn test_streams_interleave_in_arrival_order() {
+        let args = sh("echo one; sleep 0.2; echo two >&2; sleep 0.2; echo three");
+        let events = collect(spawn(&args, &[]).unwrap().events).await;
+
+        assert_eq!(
+            lines(&events),
+            vec![
+                (Stream::Stdout, "one".to_string()),
+                (Stream::Stderr, "two".to_string()),
+                (Stream::Stdout, "three".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_carriage_return_updates_only_move_gauge() {
+        let args = sh("printf 'big.iso\\n  100  10%%  1.00MB/s  0:00:09\\r  500  50%%  1.00MB/s  0:00:05\\r  1,000 100%%  1.00MB/s  0:00:00 (xfr#1, to-chk=1/2)\\n'");
+        let events = collect(spawn(&args, &[]).unwrap().events).await;
+
+        let percents: Vec<f64> = events
+            .iter()
+            .filter_map(|e| match e {
+                TransferEvent::Progress(p, _) => Some(*p),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(percents, vec![10.0, 50.0, 50.0]);
+        assert_eq!(lines(&events).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_large_stderr_does_not_deadlock() {
+        // Far more than a pipe buffer on stderr before anything on stdout
+        let args = sh("i=0; while [ $i -lt 5000 ]; do echo \"error line $i\" >&2; i=$((i+1)); done; echo done");
+        let events = collect(spawn(&args, &[]).unwrap().events).await;
+
+        assert_eq!(lines(&events).len(), 5001);
+        assert_eq!(events.last(), Some(&TransferEvent::Finished(Some(0))));
+    }
+
+    #[test]
+    fn test_output_line_display() {
+        let line = OutputLine {
+            stream: Stream::Stderr,
+            elapsed: Duration::from_millis(61_234),
+            text: "failed".to_string(),
+        };
+        assert_eq!(line.display(), "[01:01.234] [ERR] failed");
+
+        let line = OutputLine {
+            stream: Stream::Stdout,
+            ..line
+        };
+        assert_eq!(line.display(), "[01:01.234] failed");
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_stops_transfer() {
+        let args: Vec<String> = ["sleep", "30"].iter().map(|s| s.to_string()).collect();
+        let transfer = spawn(&args, &[]).unwrap();
+        let pid = transfer.pid.unwrap();
+
+        send_signal(pid, Signal::Stop).unwrap();
+        send_signal(pid, Signal::Continue).unwrap();
+        send_signal(pid, Signal::Interrupt).unwrap();
+
+        let events = collect(transfer.events).await;
+        assert_eq!(events.last(), Some(&TransferEvent::Finished(None)));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_passes_environment() {
+        let env = vec![("RSYNC_PASSWORD".to_string(), "s3cret".to_string())];
+        let events = collect(spawn(&sh("echo $RSYNC_PASSWORD"), &env).unwrap().events).await;
+        assert_eq!(lines(&events), vec![(Stream::Stdout, "s3cret".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_missing_binary() {
+        let args = vec!["rsync_tui_no_such_binary".to_string()];
+        assert!(spawn(&args, &[]).is_err());
+    }
+}
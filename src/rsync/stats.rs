@@ -0,0 +1,193 @@
+//! Parsing of the `--stats` trailer rsync prints after a transfer, such as
+//! "Number of created files: 10 (reg: 8, dir: 2)" or
+//! "total size is 1,234,567  speedup is 99.54".
+
+use serde::{Deserialize, Serialize};
+
+use super::plan::format_bytes;
+use super::progress::parse_size;
+
+/// Summary of a transfer from its `--stats` output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransferStats {
+    /// Files and directories in the transfer
+    pub files: u64,
+    /// Created and deleted counts (missing before rsync 3.1.0)
+    pub created: Option<u64>,
+    pub deleted: Option<u64>,
+    /// Regular files whose data was sent
+    pub transferred: u64,
+    /// Size of all files, and of those transferred
+    pub total_size: u64,
+    pub transferred_size: u64,
+    /// Bytes sent as new data and bytes reused from the destination
+    pub literal: u64,
+    pub matched: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// Total size divided by the bytes sent and received
+    pub speedup: f64,
+}
+
+impl TransferStats {
+    /// Take the value from one line of output, returning whether it was a
+    /// line of the trailer
+    pub fn apply(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        if let Some((label, value)) = line.split_once(": ") {
+            let number = first_number(value);
+            let field = match label {
+                "Number of files" => &mut self.files,
+                "Number of regular files transferred" | "Number of files transferred" => {
+                    &mut self.transferred
+                }
+                "Total file size" => &mut self.total_size,
+                "Total transferred file size" => &mut self.transferred_size,
+                "Literal data" => &mut self.literal,
+                "Matched data" => &mut self.matched,
+                "Total bytes sent" => &mut self.bytes_sent,
+                "Total bytes received" => &mut self.bytes_received,
+                "Number of created files" => {
+                    self.created = number;
+                    return number.is_some();
+                }
+                "Number of deleted files" => {
+                    self.deleted = number;
+                    return number.is_some();
+                }
+                _ => return false,
+            };
+            return match number {
+                Some(number) => {
+                    *field = number;
+                    true
+                }
+                None => false,
+            };
+        }
+        // "total size is 1,234,567  speedup is 99.54 (DRY RUN)"
+        if let Some(rest) = line.strip_prefix("total size is ") {
+            let mut words = rest.split_whitespace();
+            let total = words.next().and_then(parse_size);
+            let speedup = words
+                .skip_while(|word| *word != "is")
+                .nth(1)
+                .and_then(|word| word.replace(',', "").parse().ok());
+            if let (Some(total), Some(speedup)) = (total, speedup) {
+                self.total_size = total;
+                self.speedup = speedup;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// One line for the Progress panel and logs
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{} files", self.files)];
+        if let Some(created) = self.created {
+            parts.push(format!("{} created", created));
+        }
+        if let Some(deleted) = self.deleted {
+            parts.push(format!("{} deleted", deleted));
+        }
+        parts.push(format!("{} transferred", self.transferred));
+        format!(
+            "{}; {} of {}, speedup {:.2}",
+            parts.join(", "),
+            format_bytes(self.transferred_size),
+            format_bytes(self.total_size),
+            self.speedup
+        )
+    }
+}
+
+/// Leading number of a value such as "1,234 (reg: 1,000, dir: 234)",
+/// "123,456 bytes" or "1.23M bytes"
+fn first_number(value: &str) -> Option<u64> {
+    parse_size(value.split_whitespace().next()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stats from a whole run's output, as the app and headless runs
+    /// collect them line by line
+    fn parse_stats<'a>(lines: impl IntoIterator<Item = &'a str>) -> Option<TransferStats> {
+        let mut stats: Option<TransferStats> = None;
+        for line in lines {
+            let mut next = stats.unwrap_or_default();
+            if next.apply(line) {
+                stats = Some(next);
+            }
+        }
+        stats
+    }
+
+    const STATS_3_2: &str = "\
+Number of files: 1,234 (reg: 1,000, dir: 234)
+Number of created files: 10 (reg: 8, dir: 2)
+Number of deleted files: 3 (reg: 3)
+Number of regular files transferred: 12
+Total file size: 123,456,789 bytes
+Total transferred file size: 1,234,567 bytes
+Literal data: 1,034,567 bytes
+Matched data: 200,000 bytes
+File list size: 12,345
+File list generation time: 0.001 seconds
+File list transfer time: 0.000 seconds
+Total bytes sent: 1,040,000
+Total bytes received: 300
+
+sent 1,040,000 bytes  received 300 bytes  826,866.67 bytes/sec
+total size is 123,456,789  speedup is 118.73
+";
+
+    #[test]
+    fn test_parse_stats() {
+        let stats = parse_stats(STATS_3_2.lines()).unwrap();
+        assert_eq!(
+            stats,
+            TransferStats {
+                files: 1234,
+                created: Some(10),
+                deleted: Some(3),
+                transferred: 12,
+                total_size: 123_456_789,
+                transferred_size: 1_234_567,
+                literal: 1_034_567,
+                matched: 200_000,
+                bytes_sent: 1_040_000,
+                bytes_received: 300,
+                speedup: 118.73,
+            }
+        );
+        assert_eq!(
+            stats.summary(),
+            "1234 files, 10 created, 3 deleted, 12 transferred; 1.2 MB of 123.5 MB, speedup 118.73"
+        );
+    }
+
+    #[test]
+    fn test_parse_old_and_human_readable_stats() {
+        // rsync 2.6.9 has no created/deleted counts
+        let old = "Number of files: 5\nNumber of files transferred: 2\n\
+                   total size is 2048  speedup is 1.50 (DRY RUN)";
+        let stats = parse_stats(old.lines()).unwrap();
+        assert_eq!((stats.files, stats.transferred), (5, 2));
+        assert_eq!((stats.created, stats.deleted), (None, None));
+        assert_eq!(stats.speedup, 1.5);
+
+        let human = "Total file size: 1.23M bytes\nTotal bytes sent: 4.56K";
+        let stats = parse_stats(human.lines()).unwrap();
+        assert_eq!(stats.total_size, 1_230_000);
+        assert_eq!(stats.bytes_sent, 4_560);
+
+        assert_eq!(
+            parse_stats(["sending incremental file list", "a.txt"]),
+            None
+        );
+    }
+}
@@ -0,0 +1,415 @@
+use serde::{Deserialize, Serialize};
+
+use crate::rsync::parse;
+
+/// Rules applied before running a transfer that can delete files
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SafetyPolicy {
+    /// Refuse to run until the same command has been dry-run
+    pub require_dry_run: bool,
+    /// Refuse to run if the last dry run predicted more deletions than this
+    pub max_deletions: Option<usize>,
+}
+
+impl SafetyPolicy {
+    /// Rules in force, such as "require dry run" (empty if none)
+    pub fn rules(&self) -> Vec<String> {
+        let mut rules = Vec::new();
+        if self.require_dry_run {
+            rules.push("require dry run".to_string());
+        }
+        if let Some(max) = self.max_deletions {
+            rules.push(format!("max {} deletions", max));
+        }
+        rules
+    }
+}
+
+/// Setting of the safety policy, listed in the Options panel after the
+/// rsync options
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicySetting {
+    RequireDryRun,
+    MaxDeletions,
+}
+
+impl PolicySetting {
+    pub const ALL: [PolicySetting; 2] = [Self::RequireDryRun, Self::MaxDeletions];
+
+    /// Field of the policy in saved profiles
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::RequireDryRun => "require_dry_run",
+            Self::MaxDeletions => "max_deletions",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::RequireDryRun => "Require dry run",
+            Self::MaxDeletions => "Max deletions",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::RequireDryRun => {
+                "Refuse runs that delete files until the same command has been dry-run (Ctrl+n)"
+            }
+            Self::MaxDeletions => {
+                "Refuse runs that delete files unless a dry run of the same command predicted at most this many deletions"
+            }
+        }
+    }
+}
+
+/// Result of the last dry run this session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRunRecord {
+    /// Real command the dry run was made for
+    pub command: Vec<String>,
+    pub deletions: usize,
+}
+
+/// What to do before running a command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// Not destructive, run immediately
+    Proceed,
+    /// Destructive, ask the user to confirm
+    Confirm,
+    /// Destructive and not allowed by the policy
+    Block(String),
+}
+
+/// Whether the command can delete files: `--delete` and its variants, the
+/// `--del` alias and `--remove-source-files`
+pub fn is_destructive(command: &[String]) -> bool {
+    let deletes = options(command, false)
+        .iter()
+        .any(|opt| opt.starts_with("--delete") || opt == "--del" || opt == "--remove-source-files");
+    deletes && !is_dry_run(command)
+}
+
+/// Whether the command only reports what it would do (`-n`/`--dry-run`).
+/// Only options before the first path count: rsync also reads options
+/// after it, but treating those commands as real runs errs on the side of
+/// confirming.
+pub fn is_dry_run(command: &[String]) -> bool {
+    options(command, true)
+        .iter()
+        .any(|opt| opt == "--dry-run" || opt == "-n")
+}
+
+/// Options of an rsync command line, without the program name or option
+/// values: long options without `=VALUE`, and each flag of a short cluster
+/// such as `-avn` on its own. Values given as separate arguments are
+/// skipped, so `--filter "- node_modules/"` is not read as `-n`.
+/// Scanning ends at `--`, and with `stop_at_path` at the first path.
+fn options(command: &[String], stop_at_path: bool) -> Vec<String> {
+    let mut found = Vec::new();
+    let mut args = command.iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            match long.split_once('=') {
+                Some((name, _)) => found.push(format!("--{}", name)),
+                None => {
+                    if parse::long_takes_value(arg) {
+                        args.next();
+                    }
+                    found.push(arg.clone());
+                }
+            }
+        } else if let Some(cluster) = arg.strip_prefix('-').filter(|c| !c.is_empty()) {
+            for (i, c) in cluster.char_indices() {
+                found.push(format!("-{}", c));
+                if parse::short_takes_value(c) {
+                    // The value is the rest of the cluster or the next argument
+                    if i + c.len_utf8() == cluster.len() {
+                        args.next();
+                    }
+                    break;
+                }
+            }
+        } else if stop_at_path {
+            break;
+        }
+    }
+    found
+}
+
+/// Decide whether `command` may run under `policy`
+pub fn check(
+    command: &[String],
+    policy: &SafetyPolicy,
+    last_dry_run: Option<&DryRunRecord>,
+) -> Verdict {
+    if !is_destructive(command) {
+        return Verdict::Proceed;
+    }
+
+    // Only a dry run of this exact command counts
+    let dry_run = last_dry_run.filter(|r| r.command == command);
+
+    if policy.require_dry_run && dry_run.is_none() {
+        return Verdict::Block(
+            "Policy requires a dry run of this command first (Ctrl+n)".to_string(),
+        );
+    }
+
+    if let Some(max) = policy.max_deletions {
+        match dry_run {
+            None => {
+                return Verdict::Block(format!(
+                    "Policy allows at most {} deletions; dry-run first to count them (Ctrl+n)",
+                    max
+                ))
+            }
+            Some(record) if record.deletions > max => {
+                return Verdict::Block(format!(
+                    "Dry run predicted {} deletions, policy allows at most {}",
+                    record.deletions, max
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    Verdict::Confirm
+}
+
+/// Summary lines describing the risk of running `command`
+pub fn summarize(
+    destination: &str,
+    command: &[String],
+    policy: &SafetyPolicy,
+    last_dry_run: Option<&DryRunRecord>,
+) -> Vec<String> {
+    let dry_run = match last_dry_run {
+        Some(record) if record.command == command => {
+            format!("yes, predicted {} deletions", record.deletions)
+        }
+        Some(record) => format!(
+            "only for a different command (predicted {} deletions)",
+            record.deletions
+        ),
+        None => "no".to_string(),
+    };
+
+    let mut rules = policy.rules();
+    if rules.is_empty() {
+        rules.push("none".to_string());
+    }
+
+    let options = options(command, false);
+    let mut lines = Vec::new();
+    if options
+        .iter()
+        .any(|opt| opt.starts_with("--delete") || opt == "--del")
+    {
+        lines.push(
+            "--delete removes files on the destination that are not in the source.".to_string(),
+        );
+    }
+    if options.iter().any(|opt| opt == "--remove-source-files") {
+        lines.push("--remove-source-files deletes source files once they are sent.".to_string());
+    }
+    lines.extend([
+        String::new(),
+        format!("Destination:  {}", destination),
+        format!("Dry run done: {}", dry_run),
+        format!("Policy:       {}", rules.join(", ")),
+    ]);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> Vec<String> {
+        vec!["rsync".to_string(), "--delete".to_string()]
+    }
+
+    fn record(deletions: usize) -> DryRunRecord {
+        DryRunRecord {
+            command: command(),
+            deletions,
+        }
+    }
+
+    #[test]
+    fn test_non_destructive_proceeds() {
+        let policy = SafetyPolicy {
+            require_dry_run: true,
+            max_deletions: Some(0),
+        };
+        let plain = vec!["rsync".to_string(), "-a".to_string()];
+        assert_eq!(check(&plain, &policy, None), Verdict::Proceed);
+
+        let mut dry = command();
+        dry.push("-n".to_string());
+        assert_eq!(check(&dry, &policy, None), Verdict::Proceed);
+    }
+
+    #[test]
+    fn test_delete_requires_confirmation() {
+        let policy = SafetyPolicy::default();
+        assert_eq!(check(&command(), &policy, None), Verdict::Confirm);
+    }
+
+    #[test]
+    fn test_require_dry_run_policy() {
+        let policy = SafetyPolicy {
+            require_dry_run: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            check(&command(), &policy, None),
+            Verdict::Block(_)
+        ));
+
+        // A dry run of a different command does not count
+        let other = DryRunRecord {
+            command: vec!["rsync".to_string()],
+            deletions: 0,
+        };
+        assert!(matches!(
+            check(&command(), &policy, Some(&other)),
+            Verdict::Block(_)
+        ));
+
+        assert_eq!(
+            check(&command(), &policy, Some(&record(3))),
+            Verdict::Confirm
+        );
+    }
+
+    #[test]
+    fn test_max_deletions_policy() {
+        let policy = SafetyPolicy {
+            max_deletions: Some(10),
+            ..Default::default()
+        };
+        assert!(matches!(
+            check(&command(), &policy, None),
+            Verdict::Block(_)
+        ));
+        assert!(matches!(
+            check(&command(), &policy, Some(&record(11))),
+            Verdict::Block(_)
+        ));
+        assert_eq!(
+            check(&command(), &policy, Some(&record(10))),
+            Verdict::Confirm
+        );
+    }
+
+    #[test]
+    fn test_is_destructive() {
+        assert!(is_destructive(&command()));
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(is_destructive(&args(&["rsync", "-av", "--delete-after"])));
+        assert!(!is_destructive(&args(&["rsync", "-avn", "--delete"])));
+        assert!(!is_destructive(&args(&["rsync", "--dry-run", "--delete"])));
+        assert!(!is_destructive(&args(&["rsync", "-av", "/src", "/dest"])));
+    }
+
+    #[test]
+    fn test_filter_values_are_not_dry_run_flags() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let command = args(&[
+            "rsync",
+            "--delete",
+            "--filter",
+            "- node_modules/",
+            "src",
+            "dst",
+        ]);
+        assert!(!is_dry_run(&command));
+        assert!(is_destructive(&command));
+        assert_eq!(
+            check(&command, &SafetyPolicy::default(), None),
+            Verdict::Confirm
+        );
+
+        assert!(!is_dry_run(&args(&[
+            "rsync",
+            "-f",
+            "- node_modules/",
+            "--delete",
+            "a",
+            "b"
+        ])));
+        assert!(!is_dry_run(&args(&[
+            "rsync", "-e", "ssh -n", "--delete", "a", "b"
+        ])));
+        assert!(!is_dry_run(&args(&[
+            "rsync",
+            "--exclude",
+            "-n",
+            "--delete",
+            "a",
+            "b"
+        ])));
+        // Options after the first path are not trusted to make a dry run
+        assert!(!is_dry_run(&args(&["rsync", "--delete", "a", "-n", "b"])));
+        assert!(is_dry_run(&args(&[
+            "rsync", "-f", "- x", "-avn", "--delete", "a", "b"
+        ])));
+        assert!(is_dry_run(&args(&[
+            "rsync",
+            "--filter=- x",
+            "--dry-run",
+            "a",
+            "b"
+        ])));
+    }
+
+    #[test]
+    fn test_del_and_remove_source_files_are_destructive() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(is_destructive(&args(&["rsync", "-a", "--del", "a/", "b/"])));
+        assert!(is_destructive(&args(&[
+            "rsync",
+            "-a",
+            "--remove-source-files",
+            "a/",
+            "b/"
+        ])));
+        assert!(!is_destructive(&args(&[
+            "rsync", "-an", "--del", "a/", "b/"
+        ])));
+        // Extra sources come before options passed through to rsync
+        assert!(is_destructive(&args(&[
+            "rsync", "-a", "c/", "--del", "a/", "b/"
+        ])));
+        assert!(!is_destructive(&args(&[
+            "rsync",
+            "-a",
+            "--delay-updates",
+            "a/",
+            "b/"
+        ])));
+    }
+
+    #[test]
+    fn test_summarize() {
+        let policy = SafetyPolicy {
+            require_dry_run: true,
+            max_deletions: Some(5),
+        };
+        let lines = summarize("nas:/backup", &command(), &policy, Some(&record(2)));
+        assert!(lines.contains(&"Destination:  nas:/backup".to_string()));
+        assert!(lines.contains(&"Dry run done: yes, predicted 2 deletions".to_string()));
+        assert!(lines.contains(&"Policy:       require dry run, max 5 deletions".to_string()));
+
+        let lines = summarize("nas:/backup", &command(), &SafetyPolicy::default(), None);
+        assert!(lines.contains(&"Dry run done: no".to_string()));
+        assert!(lines.contains(&"Policy:       none".to_string()));
+    }
+}
@@ -0,0 +1,320 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Maximum nesting of Include directives, as in OpenSSH
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A `Host` block: its patterns and the settings under it
+#[derive(Debug, Clone, PartialEq)]
+struct Block {
+    /// Host patterns; `!pattern` excludes. Empty for blocks that never match.
+    patterns: Vec<String>,
+    /// Keyword (lowercase) and value, in file order
+    settings: Vec<(String, String)>,
+}
+
+impl Block {
+    fn matches(&self, host: &str) -> bool {
+        let mut matched = false;
+        for pattern in &self.patterns {
+            match pattern.strip_prefix('!') {
+                Some(negated) if wildcard_match(negated, host) => return false,
+                Some(_) => {}
+                None => matched |= wildcard_match(pattern, host),
+            }
+        }
+        matched
+    }
+}
+
+/// ssh host pattern: `*` matches any run of characters and `?` one
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[char], text: &[char]) -> bool {
+        match pattern.first() {
+            None => text.is_empty(),
+            Some('*') => (0..=text.len()).any(|skip| matches(&pattern[1..], &text[skip..])),
+            Some('?') => !text.is_empty() && matches(&pattern[1..], &text[1..]),
+            Some(c) => {
+                text.first().is_some_and(|t| t.eq_ignore_ascii_case(c))
+                    && matches(&pattern[1..], &text[1..])
+            }
+        }
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    matches(&pattern, &text)
+}
+
+/// Connection settings ssh would use for a host alias
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostInfo {
+    pub hostname: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+}
+
+/// Parsed `~/.ssh/config`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SshConfig {
+    blocks: Vec<Block>,
+}
+
+impl SshConfig {
+    /// Read `~/.ssh/config`; a missing or unreadable file gives no hosts
+    pub fn load() -> Self {
+        let Ok(home) = std::env::var("HOME") else {
+            return Self::default();
+        };
+        let dir = Path::new(&home).join(".ssh");
+        match fs::read_to_string(dir.join("config")) {
+            Ok(text) => Self::parse(&text, &dir),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Parse config text; relative Include paths are resolved against `dir`
+    pub fn parse(text: &str, dir: &Path) -> Self {
+        let mut config = Self {
+            // Settings before the first Host line apply to every host
+            blocks: vec![Block {
+                patterns: vec!["*".to_string()],
+                settings: Vec::new(),
+            }],
+        };
+        config.read(text, dir, 0);
+        config
+    }
+
+    fn read(&mut self, text: &str, dir: &Path, depth: usize) {
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // "Keyword value" or "Keyword=value"
+            let split = line
+                .find(|c: char| c.is_whitespace() || c == '=')
+                .unwrap_or(line.len());
+            let keyword = line[..split].to_ascii_lowercase();
+            let rest = line[split..].trim_start();
+            let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+            let args = split_args(rest);
+
+            match keyword.as_str() {
+                "host" => self.blocks.push(Block {
+                    patterns: args,
+                    settings: Vec::new(),
+                }),
+                // Match conditions aren't evaluated, so only "Match all" applies
+                "match" => {
+                    let all = args.len() == 1 && args[0].eq_ignore_ascii_case("all");
+                    self.blocks.push(Block {
+                        patterns: if all {
+                            vec!["*".to_string()]
+                        } else {
+                            Vec::new()
+                        },
+                        settings: Vec::new(),
+                    });
+                }
+                // Included lines continue the current block, as in ssh
+                "include" if depth < MAX_INCLUDE_DEPTH => {
+                    for pattern in &args {
+                        for file in include_files(pattern, dir) {
+                            if let Ok(text) = fs::read_to_string(&file) {
+                                self.read(&text, dir, depth + 1);
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    if let Some(block) = self.blocks.last_mut() {
+                        block.settings.push((keyword, args.join(" ")));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Host aliases that can be typed as-is: Host patterns without
+    /// wildcards or negation, in file order
+    pub fn aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for pattern in self.blocks.iter().flat_map(|b| &b.patterns) {
+            if pattern.contains(['*', '?', '!']) || aliases.contains(pattern) {
+                continue;
+            }
+            aliases.push(pattern.clone());
+        }
+        aliases
+    }
+
+    /// Settings for `host`: the first value found for each keyword wins
+    pub fn resolve(&self, host: &str) -> HostInfo {
+        let value = |keyword: &str| {
+            self.blocks
+                .iter()
+                .filter(|block| block.matches(host))
+                .flat_map(|block| &block.settings)
+                .find(|(key, _)| key == keyword)
+                .map(|(_, value)| value.clone())
+        };
+        HostInfo {
+            hostname: value("hostname")
+                .map(|name| name.replace("%h", host))
+                .unwrap_or_else(|| host.to_string()),
+            user: value("user"),
+            port: value("port").and_then(|port| port.parse().ok()),
+            identity_file: value("identityfile"),
+            proxy_jump: value("proxyjump"),
+        }
+    }
+}
+
+/// Arguments separated by whitespace, with double quotes grouping words
+fn split_args(text: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !arg.is_empty() {
+                    args.push(std::mem::take(&mut arg));
+                }
+            }
+            c => arg.push(c),
+        }
+    }
+    if !arg.is_empty() {
+        args.push(arg);
+    }
+    args
+}
+
+/// Files named by an Include argument, which may use `~` and wildcards in
+/// its last component
+fn include_files(pattern: &str, dir: &Path) -> Vec<PathBuf> {
+    let path = PathBuf::from(crate::path::expand_tilde(pattern));
+    let path = if path.is_absolute() {
+        path
+    } else {
+        dir.join(path)
+    };
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let parent = path.parent().unwrap_or(dir);
+    let Ok(entries) = fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| wildcard_match(&name, &e.file_name().to_string_lossy()))
+        .map(|e| e.path())
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+# Defaults for the office
+User alice
+
+Host nas backup
+    HostName nas.home.lan
+    Port 2222
+
+Host web-* !web-test
+    HostName %h.example.com
+    ProxyJump bastion
+
+Host bastion
+    HostName=203.0.113.7
+    User admin
+    IdentityFile \"~/.ssh/bastion key\"
+
+Match host nas exec \"true\"
+    User matched
+
+Host *
+    Port 22
+    User nobody
+";
+
+    fn config() -> SshConfig {
+        SshConfig::parse(CONFIG, Path::new("/nonexistent"))
+    }
+
+    #[test]
+    fn test_aliases() {
+        assert_eq!(config().aliases(), ["nas", "backup", "bastion"]);
+    }
+
+    #[test]
+    fn test_resolve_first_value_wins() {
+        let nas = config().resolve("nas");
+        assert_eq!(nas.hostname, "nas.home.lan");
+        assert_eq!(nas.port, Some(2222));
+        // Global settings come before any Host block
+        assert_eq!(nas.user.as_deref(), Some("alice"));
+
+        let bastion = config().resolve("bastion");
+        assert_eq!(bastion.hostname, "203.0.113.7");
+        assert_eq!(bastion.port, Some(22));
+        assert_eq!(bastion.identity_file.as_deref(), Some("~/.ssh/bastion key"));
+    }
+
+    #[test]
+    fn test_resolve_wildcards_and_negation() {
+        let web = config().resolve("web-1");
+        assert_eq!(web.hostname, "web-1.example.com");
+        assert_eq!(web.proxy_jump.as_deref(), Some("bastion"));
+
+        let test = config().resolve("web-test");
+        assert_eq!(test.hostname, "web-test");
+        assert_eq!(test.proxy_jump, None);
+
+        assert_eq!(config().resolve("unknown").hostname, "unknown");
+    }
+
+    #[test]
+    fn test_include() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("config.d")).unwrap();
+        fs::write(
+            dir.join("config.d/10-work"),
+            "Host work\n  HostName work.example.com\n",
+        )
+        .unwrap();
+        fs::write(dir.join("config.d/20-lab"), "Host lab\n  Port 2200\n").unwrap();
+        fs::write(dir.join("extra"), "Host extra\n").unwrap();
+
+        let config = SshConfig::parse(
+            "Include config.d/*\nInclude extra missing\nHost home\n",
+            dir,
+        );
+        assert_eq!(config.aliases(), ["work", "lab", "extra", "home"]);
+        assert_eq!(config.resolve("lab").port, Some(2200));
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("web-*", "web-01"));
+        assert!(wildcard_match("db?", "DB1"));
+        assert!(!wildcard_match("db?", "db12"));
+    }
+}
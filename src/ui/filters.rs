@@ -0,0 +1,191 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use super::layout::centered_rect;
+use crate::app::FilterEditor;
+use crate::rsync::filter::{FilterRule, RuleKind};
+use crate::rsync::matcher::{FilterReport, MAX_ENTRIES};
+
+/// Render the filter rule editor popup over the panels
+pub fn render(frame: &mut Frame, editor: &FilterEditor, rules: &[FilterRule]) {
+    // The tester gets most of the screen
+    let area = if editor.test.is_some() {
+        let size = frame.size();
+        centered_rect(
+            size.width.saturating_sub(4),
+            size.height.saturating_sub(2),
+            size,
+        )
+    } else {
+        let height = rules.len().max(1) as u16 + 6;
+        centered_rect(90, height, frame.size())
+    };
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title("Filter Rules (first match wins)")
+        .borders(Borders::ALL)
+        .border_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),    // Rules
+            Constraint::Length(1), // Rule being typed
+            Constraint::Length(1), // Keys
+        ])
+        .split(inner);
+
+    match &editor.test {
+        Some(test) => {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .split(chunks[0]);
+            render_rules(frame, columns[0], editor, rules);
+            render_test(frame, columns[1], test, editor.test_scroll);
+        }
+        None => render_rules(frame, chunks[0], editor, rules),
+    }
+    render_edit(frame, chunks[1], editor);
+
+    let keys = match (editor.edit.is_some(), editor.test.is_some()) {
+        (true, _) => "[Tab] Kind  [Enter] Save  [Esc] Cancel",
+        (false, true) => {
+            "[j/k] Move  [J/K] Reorder  [a] Add  [e] Edit  [d] Delete  [g] Import ignores  [PgUp/PgDn] Scroll  [r] Rescan  [t] Hide test  [Esc] Close"
+        }
+        (false, false) => {
+            "[j/k] Move  [J/K] Reorder  [a] Add  [e/Enter] Edit  [d] Delete  [g] Import ignores  [t] Test  [Esc] Close"
+        }
+    };
+    frame.render_widget(
+        Paragraph::new(keys).style(Style::default().fg(Color::DarkGray)),
+        chunks[2],
+    );
+}
+
+fn render_rules(frame: &mut Frame, area: Rect, editor: &FilterEditor, rules: &[FilterRule]) {
+    if rules.is_empty() {
+        let empty = Paragraph::new("No filter rules. Press [a] to add one.")
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(empty, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:>3}  ", i + 1),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                kind_span(rule.kind),
+                Span::raw(rule.pattern.clone()),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(editor.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+/// Every file in the source with the rule that decided it
+fn render_test(frame: &mut Frame, area: Rect, test: &Result<FilterReport, String>, scroll: usize) {
+    let report = match test {
+        Ok(report) => report,
+        Err(message) => {
+            let error = Paragraph::new(message.as_str())
+                .style(Style::default().fg(Color::Red))
+                .block(Block::default().title("Test").borders(Borders::LEFT));
+            frame.render_widget(error, area);
+            return;
+        }
+    };
+
+    let included = report.entries.iter().filter(|e| e.included).count();
+    let mut title = format!(
+        "Test: {} included, {} excluded",
+        included,
+        report.entries.len() - included
+    );
+    if report.truncated {
+        title.push_str(&format!(" (first {} entries)", MAX_ENTRIES));
+    }
+
+    // Rules left out of the test stay on top, since the result may be wrong
+    let mut lines: Vec<Line> = report
+        .untestable
+        .iter()
+        .map(|rule| {
+            Line::from(Span::styled(
+                format!("! Not tested: {}", rule),
+                Style::default().fg(Color::Yellow),
+            ))
+        })
+        .collect();
+    let rows = (area.height as usize).saturating_sub(lines.len());
+    lines.extend(report.entries.iter().skip(scroll).take(rows).map(|entry| {
+        let (mark, color) = if entry.included {
+            ("+ ", Color::Green)
+        } else {
+            ("- ", Color::Red)
+        };
+        let name = if entry.is_dir {
+            format!("{}/", entry.path)
+        } else {
+            entry.path.clone()
+        };
+        let rule = entry.rule.as_deref().unwrap_or("(no rule)");
+        Line::from(vec![
+            Span::styled(mark, Style::default().fg(color)),
+            Span::styled(format!("{:<40} ", name), Style::default().fg(color)),
+            Span::styled(rule.to_string(), Style::default().fg(Color::DarkGray)),
+        ])
+    }));
+
+    let test = Paragraph::new(lines).block(Block::default().title(title).borders(Borders::LEFT));
+    frame.render_widget(test, area);
+}
+
+fn render_edit(frame: &mut Frame, area: Rect, editor: &FilterEditor) {
+    let Some(edit) = &editor.edit else {
+        if let Some(message) = &editor.message {
+            let message =
+                Paragraph::new(message.as_str()).style(Style::default().fg(Color::Yellow));
+            frame.render_widget(message, area);
+        }
+        return;
+    };
+    let label = if edit.index.is_some() { "Edit" } else { "Add" };
+    let line = Line::from(vec![
+        Span::styled(format!("{}: ", label), Style::default().fg(Color::Yellow)),
+        kind_span(edit.kind),
+        Span::raw(edit.text.clone()),
+        Span::styled("█", Style::default().fg(Color::Yellow)),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+/// Rule kind padded to a column, coloured by whether it lets files through
+fn kind_span(kind: RuleKind) -> Span<'static> {
+    let color = match kind {
+        RuleKind::Include | RuleKind::IncludeFrom => Color::Green,
+        RuleKind::Exclude | RuleKind::ExcludeFrom => Color::Red,
+        RuleKind::Filter | RuleKind::DirMerge => Color::Cyan,
+    };
+    Span::styled(format!("{:<14}", kind.label()), Style::default().fg(color))
+}
@@ -0,0 +1,167 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use super::layout::{centered_rect, severity_color};
+use crate::app::{HistoryView, StatusFilter};
+use crate::history::{format_duration, HistoryEntry};
+use crate::rsync::command::format_command;
+use crate::rsync::exit::Severity;
+use crate::rsync::plan::format_bytes;
+use crate::timestamp;
+
+/// Render the run history popup over the panels
+pub fn render(frame: &mut Frame, view: &HistoryView) {
+    let height = view.visible().len().clamp(1, 15) as u16 + 9;
+    let area = centered_rect(100, height, frame.size());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(title(view))
+        .borders(Borders::ALL)
+        .border_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(2),    // Table
+            Constraint::Length(4), // Selected run
+            Constraint::Length(1), // Search / keys
+        ])
+        .split(inner);
+
+    render_table(frame, chunks[0], view);
+    render_details(frame, chunks[1], view.selected());
+    render_prompt(frame, chunks[2], view);
+}
+
+/// "History", followed by the filters in use
+fn title(view: &HistoryView) -> String {
+    let mut filters = Vec::new();
+    if let Some(profile) = &view.profile {
+        filters.push(format!("profile {}", profile));
+    }
+    if view.status != StatusFilter::All {
+        filters.push(view.status.label().to_string());
+    }
+    if !view.search.is_empty() {
+        filters.push(format!("\"{}\"", view.search));
+    }
+    if filters.is_empty() {
+        "History".to_string()
+    } else {
+        format!("History ({})", filters.join(", "))
+    }
+}
+
+fn render_table(frame: &mut Frame, area: Rect, view: &HistoryView) {
+    let visible = view.visible();
+    if visible.is_empty() {
+        let text = if view.entries.is_empty() {
+            "No runs recorded yet."
+        } else {
+            "No runs match the filters."
+        };
+        let empty = Paragraph::new(text).style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(empty, area);
+        return;
+    }
+
+    let header = Row::new(["Started", "Profile", "Status", "Duration", "Transferred"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows: Vec<Row> = visible
+        .iter()
+        .map(|entry| {
+            Row::new(vec![
+                Cell::from(timestamp::format(entry.started_at)),
+                Cell::from(entry.profile.clone()),
+                status_cell(entry),
+                Cell::from(format_duration(entry.duration_ms)),
+                Cell::from(entry.bytes.map(format_bytes).unwrap_or_default()),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(17),
+            Constraint::Length(16),
+            Constraint::Min(16),
+            Constraint::Length(9),
+            Constraint::Length(12),
+        ],
+    )
+    .header(header)
+    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = TableState::default().with_selected(Some(view.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+/// Exit status coloured as in the Progress panel, marking dry runs
+fn status_cell(entry: &HistoryEntry) -> Cell<'static> {
+    let exit = entry.exit();
+    let severity = match exit.severity(false) {
+        _ if entry.success => Severity::Ok,
+        severity => severity,
+    };
+    let mut text = exit.status();
+    if entry.dry_run {
+        text.push_str(" (dry)");
+    }
+    Cell::from(text).style(Style::default().fg(severity_color(severity)))
+}
+
+/// Full command, stats and log file of the selected run
+fn render_details(frame: &mut Frame, area: Rect, entry: Option<&HistoryEntry>) {
+    let label = Style::default().fg(Color::Cyan);
+    let lines = match entry {
+        None => Vec::new(),
+        Some(entry) => {
+            let stats = match &entry.stats {
+                Some(stats) => stats.summary(),
+                None => "no --stats summary".to_string(),
+            };
+            let log = match &entry.log_file {
+                Some(path) => path.display().to_string(),
+                None => "none".to_string(),
+            };
+            vec![
+                Line::from(vec![
+                    Span::styled("Command ", label),
+                    Span::raw(format_command(&entry.command)),
+                ]),
+                Line::from(vec![Span::styled("Stats   ", label), Span::raw(stats)]),
+                Line::from(vec![Span::styled("Log     ", label), Span::raw(log)]),
+            ]
+        }
+    };
+    let details = Paragraph::new(lines).block(Block::default().borders(Borders::TOP));
+    frame.render_widget(details, area);
+}
+
+fn render_prompt(frame: &mut Frame, area: Rect, view: &HistoryView) {
+    let line = if view.searching {
+        Line::from(vec![
+            Span::styled("Search: ", Style::default().fg(Color::Yellow)),
+            Span::raw(view.search.clone()),
+            Span::styled("█", Style::default().fg(Color::Yellow)),
+        ])
+    } else {
+        Line::from(Span::styled(
+            "[Enter/r] Re-run  [p] Profile  [f] Status  [/] Search  [j/k] Move  [Esc] Close",
+            Style::default().fg(Color::DarkGray),
+        ))
+    };
+    frame.render_widget(Paragraph::new(line), area);
+}
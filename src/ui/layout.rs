@@ -2,22 +2,49 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Gauge, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Cell, Clear, Gauge, List, ListItem, Paragraph, Row, Table, Wrap},
     Frame,
 };
 
-use crate::app::{App, Mode, Panel};
+use crate::app::{App, Mode, Panel, TransferState};
 use crate::rsync::command::format_command;
+use crate::rsync::exit::{RsyncExit, Severity};
+use crate::rsync::itemize::{ChangeKind, FileType};
+use crate::rsync::plan::format_bytes;
+use crate::rsync::runner::Stream;
+use crate::rsync::stats::TransferStats;
 
 /// Render the entire UI
 pub fn render(frame: &mut Frame, app: &App) {
+    // The dry-run review replaces the panels until it is closed
+    if let Some(review) = &app.review {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3), // Title
+                Constraint::Min(6),    // Review
+                Constraint::Length(3), // Help bar
+            ])
+            .split(frame.size());
+
+        render_title(frame, chunks[0], app);
+        super::review::render(frame, chunks[1], review);
+        render_help(frame, chunks[2], app);
+        render_profiles(frame, app);
+        render_history(frame, app);
+        render_command_input(frame, app);
+        render_script_path(frame, app);
+        render_confirm(frame, app);
+        return;
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),  // Title
             Constraint::Length(3),  // Source (100% width)
             Constraint::Length(3),  // Destination (100% width)
-            Constraint::Length(5),  // Options
+            Constraint::Length(super::options::height(app)), // Options
             Constraint::Length(6),  // Logs
             Constraint::Min(6),     // Progress
             Constraint::Length(3),  // Help bar
@@ -27,10 +54,135 @@ pub fn render(frame: &mut Frame, app: &App) {
     render_title(frame, chunks[0], app);
     render_source(frame, chunks[1], app);
     render_destination(frame, chunks[2], app);
-    render_options(frame, chunks[3], app);
+    super::options::render(frame, chunks[3], app);
     render_logs(frame, chunks[4], app);
     render_progress(frame, chunks[5], app);
     render_help(frame, chunks[6], app);
+    render_host_picker(frame, chunks[2], app);
+    render_modules(frame, app);
+    render_filters(frame, app);
+    render_profiles(frame, app);
+    render_history(frame, app);
+    render_command_input(frame, app);
+    render_script_path(frame, app);
+    render_confirm(frame, app);
+}
+
+fn render_profiles(frame: &mut Frame, app: &App) {
+    if let Some(manager) = &app.profiles {
+        super::profiles::render(frame, manager, &app.profile_name);
+    }
+}
+
+fn render_history(frame: &mut Frame, app: &App) {
+    if let Some(view) = &app.history {
+        super::history::render(frame, view);
+    }
+}
+
+fn render_modules(frame: &mut Frame, app: &App) {
+    if let Some(browser) = &app.modules {
+        super::modules::render(frame, browser);
+    }
+}
+
+fn render_filters(frame: &mut Frame, app: &App) {
+    if let Some(editor) = &app.filter_editor {
+        super::filters::render(frame, editor, &app.options.filters);
+    }
+}
+
+fn render_command_input(frame: &mut Frame, app: &App) {
+    let Some(line) = &app.command_input else {
+        return;
+    };
+    let lines = vec![
+        Line::from("Paste or type an rsync command; it replaces the source, destination and options."),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("$ ", Style::default().fg(Color::Green)),
+            Span::raw(line.clone()),
+            Span::styled("█", Style::default().fg(Color::Yellow)),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled("[Enter] Load  [Esc] Cancel", Style::default().fg(Color::DarkGray))),
+    ];
+    render_modal(frame, "Load rsync command", lines, Color::Cyan);
+}
+
+fn render_script_path(frame: &mut Frame, app: &App) {
+    let Some(path) = &app.script_path else {
+        return;
+    };
+    let (notice, keys) = if app.script_overwrite {
+        (
+            Span::styled("The file exists. Press Enter again to replace it.", Style::default().fg(Color::Yellow)),
+            "[Enter] Overwrite  [Esc] Cancel",
+        )
+    } else {
+        (Span::raw(""), "[Enter] Write  [Esc] Cancel")
+    };
+    let lines = vec![
+        Line::from("Write the command as an executable shell script to:"),
+        Line::from(""),
+        Line::from(vec![
+            Span::raw(path.clone()),
+            Span::styled("█", Style::default().fg(Color::Yellow)),
+        ]),
+        Line::from(notice),
+        Line::from(Span::styled(keys, Style::default().fg(Color::DarkGray))),
+    ];
+    render_modal(frame, "Export script", lines, Color::Cyan);
+}
+
+/// Area of the given size centered in `area`, clamped to fit
+pub fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Draw a bordered modal over whatever is underneath
+pub fn render_modal(frame: &mut Frame, title: &str, lines: Vec<Line>, color: Color) {
+    let height = lines.len() as u16 + 2;
+    let area = centered_rect(80, height, frame.size());
+    let modal = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .title(title.to_string())
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(modal, area);
+}
+
+fn render_confirm(frame: &mut Frame, app: &App) {
+    let Some(confirm) = &app.confirm else {
+        return;
+    };
+
+    let mut lines: Vec<Line> = confirm.lines.iter().map(|l| Line::from(l.as_str())).collect();
+    lines.push(Line::from(""));
+    match &confirm.blocked {
+        Some(reason) => {
+            lines.push(Line::from(Span::styled(
+                format!("Blocked: {}", reason),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )));
+            lines.push(Line::from("[Esc] Close"));
+        }
+        None => lines.push(Line::from(vec![
+            Span::styled("[y] Run with --delete", Style::default().fg(Color::Red)),
+            Span::raw("  [n/Esc] Cancel"),
+        ])),
+    }
+
+    render_modal(frame, "Confirm destructive sync", lines, Color::Red);
 }
 
 fn render_title(frame: &mut Frame, area: Rect, app: &App) {
@@ -46,11 +198,34 @@ fn render_title(frame: &mut Frame, area: Rect, app: &App) {
     let title = Paragraph::new(Line::from(vec![
         Span::styled("rsync TUI ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         Span::styled(mode_str, Style::default().fg(mode_color).add_modifier(Modifier::BOLD)),
+        Span::styled(format!("  {}", app.profile_name), Style::default().fg(Color::DarkGray)),
+        rsync_span(app),
     ]))
     .block(Block::default().borders(Borders::ALL));
     frame.render_widget(title, area);
 }
 
+/// Installed rsync, in red when it couldn't be run
+fn rsync_span(app: &App) -> Span<'static> {
+    match &app.rsync.error {
+        Some(_) => Span::styled("  rsync not found", Style::default().fg(Color::Red)),
+        None if app.rsync.version.is_none() => Span::raw(""),
+        None => Span::styled(
+            format!("  {}", app.rsync.describe()),
+            Style::default().fg(Color::DarkGray),
+        ),
+    }
+}
+
+/// Panel title, noting a remote directory listing for Tab in progress
+fn path_title(title: &str, panel: Panel, app: &App) -> String {
+    if app.active_panel == panel && app.remote.is_listing() {
+        format!("{} (listing remote directory...)", title)
+    } else {
+        title.to_string()
+    }
+}
+
 fn render_source(frame: &mut Frame, area: Rect, app: &App) {
     let style = panel_style(app.active_panel == Panel::Source);
     let source = Paragraph::new(if app.source.is_empty() {
@@ -65,7 +240,7 @@ fn render_source(frame: &mut Frame, area: Rect, app: &App) {
     })
     .block(
         Block::default()
-            .title("[1] Source")
+            .title(path_title("[1] Source", Panel::Source, app))
             .borders(Borders::ALL)
             .border_style(style),
     );
@@ -86,43 +261,83 @@ fn render_destination(frame: &mut Frame, area: Rect, app: &App) {
     })
     .block(
         Block::default()
-            .title("[2] Destination")
+            .title(path_title("[2] Destination", Panel::Destination, app))
             .borders(Borders::ALL)
             .border_style(style),
     );
     frame.render_widget(dest, area);
 }
 
-fn render_options(frame: &mut Frame, area: Rect, app: &App) {
-    let opts = &app.options;
-    let items = vec![
-        format_option("a", "Archive", opts.archive),
-        format_option("v", "Verbose", opts.verbose),
-        format_option("z", "Compress", opts.compress),
-        format_option("n", "Dry-run", opts.dry_run),
-        format_option("p", "Progress", opts.progress),
-        format_option("d", "Delete", opts.delete),
-        format_option("h", "Human", opts.human_readable),
-        format_option("e", "SSH", opts.use_ssh),
-    ];
+/// Matching ~/.ssh/config aliases, dropped down under the Destination panel
+/// while a `host:` style path is typed
+fn render_host_picker(frame: &mut Frame, destination: Rect, app: &App) {
+    const MAX_ROWS: usize = 8;
+
+    let matches = app.host_matches();
+    let Some((highlighted, info)) = app.highlighted_host() else {
+        return;
+    };
+
+    let selected = app.host_selected.min(matches.len() - 1);
+    let first = selected.saturating_sub(MAX_ROWS - 1);
+    let mut lines: Vec<Line> = matches
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(MAX_ROWS)
+        .map(|(i, alias)| {
+            if i == selected {
+                Line::from(Span::styled(
+                    format!("> {}", alias),
+                    Style::default().fg(Color::Black).bg(Color::Cyan),
+                ))
+            } else {
+                Line::from(format!("  {}", alias))
+            }
+        })
+        .collect();
 
-    let options_text = items.join("  ");
-    let style = panel_style(app.active_panel == Panel::Options);
+    let mut details = vec![format!("HostName {}", info.hostname)];
+    if let Some(user) = &info.user {
+        details.push(format!("User {}", user));
+    }
+    if let Some(port) = info.port {
+        details.push(format!("Port {}", port));
+    }
+    if let Some(jump) = &info.proxy_jump {
+        details.push(format!("ProxyJump {}", jump));
+    }
+    if let Some(identity) = &info.identity_file {
+        details.push(format!("IdentityFile {}", identity));
+    }
+    lines.push(Line::from(Span::styled(
+        details.join("  "),
+        Style::default().fg(Color::Yellow),
+    )));
 
-    let options = Paragraph::new(options_text).block(
+    let screen = frame.size();
+    let top = destination.y + destination.height;
+    let area = Rect {
+        x: destination.x + 2,
+        y: top,
+        width: destination.width.saturating_sub(4).min(70),
+        height: (lines.len() as u16 + 2).min(screen.height.saturating_sub(top)),
+    };
+    let picker = Paragraph::new(lines).block(
         Block::default()
-            .title("[3] Options")
+            .title(format!("Hosts: {} [Tab] complete", highlighted))
             .borders(Borders::ALL)
-            .border_style(style),
+            .border_style(Style::default().fg(Color::Cyan)),
     );
-    frame.render_widget(options, area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(picker, area);
 }
 
 fn render_logs(frame: &mut Frame, area: Rect, app: &App) {
     let style = panel_style(app.active_panel == Panel::Logs);
 
     // Show command preview at top, then logs
-    let cmd = format_command(&app.source, &app.destination, &app.options);
+    let cmd = format_command(&app.command(&app.options));
     let mut lines: Vec<ListItem> = vec![
         ListItem::new(Line::from(vec![
             Span::styled("> ", Style::default().fg(Color::Green)),
@@ -148,21 +363,46 @@ fn render_logs(frame: &mut Frame, area: Rect, app: &App) {
 fn render_progress(frame: &mut Frame, area: Rect, app: &App) {
     let style = panel_style(app.active_panel == Panel::Progress);
 
-    // Split area: top for gauge, bottom for output
+    // How the last run ended, once it is over
+    let exit = match app.transfer_state {
+        TransferState::Finished(code) => Some(RsyncExit::from_code(code)),
+        _ => None,
+    };
+    let diagnosis = exit.map(|exit| {
+        let mut lines = diagnosis_lines(exit, app.run_vanished_ok);
+        if let Some(stats) = &app.stats {
+            lines.extend(stats_lines(stats));
+        }
+        lines
+    });
+
+    // Split area: gauge on top, then the diagnosis and --stats summary, then
+    // the output
     let inner_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3), // Progress bar
-            Constraint::Min(1),    // Output lines
+            Constraint::Length(diagnosis.as_ref().map_or(0, |lines| lines.len() as u16)),
+            Constraint::Min(1), // Output lines
         ])
         .split(area);
 
-    // Progress bar with percentage
-    let label = if app.transfer_info.is_empty() {
+    // Progress bar with percentage, prefixed by the transfer state
+    let mut label = if app.transfer_info.is_empty() {
         format!("{:.0}%", app.progress_percentage)
     } else {
         format!("{:.0}% - {}", app.progress_percentage, app.transfer_info)
     };
+    let (state, state_color) = match (app.transfer_state, exit) {
+        (TransferState::Paused, _) => ("Paused".to_string(), Color::Yellow),
+        (TransferState::Cancelling, _) => ("Cancelling".to_string(), Color::Red),
+        (TransferState::Cancelled, _) => ("Cancelled".to_string(), Color::Red),
+        (_, Some(exit)) => (exit.summary(), severity_color(exit.severity(app.run_vanished_ok))),
+        _ => (String::new(), Color::Cyan),
+    };
+    if !state.is_empty() {
+        label = format!("{} - {}", state, label);
+    }
 
     let gauge = Gauge::default()
         .block(
@@ -171,18 +411,28 @@ fn render_progress(frame: &mut Frame, area: Rect, app: &App) {
                 .borders(Borders::ALL)
                 .border_style(style),
         )
-        .gauge_style(Style::default().fg(Color::Cyan).bg(Color::DarkGray))
+        .gauge_style(Style::default().fg(state_color).bg(Color::DarkGray))
         .percent(app.progress_percentage as u16)
         .label(label);
     frame.render_widget(gauge, inner_chunks[0]);
 
+    if let Some(lines) = diagnosis {
+        let block = Block::default()
+            .borders(Borders::LEFT | Borders::RIGHT)
+            .border_style(style);
+        frame.render_widget(Paragraph::new(lines).block(block), inner_chunks[1]);
+    }
+
     // Rsync output lines
     let output_lines: Vec<ListItem> = app
         .progress_output
         .iter()
         .rev()
         .take(10)
-        .map(|line| ListItem::new(line.as_str()))
+        .map(|line| match line.stream {
+            Stream::Stdout => ListItem::new(line.display()),
+            Stream::Stderr => ListItem::new(line.display()).style(Style::default().fg(Color::Red)),
+        })
         .collect();
 
     let output = List::new(output_lines).block(
@@ -190,14 +440,160 @@ fn render_progress(frame: &mut Frame, area: Rect, app: &App) {
             .borders(Borders::LEFT | Borders::RIGHT | Borders::BOTTOM)
             .border_style(style),
     );
-    frame.render_widget(output, inner_chunks[1]);
+
+    // With --itemize-changes output, show the change list beside the output
+    if app.changes.is_empty() {
+        frame.render_widget(output, inner_chunks[2]);
+    } else {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(inner_chunks[2]);
+        frame.render_widget(output, columns[0]);
+        render_changes(frame, columns[1], app, style);
+    }
+}
+
+/// Colour for an exit status
+pub fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Ok => Color::Green,
+        Severity::Warning => Color::Yellow,
+        Severity::Error => Color::Red,
+    }
+}
+
+/// Status, explanation and next step for a finished run. A clean exit
+/// needs no more than the progress bar says.
+fn diagnosis_lines(exit: RsyncExit, vanished_ok: bool) -> Vec<Line<'static>> {
+    if exit == RsyncExit::Success {
+        return Vec::new();
+    }
+    let color = severity_color(exit.severity(vanished_ok));
+    let mut lines = vec![Line::from(vec![
+        Span::styled(
+            exit.status(),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(format!("  {}", exit.explanation())),
+    ])];
+    if let Some(suggestion) = exit.suggestion().filter(|_| !exit.is_success(vanished_ok)) {
+        lines.push(Line::from(Span::styled(
+            format!("Next: {}", suggestion),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines
+}
+
+/// Summary card from the --stats trailer
+fn stats_lines(stats: &TransferStats) -> Vec<Line<'static>> {
+    let label = Style::default().fg(Color::Cyan);
+    let mut files = format!("{} total", stats.files);
+    if let Some(created) = stats.created {
+        files.push_str(&format!(", {} created", created));
+    }
+    if let Some(deleted) = stats.deleted {
+        files.push_str(&format!(", {} deleted", deleted));
+    }
+    files.push_str(&format!(", {} transferred", stats.transferred));
+    vec![
+        Line::from(vec![Span::styled("Files  ", label), Span::raw(files)]),
+        Line::from(vec![
+            Span::styled("Data   ", label),
+            Span::raw(format!(
+                "{} of {} (literal {}, matched {})",
+                format_bytes(stats.transferred_size),
+                format_bytes(stats.total_size),
+                format_bytes(stats.literal),
+                format_bytes(stats.matched)
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled("Wire   ", label),
+            Span::raw(format!(
+                "sent {}, received {}, speedup {:.2}",
+                format_bytes(stats.bytes_sent),
+                format_bytes(stats.bytes_received),
+                stats.speedup
+            )),
+        ]),
+    ]
+}
+
+fn render_changes(frame: &mut Frame, area: Rect, app: &App, style: Style) {
+    let rows: Vec<Row> = app
+        .changes
+        .iter()
+        .skip(app.changes_scroll)
+        .map(|change| {
+            let (kind, color) = match change.kind {
+                ChangeKind::Created => ("created", Color::Green),
+                ChangeKind::Deleted => ("deleted", Color::Red),
+                ChangeKind::Updated => ("updated", Color::Yellow),
+                ChangeKind::Attributes => ("attrs", Color::Cyan),
+                ChangeKind::Unchanged => ("same", Color::DarkGray),
+            };
+            let file_type = match change.file_type {
+                FileType::File => "file",
+                FileType::Directory => "dir",
+                FileType::Symlink => "link",
+                FileType::Device => "dev",
+                FileType::Special => "special",
+            };
+            Row::new(vec![
+                Cell::from(kind).style(Style::default().fg(color)),
+                Cell::from(file_type),
+                Cell::from(change.attributes.names().join(",")),
+                Cell::from(change.path.as_str()),
+            ])
+        })
+        .collect();
+
+    let title = format!(
+        "Changes ({}/{})",
+        (app.changes_scroll + 1).min(app.changes.len()),
+        app.changes.len()
+    );
+    let widths = [
+        Constraint::Length(8),
+        Constraint::Length(7),
+        Constraint::Length(14),
+        Constraint::Min(10),
+    ];
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(vec!["Change", "Type", "Attributes", "Path"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::RIGHT | Borders::BOTTOM)
+                .border_style(style),
+        );
+    frame.render_widget(table, area);
 }
 
 fn render_help(frame: &mut Frame, area: Rect, app: &App) {
     let help_text = match (&app.mode, &app.active_panel) {
-        (Mode::Normal, Panel::Logs) => "[1-5/j/k] Panels  [Enter] Run  [i] Insert  [a/v/z/n/p/d/h/e] Options  [q] Quit",
-        (Mode::Normal, _) => "[1-5/j/k] Panels  [i] Insert  [a/v/z/n/p/d/h/e] Options  [Ctrl+s] Sync  [q] Quit",
-        (Mode::Insert, _) => "[Esc] Normal  [Enter] Next  [Tab] Autocomplete  [Ctrl+s] Sync  [Ctrl+n] Dry-run",
+        _ if app.confirm.is_some() => "[y] Confirm  [n/Esc] Cancel",
+        _ if app.profiles.is_some() => "[j/k] Move  [Enter] Load  [s/r/c/d] Save as/Rename/Duplicate/Delete  [Esc] Close",
+        _ if app.history.as_ref().is_some_and(|view| view.searching) => "[type] Search  [Enter/Esc] Done",
+        _ if app.history.is_some() => "[j/k] Move  [Enter/r] Re-run  [p] Profile  [f] Status  [/] Search  [Esc] Close",
+        _ if app.command_input.is_some() => "[Enter] Load command  [Esc] Cancel",
+        _ if app.script_overwrite => "[Enter] Overwrite script  [Esc] Cancel",
+        _ if app.script_path.is_some() => "[Enter] Write script  [Esc] Cancel",
+        _ if app.modules.is_some() => "[Up/Down] Move  [type] Filter  [Enter] Choose module  [Esc] Close",
+        _ if app.filter_editor.is_some() => "[j/k] Move  [J/K] Reorder  [a] Add  [e] Edit  [d] Delete  [g] Import ignores  [t] Test  [Esc] Close",
+        _ if app.option_edit.is_some() => "[Enter] Apply  [Esc] Cancel  [Left/Right] Choices  [Backspace] Delete",
+        _ if app.review.is_some() => "[j/k] Move  [Enter/l] Open dir  [Backspace/h] Up  [e] Execute plan  [Esc] Close",
+        _ if app.is_running() => "[Ctrl+p] Pause/Resume  [Ctrl+x] Cancel (twice to force)  [1-5/j/k] Panels  [q] Quit",
+        (Mode::Normal, Panel::Logs) => "[1-5/j/k] Panels  [Enter] Run  [i] Insert  [a/v/z/…] Options  [q] Quit",
+        (Mode::Normal, Panel::Options) => "[j/k] Move  [Space/Enter] Toggle/Edit  [Backspace] Reset  [Tab/1-5] Panels  [q] Quit",
+        (Mode::Normal, Panel::Progress) => "[1-5/j/k] Panels  [Up/Down/PgUp/PgDn] Scroll changes  [a/v/z/…] Options  [q] Quit",
+        (Mode::Normal, _) => "[1-5/j/k] Panels  [i] Insert  [a/v/z/…] Options  [Ctrl+s] Sync  [Ctrl+w] Save  [Ctrl+o] Profiles  [Ctrl+r] History  [Ctrl+f] Filters  [Ctrl+v] Load command  [Ctrl+y] Copy command  [Ctrl+e] Export script  [q] Quit",
+        (Mode::Insert, _) => "[Esc] Normal  [Enter] Next  [Tab] Autocomplete  [Up/Down] Hosts  [Ctrl+s] Sync  [Ctrl+n] Dry-run",
     };
     let help = Paragraph::new(help_text)
         .style(Style::default().fg(Color::DarkGray))
@@ -205,15 +601,10 @@ fn render_help(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(help, area);
 }
 
-fn panel_style(active: bool) -> Style {
+pub fn panel_style(active: bool) -> Style {
     if active {
         Style::default().fg(Color::Yellow)
     } else {
         Style::default().fg(Color::White)
     }
 }
-
-fn format_option(key: &str, name: &str, enabled: bool) -> String {
-    let check = if enabled { "x" } else { " " };
-    format!("[{}]{} {}", check, key, name)
-}
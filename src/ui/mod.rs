@@ -1 +1,7 @@
+pub mod filters;
+pub mod history;
 pub mod layout;
+pub mod modules;
+pub mod options;
+pub mod profiles;
+pub mod review;
@@ -0,0 +1,75 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use super::layout::centered_rect;
+use crate::app::ModuleBrowser;
+
+/// Render the rsync daemon module browser over the panels
+pub fn render(frame: &mut Frame, browser: &ModuleBrowser) {
+    let visible = browser.visible();
+    let height = visible.len().clamp(1, 15) as u16 + 5;
+    let area = centered_rect(80, height, frame.size());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!("Modules on {}", browser.base))
+        .borders(Borders::ALL)
+        .border_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),    // Modules
+            Constraint::Length(1), // Filter / keys
+        ])
+        .split(inner);
+
+    render_modules(frame, chunks[0], browser);
+
+    let keys = Line::from(vec![
+        Span::styled("Module: ", Style::default().fg(Color::Yellow)),
+        Span::raw(browser.filter.clone()),
+        Span::styled("█", Style::default().fg(Color::Yellow)),
+        Span::styled(
+            "  [Up/Down] Move  [Enter] Choose  [Esc] Close",
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]);
+    frame.render_widget(Paragraph::new(keys), chunks[1]);
+}
+
+fn render_modules(frame: &mut Frame, area: Rect, browser: &ModuleBrowser) {
+    let message = match &browser.modules {
+        None => Some(("Listing modules...".to_string(), Color::DarkGray)),
+        Some(Err(e)) => Some((format!("Cannot list modules: {}", e), Color::Red)),
+        Some(Ok(_)) if browser.visible().is_empty() => {
+            Some(("No matching modules".to_string(), Color::DarkGray))
+        }
+        Some(Ok(_)) => None,
+    };
+    if let Some((text, color)) = message {
+        frame.render_widget(Paragraph::new(text).style(Style::default().fg(color)), area);
+        return;
+    }
+
+    let rows: Vec<Row> = browser
+        .visible()
+        .iter()
+        .map(|module| Row::new([module.name.clone(), module.comment.clone()]))
+        .collect();
+    let table = Table::new(rows, [Constraint::Length(24), Constraint::Min(10)])
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = TableState::default().with_selected(Some(browser.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
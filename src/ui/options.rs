@@ -0,0 +1,381 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+use super::layout::panel_style;
+use crate::app::{App, OptionRow, Panel};
+use crate::rsync::catalog::{self, OptionSpec, ValueType, CATALOG};
+use crate::rsync::command;
+use crate::safety::PolicySetting;
+
+/// Name of the `vanished_ok` row
+const VANISHED_LABEL: &str = "Vanished OK";
+
+/// Height of the Options panel: the full list while it is focused, a
+/// one-paragraph summary otherwise
+pub fn height(app: &App) -> u16 {
+    if app.active_panel == Panel::Options {
+        12
+    } else {
+        5
+    }
+}
+
+/// Render the Options panel
+pub fn render(frame: &mut Frame, area: Rect, app: &App) {
+    if app.active_panel != Panel::Options {
+        render_summary(frame, area, app);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(area);
+
+    render_list(frame, chunks[0], app);
+    render_description(frame, chunks[1], app);
+}
+
+/// Enabled flags and set values on a single wrapped paragraph
+fn render_summary(frame: &mut Frame, area: Rect, app: &App) {
+    let opts = &app.options;
+    let mut items = Vec::new();
+    for spec in CATALOG {
+        if spec.value == ValueType::Bool {
+            // Options without a key are only listed while they are on
+            let enabled = opts.is_enabled(spec.id);
+            if spec.key.is_some() || enabled {
+                let key = spec.key.map(String::from).unwrap_or_default();
+                items.push(format_option(&key, spec.label, enabled));
+            }
+        } else if let Some(value) = opts.value(spec.id) {
+            items.push(format!(
+                "{}: {}",
+                spec.label,
+                catalog::display_value(spec, &value.to_string())
+            ));
+        }
+    }
+
+    if !opts.filters.is_empty() {
+        items.push(format!("Filters: {} rules (Ctrl+f)", opts.filters.len()));
+    }
+    if !opts.extra_args.is_empty() {
+        items.push(format!("Extra: {}", opts.extra_args.join(" ")));
+    }
+    let rules = app.policy.rules();
+    if !rules.is_empty() {
+        items.push(format!("Policy: {}", rules.join(", ")));
+    }
+    if app.vanished_ok {
+        items.push("Vanished files OK".to_string());
+    }
+
+    let options = Paragraph::new(items.join("  "))
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .title("[3] Options")
+                .borders(Borders::ALL)
+                .border_style(panel_style(false)),
+        );
+    frame.render_widget(options, area);
+}
+
+/// Every option in the catalog, then the safety policy and `vanished_ok`,
+/// with a cursor
+fn render_list(frame: &mut Frame, area: Rect, app: &App) {
+    let items: Vec<ListItem> = OptionRow::all()
+        .map(|row| match row {
+            OptionRow::Rsync(spec) => option_item(app, spec),
+            OptionRow::Policy(setting) => policy_item(app, setting),
+            OptionRow::VanishedOk => vanished_item(app),
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title("[3] Options")
+                .borders(Borders::ALL)
+                .border_style(panel_style(true)),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = ListState::default().with_selected(Some(app.option_cursor));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+/// One row: state, key, label and the argument it adds
+fn option_item(app: &App, spec: &OptionSpec) -> ListItem<'static> {
+    let key = spec
+        .key
+        .map(String::from)
+        .unwrap_or_else(|| " ".to_string());
+
+    let line = match spec.value {
+        ValueType::Bool => {
+            let enabled = app.options.is_enabled(spec.id);
+            let color = if enabled {
+                Color::Green
+            } else {
+                Color::DarkGray
+            };
+            let mut spans = vec![
+                Span::raw(format!("{} {} ", if enabled { "[x]" } else { "[ ]" }, key)),
+                Span::raw(format!("{:<16}", spec.label)),
+                Span::styled(spec.flag, Style::default().fg(color)),
+            ];
+            if app.rsync.unsupported(spec, None).is_some() {
+                spans[1].style = Style::default().fg(Color::DarkGray);
+                spans.push(Span::styled(
+                    " (unsupported)",
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            Line::from(spans)
+        }
+        _ => {
+            let editing = app.option_edit.as_ref().filter(|edit| edit.id == spec.id);
+            let value = match editing {
+                Some(edit) => Span::styled(
+                    format!("{}█", catalog::display_value(spec, &edit.text)),
+                    Style::default().fg(Color::Yellow),
+                ),
+                None => match app.options.value(spec.id) {
+                    Some(value) => Span::styled(
+                        catalog::display_value(spec, &value.to_string()),
+                        Style::default().fg(Color::Green),
+                    ),
+                    None => Span::styled("unset", Style::default().fg(Color::DarkGray)),
+                },
+            };
+            let mut spans = vec![
+                Span::raw("      "),
+                Span::raw(format!("{:<16}", spec.label)),
+                Span::raw(format!("{} ", spec.flag)),
+                value,
+            ];
+            if app
+                .rsync
+                .unsupported(spec, app.options.value(spec.id))
+                .is_some()
+            {
+                spans[1].style = Style::default().fg(Color::DarkGray);
+                spans.push(Span::styled(
+                    " (unsupported)",
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            Line::from(spans)
+        }
+    };
+    ListItem::new(line)
+}
+
+/// Policy setting, marked as such instead of showing a flag
+fn policy_item(app: &App, setting: PolicySetting) -> ListItem<'static> {
+    let policy = Style::default().fg(Color::Magenta);
+    let line = match setting {
+        PolicySetting::RequireDryRun => {
+            let enabled = app.policy.require_dry_run;
+            Line::from(vec![
+                Span::raw(format!("{}   ", if enabled { "[x]" } else { "[ ]" })),
+                Span::raw(format!("{:<16}", setting.label())),
+                Span::styled("policy", policy),
+            ])
+        }
+        PolicySetting::MaxDeletions => {
+            let editing = app
+                .option_edit
+                .as_ref()
+                .filter(|edit| edit.id == setting.id());
+            let value = match (editing, app.policy.max_deletions) {
+                (Some(edit), _) => Span::styled(
+                    format!("{}█", edit.text),
+                    Style::default().fg(Color::Yellow),
+                ),
+                (None, Some(max)) => {
+                    Span::styled(max.to_string(), Style::default().fg(Color::Green))
+                }
+                (None, None) => Span::styled("unset", Style::default().fg(Color::DarkGray)),
+            };
+            Line::from(vec![
+                Span::raw("      "),
+                Span::raw(format!("{:<16}", setting.label())),
+                Span::styled("policy ", policy),
+                value,
+            ])
+        }
+    };
+    ListItem::new(line)
+}
+
+/// Profile setting judging how runs end, marked as such
+fn vanished_item(app: &App) -> ListItem<'static> {
+    ListItem::new(Line::from(vec![
+        Span::raw(format!(
+            "{}   ",
+            if app.vanished_ok { "[x]" } else { "[ ]" }
+        )),
+        Span::raw(format!("{:<16}", VANISHED_LABEL)),
+        Span::styled("profile", Style::default().fg(Color::Blue)),
+    ]))
+}
+
+/// Explanation of the option under the cursor
+fn render_description(frame: &mut Frame, area: Rect, app: &App) {
+    let spec = match app.selected_row() {
+        OptionRow::Rsync(spec) => spec,
+        OptionRow::Policy(setting) => {
+            render_policy_description(frame, area, app, setting);
+            return;
+        }
+        OptionRow::VanishedOk => {
+            render_vanished_description(frame, area);
+            return;
+        }
+    };
+
+    let kind = match spec.value {
+        ValueType::Bool => "on/off".to_string(),
+        ValueType::Int => "number".to_string(),
+        ValueType::Text => "text".to_string(),
+        ValueType::Enum(choices) => format!("one of {}", choices.join(", ")),
+        ValueType::List => "list (comma-separated)".to_string(),
+    };
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            spec.flag,
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(spec.description),
+        Line::from(""),
+        Line::from(format!("Value: {}", kind)),
+    ];
+    if let Some(key) = spec.key {
+        lines.push(Line::from(format!("Key:   {}", key)));
+    }
+    if spec.ssh && spec.value != ValueType::Bool {
+        lines.push(Line::from(format!(
+            "Part of the -e command: {}",
+            command::remote_shell(&app.options)
+        )));
+    }
+    if !spec.conflicts.is_empty() {
+        let flags: Vec<&str> = spec
+            .conflicts
+            .iter()
+            .filter_map(|id| catalog::find(id))
+            .map(|other| other.flag)
+            .collect();
+        lines.push(Line::from(format!("Turns off: {}", flags.join(", "))));
+    }
+    if let Some((major, minor, patch)) = spec.since {
+        lines.push(Line::from(format!(
+            "Needs rsync {}.{}.{} or later",
+            major, minor, patch
+        )));
+    }
+    if let Some(reason) = app.rsync.unsupported(spec, app.options.value(spec.id)) {
+        lines.push(Line::from(Span::styled(
+            format!("Unsupported: {}", reason),
+            Style::default().fg(Color::Red),
+        )));
+    }
+
+    let hint = match (spec.value, app.option_edit.is_some()) {
+        (ValueType::Bool, _) => "[Space] Toggle  [Backspace] Default",
+        (ValueType::Enum(_), true) => "[Left/Right] Choose  [Enter] Apply  [Esc] Cancel",
+        (_, true) => "[Enter] Apply (empty unsets)  [Esc] Cancel",
+        (_, false) => "[Enter] Edit  [Backspace] Unset",
+    };
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        hint,
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let description = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().title(spec.label).borders(Borders::ALL));
+    frame.render_widget(description, area);
+}
+
+/// Explanation of a policy setting, which is checked by the TUI rather
+/// than passed to rsync
+fn render_policy_description(frame: &mut Frame, area: Rect, app: &App, setting: PolicySetting) {
+    let (kind, hint) = match (setting, app.option_edit.is_some()) {
+        (PolicySetting::RequireDryRun, _) => ("on/off", "[Space] Toggle  [Backspace] Off"),
+        (PolicySetting::MaxDeletions, true) => {
+            ("number", "[Enter] Apply (empty unsets)  [Esc] Cancel")
+        }
+        (PolicySetting::MaxDeletions, false) => ("number", "[Enter] Edit  [Backspace] Unset"),
+    };
+    let lines = vec![
+        Line::from(Span::styled(
+            "Safety policy",
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(setting.description()),
+        Line::from(""),
+        Line::from(format!("Value: {}", kind)),
+        Line::from("Checked before runs that delete files; saved with the profile"),
+        Line::from(""),
+        Line::from(Span::styled(hint, Style::default().fg(Color::DarkGray))),
+    ];
+
+    let description = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .title(setting.label())
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(description, area);
+}
+
+/// Explanation of `vanished_ok`, which decides how the exit code of a run
+/// is read rather than being passed to rsync
+fn render_vanished_description(frame: &mut Frame, area: Rect) {
+    let lines = vec![
+        Line::from(Span::styled(
+            "Profile setting",
+            Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(
+            "Count rsync's exit code 24 (source files vanished during the transfer) as success, \
+             which is common when backing up live directories",
+        ),
+        Line::from(""),
+        Line::from("Value: on/off"),
+        Line::from(
+            "Applies to the status, history and --headless exit code; saved with the profile",
+        ),
+        Line::from(""),
+        Line::from(Span::styled(
+            "[Space] Toggle  [Backspace] Off",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+
+    let description = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().title(VANISHED_LABEL).borders(Borders::ALL));
+    frame.render_widget(description, area);
+}
+
+fn format_option(key: &str, name: &str, enabled: bool) -> String {
+    let check = if enabled { "x" } else { " " };
+    format!("[{}]{} {}", check, key, name)
+}
@@ -0,0 +1,132 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use super::layout::{centered_rect, severity_color};
+use crate::app::{ProfileAction, ProfileManager};
+use crate::profile::LastRun;
+use crate::rsync::exit::RsyncExit;
+use crate::timestamp;
+
+/// Render the profile manager popup over the panels
+pub fn render(frame: &mut Frame, manager: &ProfileManager, current: &str) {
+    let height = manager.profiles.len().max(1) as u16 + 6;
+    let area = centered_rect(100, height, frame.size());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title("Profiles")
+        .borders(Borders::ALL)
+        .border_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(2),    // Table
+            Constraint::Length(1), // Prompt / keys
+        ])
+        .split(inner);
+
+    render_table(frame, chunks[0], manager, current);
+    render_prompt(frame, chunks[1], manager);
+}
+
+fn render_table(frame: &mut Frame, area: Rect, manager: &ProfileManager, current: &str) {
+    let header = Row::new(["Name", "Description", "Last run", "Status"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows: Vec<Row> = manager
+        .profiles
+        .iter()
+        .map(|profile| {
+            // Mark the profile currently loaded in the editor
+            let marker = if profile.name == current { "* " } else { "  " };
+            let (when, status) = last_run_cells(profile.last_run.as_ref(), profile.vanished_ok);
+            Row::new(vec![
+                Cell::from(format!("{}{}", marker, profile.name)),
+                Cell::from(profile.description.clone()),
+                Cell::from(when),
+                status,
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(20),
+            Constraint::Min(20),
+            Constraint::Length(17),
+            Constraint::Length(10),
+        ],
+    )
+    .header(header)
+    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    if manager.profiles.is_empty() {
+        let empty = Paragraph::new("No saved profiles. Press [s] to save the current setup.")
+            .style(Style::default().fg(Color::DarkGray));
+        frame.render_widget(empty, area);
+        return;
+    }
+
+    let mut state = TableState::default().with_selected(Some(manager.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+/// Finish time and an exit status coloured by how bad it was
+fn last_run_cells(last_run: Option<&LastRun>, vanished_ok: bool) -> (String, Cell<'static>) {
+    match last_run {
+        None => ("never".to_string(), Cell::from("")),
+        Some(run) => {
+            let text = match run.exit_code {
+                Some(0) => "ok".to_string(),
+                Some(code) => format!("exit {}", code),
+                None => "killed".to_string(),
+            };
+            let severity = RsyncExit::from_code(run.exit_code).severity(vanished_ok);
+            let status = Cell::from(text).style(Style::default().fg(severity_color(severity)));
+            (timestamp::format(run.finished_at), status)
+        }
+    }
+}
+
+fn render_prompt(frame: &mut Frame, area: Rect, manager: &ProfileManager) {
+    let line = if let Some((action, name)) = &manager.input {
+        let label = match action {
+            ProfileAction::SaveAs => "Save current setup as",
+            ProfileAction::Rename => "Rename to",
+            ProfileAction::Duplicate => "Duplicate as",
+        };
+        Line::from(vec![
+            Span::styled(format!("{}: ", label), Style::default().fg(Color::Yellow)),
+            Span::raw(name.clone()),
+            Span::styled("█", Style::default().fg(Color::Yellow)),
+        ])
+    } else if manager.confirm_delete {
+        let name = manager.selected().map(|p| p.name.as_str()).unwrap_or("");
+        Line::from(Span::styled(
+            format!(
+                "Delete profile '{}'? [y] Delete  [any other key] Keep",
+                name
+            ),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ))
+    } else {
+        Line::from(Span::styled(
+            "[Enter] Load  [s] Save as  [r] Rename  [c] Duplicate  [d] Delete  [Esc] Close",
+            Style::default().fg(Color::DarkGray),
+        ))
+    };
+    frame.render_widget(Paragraph::new(line), area);
+}
@@ -0,0 +1,120 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::app::Review;
+use crate::rsync::itemize::ChangeKind;
+use crate::rsync::plan::{format_bytes, PlanNode, PlanTotals};
+
+/// Render the dry-run review screen
+pub fn render(frame: &mut Frame, area: Rect, review: &Review) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4), // Summary
+            Constraint::Min(3),    // Tree
+        ])
+        .split(area);
+
+    render_summary(frame, chunks[0], review);
+    render_tree(frame, chunks[1], review);
+}
+
+fn render_summary(frame: &mut Frame, area: Rect, review: &Review) {
+    let totals = review.plan.totals();
+    let mut summary = vec![Span::styled(
+        "Plan: ",
+        Style::default().add_modifier(Modifier::BOLD),
+    )];
+    summary.extend(totals_spans(&totals));
+
+    let command = review.plan.command.join(" ");
+    let text = vec![
+        Line::from(summary),
+        Line::from(vec![
+            Span::styled("> ", Style::default().fg(Color::Green)),
+            Span::raw(command),
+        ]),
+    ];
+
+    let paragraph = Paragraph::new(text).block(
+        Block::default()
+            .title("Dry-run Review")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn render_tree(frame: &mut Frame, area: Rect, review: &Review) {
+    let node = review.current();
+    let items: Vec<ListItem> = node.children.iter().map(node_item).collect();
+
+    let title = format!("/{}", review.path.join("/"));
+    let list = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = ListState::default().with_selected(Some(review.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+/// One row: change marker, name and either size or directory totals
+fn node_item(node: &PlanNode) -> ListItem<'static> {
+    let (marker, color) = match node.kind {
+        Some(ChangeKind::Created) => ("+", Color::Green),
+        Some(ChangeKind::Deleted) => ("-", Color::Red),
+        Some(ChangeKind::Updated) => ("~", Color::Yellow),
+        Some(ChangeKind::Attributes) => (".", Color::Cyan),
+        Some(ChangeKind::Unchanged) | None => (" ", Color::White),
+    };
+
+    let mut spans = vec![Span::styled(
+        format!("{} ", marker),
+        Style::default().fg(color),
+    )];
+    if node.is_dir {
+        spans.push(Span::styled(
+            format!("{}/  ", node.name),
+            Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+        ));
+        spans.extend(totals_spans(&node.totals));
+    } else {
+        spans.push(Span::styled(
+            format!("{}  ", node.name),
+            Style::default().fg(color),
+        ));
+        if node.kind != Some(ChangeKind::Deleted) {
+            spans.push(Span::styled(
+                format_bytes(node.size),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+    }
+    ListItem::new(Line::from(spans))
+}
+
+fn totals_spans(totals: &PlanTotals) -> Vec<Span<'static>> {
+    vec![
+        Span::styled(
+            format!("+{} ", totals.created),
+            Style::default().fg(Color::Green),
+        ),
+        Span::styled(
+            format!("~{} ", totals.updated),
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::styled(
+            format!("-{} ", totals.deleted),
+            Style::default().fg(Color::Red),
+        ),
+        Span::styled(
+            format!("({})", format_bytes(totals.bytes)),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]
+}